@@ -0,0 +1,53 @@
+// Times writing large narrow string payloads, plain and dense with characters
+// that need escaping, to keep string escaping linear in the payload size.
+//
+//     cargo bench --bench escape
+
+use resw::resource::StringTable;
+use resw::Build;
+use std::io::{self, BufWriter};
+use std::time::{Duration, Instant};
+
+const PAYLOAD_SIZE: usize = 4 << 20;
+const ROUNDS: u32 = 5;
+
+fn payload(escape_every: usize) -> String {
+    (0..PAYLOAD_SIZE)
+        .map(|idx| {
+            if idx % escape_every == escape_every - 1 {
+                '"'
+            } else {
+                (b'a' + (idx % 26) as u8) as char
+            }
+        })
+        .collect()
+}
+
+fn time(text: &str) -> io::Result<Duration> {
+    let mut total = Duration::default();
+    for _ in 0..ROUNDS {
+        let strings = StringTable::from_builder().string(1u16, text);
+        let build = Build::with_one_language().resource(0u16, strings.build());
+        let start = Instant::now();
+        build.write_rc(&mut BufWriter::new(io::sink()))?;
+        total += start.elapsed();
+    }
+    Ok(total / ROUNDS)
+}
+
+fn main() -> io::Result<()> {
+    for &(label, escape_every) in &[
+        ("no escapes", usize::MAX),
+        ("1 in 64 escaped", 64),
+        ("every byte escaped", 1),
+    ] {
+        let text = payload(escape_every);
+        println!(
+            "{:>20}: {:?} per {} byte payload",
+            label,
+            time(&text)?,
+            text.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// Times resource script generation for a build with 10k strings in 14
+// languages, writing straight to a `File` and through a `BufWriter`.
+//
+//     cargo bench --bench generate
+
+use resw::lang;
+use resw::resource::StringTable;
+use resw::Build;
+use resw::Lang;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const STRING_COUNT: u16 = 10_000;
+const LANGUAGE_COUNT: usize = 14;
+const ROUNDS: u32 = 5;
+
+fn make_build() -> Build {
+    let languages: &[Lang] = &lang::PRESET_LANG_FULL_MUI[..LANGUAGE_COUNT];
+    let mut strings = StringTable::from_builder();
+    for id in 0..STRING_COUNT {
+        strings = strings.string(id, format!("String number {} with some text", id));
+    }
+    for (n, &language) in languages.iter().enumerate().skip(1) {
+        for id in (0..STRING_COUNT).filter(|id| *id as usize % LANGUAGE_COUNT == n) {
+            strings = strings.lang_specific_string(language, id, format!("Translated {}", id));
+        }
+    }
+    Build::new(languages).resource(0u16, strings.build())
+}
+
+fn time(path: &Path, buffered: bool) -> io::Result<Duration> {
+    let mut total = Duration::default();
+    for _ in 0..ROUNDS {
+        let build = make_build();
+        let start = Instant::now();
+        let mut file = File::create(path)?;
+        if buffered {
+            let mut w = BufWriter::new(&mut file);
+            build.write_rc(&mut w)?;
+            w.flush()?;
+        } else {
+            build.write_rc(&mut file)?;
+        }
+        total += start.elapsed();
+    }
+    Ok(total / ROUNDS)
+}
+
+fn main() -> io::Result<()> {
+    let path = std::env::temp_dir().join("resw-bench-generate.rc");
+    let unbuffered = time(&path, false)?;
+    let buffered = time(&path, true)?;
+    println!("unbuffered: {:?} per build", unbuffered);
+    println!("buffered:   {:?} per build", buffered);
+    std::fs::remove_file(&path)
+}
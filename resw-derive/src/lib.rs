@@ -0,0 +1,206 @@
+// `#[derive(Resource)]` for resources of a type resw has no builder for,
+// taken from a file or from bytes in the struct, e.g.
+//
+//     #[derive(Clone, Resource)]
+//     #[resource(keyword = "TYPELIB")]
+//     struct TypeLib {
+//         #[resource(file)]
+//         path: PathBuf,
+//     }
+//
+// The type is given by `keyword = "..."` for types the resource compiler
+// knows, or `type_name = "..."` / `type_id = N` for user defined ones. The
+// content comes from the one field marked `#[resource(file)]` (anything
+// `AsRef<Path>`) or `#[resource(data)]` (anything `AsRef<[u8]>`).
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use std::convert::TryFrom;
+use syn::{parse_macro_input, Data, DeriveInput, Error, LitInt, LitStr, Member};
+
+enum ResourceType {
+    Keyword(LitStr),
+    Name(LitStr),
+    Id(u16),
+}
+
+#[derive(PartialEq)]
+enum Source {
+    File,
+    Data,
+}
+
+#[proc_macro_derive(Resource, attributes(resource))]
+pub fn derive_resource(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let mut resource_type = None;
+    for attr in input.attrs.iter() {
+        if !attr.path().is_ident("resource") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            let ty = if meta.path.is_ident("keyword") {
+                let lit: LitStr = meta.value()?.parse()?;
+                // pasted into the script as is, so it has to be a bare word
+                let keyword = lit.value();
+                let valid = keyword.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+                    && keyword
+                        .chars()
+                        .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+                if !valid {
+                    return Err(Error::new(
+                        lit.span(),
+                        "expected a resource compiler keyword, such as \"TYPELIB\"",
+                    ));
+                }
+                ResourceType::Keyword(lit)
+            } else if meta.path.is_ident("type_name") {
+                ResourceType::Name(meta.value()?.parse()?)
+            } else if meta.path.is_ident("type_id") {
+                let lit: LitInt = meta.value()?.parse()?;
+                let id = lit.base10_parse::<u64>()?;
+                ResourceType::Id(
+                    u16::try_from(id)
+                        .map_err(|_| Error::new(lit.span(), "type ids go from 0 to 65535"))?,
+                )
+            } else {
+                return Err(meta.error("expected `keyword`, `type_name` or `type_id`"));
+            };
+            if resource_type.replace(ty).is_some() {
+                return Err(meta.error("the resource type is given more than once"));
+            }
+            Ok(())
+        })?;
+    }
+    let resource_type = resource_type.ok_or_else(|| {
+        Error::new_spanned(
+            &input.ident,
+            "missing #[resource(keyword = \"...\")], #[resource(type_name = \"...\")] \
+             or #[resource(type_id = ...)]",
+        )
+    })?;
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "#[derive(Resource)] only supports structs",
+            ))
+        }
+    };
+    let mut source = None;
+    for (field, member) in fields.iter().zip(fields.members()) {
+        for attr in field.attrs.iter() {
+            if !attr.path().is_ident("resource") {
+                continue;
+            }
+            attr.parse_nested_meta(|meta| {
+                let kind = if meta.path.is_ident("file") {
+                    Source::File
+                } else if meta.path.is_ident("data") {
+                    Source::Data
+                } else {
+                    return Err(meta.error("expected `file` or `data`"));
+                };
+                if source.replace((kind, member.clone())).is_some() {
+                    return Err(meta.error("only one field can hold the content"));
+                }
+                Ok(())
+            })?;
+        }
+    }
+    let (source, member): (Source, Member) = source.ok_or_else(|| {
+        Error::new_spanned(
+            &input.ident,
+            "mark the field with the content #[resource(file)] or #[resource(data)]",
+        )
+    })?;
+
+    let (type_expr, type_methods) = match &resource_type {
+        ResourceType::Keyword(keyword) => (
+            quote!(::resw::derive_support::ResourceType::Keyword(#keyword)),
+            quote! {
+                fn type_keyword(&self) -> ::std::option::Option<&'static str> {
+                    ::std::option::Option::Some(#keyword)
+                }
+            },
+        ),
+        ResourceType::Name(name) => (
+            quote!(::resw::derive_support::ResourceType::UserDefined(
+                ::resw::IdOrName::name_static(#name)
+            )),
+            quote! {
+                fn user_defined_type(&self) -> ::std::option::Option<::resw::IdOrName> {
+                    ::std::option::Option::Some(::resw::IdOrName::name_static(#name))
+                }
+            },
+        ),
+        ResourceType::Id(id) => (
+            quote!(::resw::derive_support::ResourceType::UserDefined(
+                ::resw::IdOrName::from(#id)
+            )),
+            quote! {
+                fn user_defined_type(&self) -> ::std::option::Option<::resw::IdOrName> {
+                    ::std::option::Option::Some(::resw::IdOrName::from(#id))
+                }
+            },
+        ),
+    };
+
+    let write = match source {
+        Source::File => quote! {
+            ::resw::derive_support::write_file_resource(
+                w,
+                l,
+                id_or_name,
+                #type_expr,
+                ::std::convert::AsRef::<::std::path::Path>::as_ref(&self.#member),
+            )
+        },
+        Source::Data => quote! {
+            ::resw::derive_support::write_data_resource(
+                w,
+                l,
+                id_or_name,
+                #type_expr,
+                ::std::convert::AsRef::<[u8]>::as_ref(&self.#member),
+            )
+        },
+    };
+    let referenced_files = if source == Source::File {
+        quote! {
+            fn referenced_files(&self) -> ::std::vec::Vec<&::std::path::Path> {
+                ::std::vec![::std::convert::AsRef::<::std::path::Path>::as_ref(&self.#member)]
+            }
+        }
+    } else {
+        TokenStream2::new()
+    };
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::resw::Resource for #ident #ty_generics #where_clause {
+            fn write_script_segment(
+                &self,
+                w: &mut dyn ::std::io::Write,
+                l: ::resw::Lang,
+                id_or_name: ::resw::IdOrName,
+            ) -> ::std::io::Result<()> {
+                #write
+            }
+
+            #referenced_files
+
+            #type_methods
+        }
+    })
+}
@@ -0,0 +1,34 @@
+use resw::{lang, Build, Resource};
+
+#[derive(Clone, Resource)]
+#[resource(type_id = 300)]
+struct Blob {
+    #[resource(data)]
+    bytes: Vec<u8>,
+}
+
+#[derive(Clone, Resource)]
+#[resource(type_id = 65535)]
+struct LastId {
+    #[resource(data)]
+    bytes: &'static [u8],
+}
+
+#[test]
+fn type_ids_are_written_in_full() {
+    let mut out = Vec::new();
+    Build::new(&[lang::LANG_ENU])
+        .resource(1u16, Blob { bytes: vec![1, 2] })
+        .resource(2u16, LastId { bytes: b"x" })
+        .write_rc(&mut out)
+        .unwrap();
+    let script = String::from_utf8(out).unwrap();
+    assert!(script.contains("\n1 300\n"), "{}", script);
+    assert!(script.contains("\n2 65535\n"), "{}", script);
+}
+
+// Attribute errors point at what is wrong, see the .stderr files.
+#[test]
+fn invalid_attributes() {
+    trybuild::TestCases::new().compile_fail("tests/ui/*.rs");
+}
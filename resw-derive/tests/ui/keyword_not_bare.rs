@@ -0,0 +1,10 @@
+use resw::Resource;
+
+#[derive(Clone, Resource)]
+#[resource(keyword = "TYPE LIB")]
+struct TypeLib {
+    #[resource(file)]
+    path: std::path::PathBuf,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use resw::Resource;
+
+#[derive(Clone, Resource)]
+struct Blob {
+    #[resource(data)]
+    bytes: Vec<u8>,
+}
+
+fn main() {}
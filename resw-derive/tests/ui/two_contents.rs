@@ -0,0 +1,12 @@
+use resw::Resource;
+
+#[derive(Clone, Resource)]
+#[resource(keyword = "TYPELIB")]
+struct TypeLib {
+    #[resource(file)]
+    path: std::path::PathBuf,
+    #[resource(data)]
+    bytes: Vec<u8>,
+}
+
+fn main() {}
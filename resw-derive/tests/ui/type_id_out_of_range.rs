@@ -0,0 +1,10 @@
+use resw::Resource;
+
+#[derive(Clone, Resource)]
+#[resource(type_id = 65536)]
+struct Blob {
+    #[resource(data)]
+    bytes: Vec<u8>,
+}
+
+fn main() {}
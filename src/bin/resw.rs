@@ -0,0 +1,101 @@
+use resw::spec::BuildSpec;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::exit;
+
+const USAGE: &str =
+    "usage: resw <spec-file> [--rc <path>] [--rc2 <path>] [--res <path>] [--depfile <path>] [--header <path>]";
+
+#[derive(Default)]
+struct Args {
+    spec: PathBuf,
+    rc: Option<PathBuf>,
+    rc2: Option<PathBuf>,
+    res: Option<PathBuf>,
+    depfile: Option<PathBuf>,
+    header: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut spec = None;
+    let mut iter = std::env::args_os().skip(1);
+    while let Some(arg) = iter.next() {
+        let slot = match arg.to_str() {
+            Some("--rc") => &mut args.rc,
+            Some("--rc2") => &mut args.rc2,
+            Some("--res") => &mut args.res,
+            Some("--depfile") => &mut args.depfile,
+            Some("--header") => &mut args.header,
+            Some("-h") | Some("--help") => return Err(String::new()),
+            Some(flag) if flag.starts_with("--") => {
+                return Err(format!("unknown option `{}`", flag))
+            }
+            _ => {
+                if spec.is_some() {
+                    return Err("more than one spec file given".to_owned());
+                }
+                spec = Some(PathBuf::from(arg));
+                continue;
+            }
+        };
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("missing value for `{}`", arg.to_string_lossy()))?;
+        *slot = Some(PathBuf::from(value));
+    }
+    args.spec = spec.ok_or_else(|| "no spec file given".to_owned())?;
+    if args.rc.is_none() && args.rc2.is_none() && args.res.is_none() && args.header.is_none() {
+        args.rc = Some(args.spec.with_extension("rc"));
+    }
+    // the depfile lists the inputs of the script compiled to .res
+    if args.depfile.is_some() && args.rc.is_none() && args.res.is_none() {
+        return Err("`--depfile` needs `--rc` or `--res`".to_owned());
+    }
+    Ok(args)
+}
+
+fn run(args: Args) -> io::Result<()> {
+    let spec = BuildSpec::load(&args.spec)?;
+    if let Some(header) = &args.header {
+        spec.generate_c_header(header)?;
+    }
+    let base_dir = args.spec.parent().unwrap_or_else(|| Path::new(""));
+    if let Some(rc2) = &args.rc2 {
+        spec.to_build(base_dir)?.generate_rc_include_file(rc2)?;
+    }
+    if args.rc.is_none() && args.res.is_none() {
+        return Ok(());
+    }
+    let build = spec.to_build(base_dir)?;
+    let rc = match (&args.rc, &args.res) {
+        (Some(rc), _) => rc.clone(),
+        (None, Some(res)) => res.with_extension("rc"),
+        (None, None) => unreachable!(),
+    };
+    match &args.depfile {
+        Some(depfile) => build.generate_rc_file_with_depfile(&rc, depfile)?,
+        None => build.generate_rc_file(&rc)?,
+    }
+    if let Some(res) = &args.res {
+        resw::Build::compile_rc_file_to_res(&rc, res)?;
+    }
+    Ok(())
+}
+
+fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(msg) => {
+            if !msg.is_empty() {
+                eprintln!("resw: {}", msg);
+            }
+            eprintln!("{}", USAGE);
+            exit(2);
+        }
+    };
+    if let Err(e) = run(args) {
+        eprintln!("resw: {}", e);
+        exit(1);
+    }
+}
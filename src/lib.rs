@@ -1,22 +1,515 @@
 #![cfg_attr(feature = "unstable", feature(specialization))]
 #![allow(dead_code)]
+use crate::sys::minwindef::DWORD;
+use crate::sys::minwindef::UINT;
+use crate::sys::minwindef::WORD;
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fmt;
 use std::io;
 use std::path::Path;
-use winapi::shared::minwindef::DWORD;
-use winapi::shared::minwindef::WORD;
+use std::rc::Rc;
+
+// The Windows SDK definitions the crate uses. winapi is empty on other targets,
+// so there they are copied here with their Windows types, which lets the
+// resource description API and script generation build anywhere (docs.rs,
+// cross compiling from other hosts). Only the `runtime` module needs Windows.
+#[cfg(windows)]
+mod sys {
+    pub(crate) use winapi::ctypes;
+    pub(crate) use winapi::shared::{minwindef, ntdef};
+    pub(crate) use winapi::um::{wingdi, winuser};
+}
+
+#[cfg(not(windows))]
+#[allow(dead_code, non_camel_case_types, clippy::upper_case_acronyms)]
+mod sys {
+    pub(crate) mod ctypes {
+        pub type c_char = i8;
+        pub type c_schar = i8;
+        pub type c_uchar = u8;
+        pub type c_short = i16;
+        pub type c_ushort = u16;
+        pub type c_int = i32;
+        pub type c_uint = u32;
+        pub type c_long = i32;
+        pub type c_ulong = u32;
+    }
+
+    pub(crate) mod minwindef {
+        use super::ctypes::*;
+        pub type BYTE = c_uchar;
+        pub type WORD = c_ushort;
+        pub type DWORD = c_ulong;
+        pub type UINT = c_uint;
+        pub type BOOL = c_int;
+        pub const TRUE: BOOL = 1;
+        pub const FALSE: BOOL = 0;
+    }
+
+    pub(crate) mod ntdef {
+        pub type USHORT = super::ctypes::c_ushort;
+        pub const LANG_ARABIC: USHORT = 0x01;
+        pub const LANG_BULGARIAN: USHORT = 0x02;
+        pub const LANG_CHINESE: USHORT = 0x04;
+        pub const LANG_CROATIAN: USHORT = 0x1a;
+        pub const LANG_CZECH: USHORT = 0x05;
+        pub const LANG_DANISH: USHORT = 0x06;
+        pub const LANG_DUTCH: USHORT = 0x13;
+        pub const LANG_ENGLISH: USHORT = 0x09;
+        pub const LANG_ESTONIAN: USHORT = 0x25;
+        pub const LANG_FINNISH: USHORT = 0x0b;
+        pub const LANG_FRENCH: USHORT = 0x0c;
+        pub const LANG_GERMAN: USHORT = 0x07;
+        pub const LANG_GREEK: USHORT = 0x08;
+        pub const LANG_HEBREW: USHORT = 0x0d;
+        pub const LANG_HUNGARIAN: USHORT = 0x0e;
+        pub const LANG_ITALIAN: USHORT = 0x10;
+        pub const LANG_JAPANESE: USHORT = 0x11;
+        pub const LANG_KOREAN: USHORT = 0x12;
+        pub const LANG_LATVIAN: USHORT = 0x26;
+        pub const LANG_LITHUANIAN: USHORT = 0x27;
+        pub const LANG_NORWEGIAN: USHORT = 0x14;
+        pub const LANG_POLISH: USHORT = 0x15;
+        pub const LANG_PORTUGUESE: USHORT = 0x16;
+        pub const LANG_ROMANIAN: USHORT = 0x18;
+        pub const LANG_RUSSIAN: USHORT = 0x19;
+        pub const LANG_SERBIAN: USHORT = 0x1a;
+        pub const LANG_SLOVAK: USHORT = 0x1b;
+        pub const LANG_SLOVENIAN: USHORT = 0x24;
+        pub const LANG_SPANISH: USHORT = 0x0a;
+        pub const LANG_SWEDISH: USHORT = 0x1d;
+        pub const LANG_THAI: USHORT = 0x1e;
+        pub const LANG_TURKISH: USHORT = 0x1f;
+        pub const LANG_UKRAINIAN: USHORT = 0x22;
+        pub const SUBLANG_ARABIC_SAUDI_ARABIA: USHORT = 0x01;
+        pub const SUBLANG_BULGARIAN_BULGARIA: USHORT = 0x01;
+        pub const SUBLANG_CHINESE_TRADITIONAL: USHORT = 0x01;
+        pub const SUBLANG_CHINESE_SIMPLIFIED: USHORT = 0x02;
+        pub const SUBLANG_CZECH_CZECH_REPUBLIC: USHORT = 0x01;
+        pub const SUBLANG_CROATIAN_CROATIA: USHORT = 0x01;
+        pub const SUBLANG_DANISH_DENMARK: USHORT = 0x01;
+        pub const SUBLANG_DUTCH: USHORT = 0x01;
+        pub const SUBLANG_ENGLISH_US: USHORT = 0x01;
+        pub const SUBLANG_ENGLISH_UK: USHORT = 0x02;
+        pub const SUBLANG_ESTONIAN_ESTONIA: USHORT = 0x01;
+        pub const SUBLANG_FINNISH_FINLAND: USHORT = 0x01;
+        pub const SUBLANG_FRENCH: USHORT = 0x01;
+        pub const SUBLANG_FRENCH_CANADIAN: USHORT = 0x03;
+        pub const SUBLANG_GERMAN: USHORT = 0x01;
+        pub const SUBLANG_GREEK_GREECE: USHORT = 0x01;
+        pub const SUBLANG_HEBREW_ISRAEL: USHORT = 0x01;
+        pub const SUBLANG_HUNGARIAN_HUNGARY: USHORT = 0x01;
+        pub const SUBLANG_ITALIAN: USHORT = 0x01;
+        pub const SUBLANG_JAPANESE_JAPAN: USHORT = 0x01;
+        pub const SUBLANG_KOREAN: USHORT = 0x01;
+        pub const SUBLANG_LATVIAN_LATVIA: USHORT = 0x01;
+        pub const SUBLANG_LITHUANIAN: USHORT = 0x01;
+        pub const SUBLANG_NORWEGIAN_BOKMAL: USHORT = 0x01;
+        pub const SUBLANG_POLISH_POLAND: USHORT = 0x01;
+        pub const SUBLANG_PORTUGUESE: USHORT = 0x02;
+        pub const SUBLANG_PORTUGUESE_BRAZILIAN: USHORT = 0x01;
+        pub const SUBLANG_ROMANIAN_ROMANIA: USHORT = 0x01;
+        pub const SUBLANG_RUSSIAN_RUSSIA: USHORT = 0x01;
+        pub const SUBLANG_SERBIAN_SERBIA_LATIN: USHORT = 0x09;
+        pub const SUBLANG_SLOVAK_SLOVAKIA: USHORT = 0x01;
+        pub const SUBLANG_SLOVENIAN_SLOVENIA: USHORT = 0x01;
+        pub const SUBLANG_SPANISH: USHORT = 0x01;
+        pub const SUBLANG_SPANISH_MEXICAN: USHORT = 0x02;
+        pub const SUBLANG_SWEDISH: USHORT = 0x01;
+        pub const SUBLANG_THAI_THAILAND: USHORT = 0x01;
+        pub const SUBLANG_TURKISH_TURKEY: USHORT = 0x01;
+        pub const SUBLANG_UKRAINIAN_UKRAINE: USHORT = 0x01;
+    }
+
+    pub(crate) mod winuser {
+        use super::ctypes::c_int;
+        use super::minwindef::{DWORD, UINT};
+        pub const VK_LBUTTON: c_int = 0x01;
+        pub const VK_RBUTTON: c_int = 0x02;
+        pub const VK_CANCEL: c_int = 0x03;
+        pub const VK_MBUTTON: c_int = 0x04;
+        pub const VK_XBUTTON1: c_int = 0x05;
+        pub const VK_XBUTTON2: c_int = 0x06;
+        pub const VK_BACK: c_int = 0x08;
+        pub const VK_TAB: c_int = 0x09;
+        pub const VK_CLEAR: c_int = 0x0C;
+        pub const VK_RETURN: c_int = 0x0D;
+        pub const VK_SHIFT: c_int = 0x10;
+        pub const VK_CONTROL: c_int = 0x11;
+        pub const VK_MENU: c_int = 0x12;
+        pub const VK_PAUSE: c_int = 0x13;
+        pub const VK_CAPITAL: c_int = 0x14;
+        pub const VK_KANA: c_int = 0x15;
+        pub const VK_HANGEUL: c_int = 0x15;
+        pub const VK_HANGUL: c_int = 0x15;
+        pub const VK_JUNJA: c_int = 0x17;
+        pub const VK_FINAL: c_int = 0x18;
+        pub const VK_HANJA: c_int = 0x19;
+        pub const VK_KANJI: c_int = 0x19;
+        pub const VK_ESCAPE: c_int = 0x1B;
+        pub const VK_CONVERT: c_int = 0x1C;
+        pub const VK_NONCONVERT: c_int = 0x1D;
+        pub const VK_ACCEPT: c_int = 0x1E;
+        pub const VK_MODECHANGE: c_int = 0x1F;
+        pub const VK_SPACE: c_int = 0x20;
+        pub const VK_PRIOR: c_int = 0x21;
+        pub const VK_NEXT: c_int = 0x22;
+        pub const VK_END: c_int = 0x23;
+        pub const VK_HOME: c_int = 0x24;
+        pub const VK_LEFT: c_int = 0x25;
+        pub const VK_UP: c_int = 0x26;
+        pub const VK_RIGHT: c_int = 0x27;
+        pub const VK_DOWN: c_int = 0x28;
+        pub const VK_SELECT: c_int = 0x29;
+        pub const VK_PRINT: c_int = 0x2A;
+        pub const VK_EXECUTE: c_int = 0x2B;
+        pub const VK_SNAPSHOT: c_int = 0x2C;
+        pub const VK_INSERT: c_int = 0x2D;
+        pub const VK_DELETE: c_int = 0x2E;
+        pub const VK_HELP: c_int = 0x2F;
+        pub const VK_LWIN: c_int = 0x5B;
+        pub const VK_RWIN: c_int = 0x5C;
+        pub const VK_APPS: c_int = 0x5D;
+        pub const VK_SLEEP: c_int = 0x5F;
+        pub const VK_NUMPAD0: c_int = 0x60;
+        pub const VK_NUMPAD1: c_int = 0x61;
+        pub const VK_NUMPAD2: c_int = 0x62;
+        pub const VK_NUMPAD3: c_int = 0x63;
+        pub const VK_NUMPAD4: c_int = 0x64;
+        pub const VK_NUMPAD5: c_int = 0x65;
+        pub const VK_NUMPAD6: c_int = 0x66;
+        pub const VK_NUMPAD7: c_int = 0x67;
+        pub const VK_NUMPAD8: c_int = 0x68;
+        pub const VK_NUMPAD9: c_int = 0x69;
+        pub const VK_MULTIPLY: c_int = 0x6A;
+        pub const VK_ADD: c_int = 0x6B;
+        pub const VK_SEPARATOR: c_int = 0x6C;
+        pub const VK_SUBTRACT: c_int = 0x6D;
+        pub const VK_DECIMAL: c_int = 0x6E;
+        pub const VK_DIVIDE: c_int = 0x6F;
+        pub const VK_F1: c_int = 0x70;
+        pub const VK_F2: c_int = 0x71;
+        pub const VK_F3: c_int = 0x72;
+        pub const VK_F4: c_int = 0x73;
+        pub const VK_F5: c_int = 0x74;
+        pub const VK_F6: c_int = 0x75;
+        pub const VK_F7: c_int = 0x76;
+        pub const VK_F8: c_int = 0x77;
+        pub const VK_F9: c_int = 0x78;
+        pub const VK_F10: c_int = 0x79;
+        pub const VK_F11: c_int = 0x7A;
+        pub const VK_F12: c_int = 0x7B;
+        pub const VK_F13: c_int = 0x7C;
+        pub const VK_F14: c_int = 0x7D;
+        pub const VK_F15: c_int = 0x7E;
+        pub const VK_F16: c_int = 0x7F;
+        pub const VK_F17: c_int = 0x80;
+        pub const VK_F18: c_int = 0x81;
+        pub const VK_F19: c_int = 0x82;
+        pub const VK_F20: c_int = 0x83;
+        pub const VK_F21: c_int = 0x84;
+        pub const VK_F22: c_int = 0x85;
+        pub const VK_F23: c_int = 0x86;
+        pub const VK_F24: c_int = 0x87;
+        pub const VK_NUMLOCK: c_int = 0x90;
+        pub const VK_SCROLL: c_int = 0x91;
+        pub const VK_OEM_NEC_EQUAL: c_int = 0x92;
+        pub const VK_OEM_FJ_JISHO: c_int = 0x92;
+        pub const VK_OEM_FJ_MASSHOU: c_int = 0x93;
+        pub const VK_OEM_FJ_TOUROKU: c_int = 0x94;
+        pub const VK_OEM_FJ_LOYA: c_int = 0x95;
+        pub const VK_OEM_FJ_ROYA: c_int = 0x96;
+        pub const VK_LSHIFT: c_int = 0xA0;
+        pub const VK_RSHIFT: c_int = 0xA1;
+        pub const VK_LCONTROL: c_int = 0xA2;
+        pub const VK_RCONTROL: c_int = 0xA3;
+        pub const VK_LMENU: c_int = 0xA4;
+        pub const VK_RMENU: c_int = 0xA5;
+        pub const VK_BROWSER_BACK: c_int = 0xA6;
+        pub const VK_BROWSER_FORWARD: c_int = 0xA7;
+        pub const VK_BROWSER_REFRESH: c_int = 0xA8;
+        pub const VK_BROWSER_STOP: c_int = 0xA9;
+        pub const VK_BROWSER_SEARCH: c_int = 0xAA;
+        pub const VK_BROWSER_FAVORITES: c_int = 0xAB;
+        pub const VK_BROWSER_HOME: c_int = 0xAC;
+        pub const VK_VOLUME_MUTE: c_int = 0xAD;
+        pub const VK_VOLUME_DOWN: c_int = 0xAE;
+        pub const VK_VOLUME_UP: c_int = 0xAF;
+        pub const VK_MEDIA_NEXT_TRACK: c_int = 0xB0;
+        pub const VK_MEDIA_PREV_TRACK: c_int = 0xB1;
+        pub const VK_MEDIA_STOP: c_int = 0xB2;
+        pub const VK_MEDIA_PLAY_PAUSE: c_int = 0xB3;
+        pub const VK_LAUNCH_MAIL: c_int = 0xB4;
+        pub const VK_LAUNCH_MEDIA_SELECT: c_int = 0xB5;
+        pub const VK_LAUNCH_APP1: c_int = 0xB6;
+        pub const VK_LAUNCH_APP2: c_int = 0xB7;
+        pub const VK_OEM_1: c_int = 0xBA;
+        pub const VK_OEM_PLUS: c_int = 0xBB;
+        pub const VK_OEM_COMMA: c_int = 0xBC;
+        pub const VK_OEM_MINUS: c_int = 0xBD;
+        pub const VK_OEM_PERIOD: c_int = 0xBE;
+        pub const VK_OEM_2: c_int = 0xBF;
+        pub const VK_OEM_3: c_int = 0xC0;
+        pub const VK_OEM_4: c_int = 0xDB;
+        pub const VK_OEM_5: c_int = 0xDC;
+        pub const VK_OEM_6: c_int = 0xDD;
+        pub const VK_OEM_7: c_int = 0xDE;
+        pub const VK_OEM_8: c_int = 0xDF;
+        pub const VK_OEM_AX: c_int = 0xE1;
+        pub const VK_OEM_102: c_int = 0xE2;
+        pub const VK_ICO_HELP: c_int = 0xE3;
+        pub const VK_ICO_00: c_int = 0xE4;
+        pub const VK_PROCESSKEY: c_int = 0xE5;
+        pub const VK_ICO_CLEAR: c_int = 0xE6;
+        pub const VK_PACKET: c_int = 0xE7;
+        pub const VK_OEM_RESET: c_int = 0xE9;
+        pub const VK_OEM_JUMP: c_int = 0xEA;
+        pub const VK_OEM_PA1: c_int = 0xEB;
+        pub const VK_OEM_PA2: c_int = 0xEC;
+        pub const VK_OEM_PA3: c_int = 0xED;
+        pub const VK_OEM_WSCTRL: c_int = 0xEE;
+        pub const VK_OEM_CUSEL: c_int = 0xEF;
+        pub const VK_OEM_ATTN: c_int = 0xF0;
+        pub const VK_OEM_FINISH: c_int = 0xF1;
+        pub const VK_OEM_COPY: c_int = 0xF2;
+        pub const VK_OEM_AUTO: c_int = 0xF3;
+        pub const VK_OEM_ENLW: c_int = 0xF4;
+        pub const VK_OEM_BACKTAB: c_int = 0xF5;
+        pub const VK_ATTN: c_int = 0xF6;
+        pub const VK_CRSEL: c_int = 0xF7;
+        pub const VK_EXSEL: c_int = 0xF8;
+        pub const VK_EREOF: c_int = 0xF9;
+        pub const VK_PLAY: c_int = 0xFA;
+        pub const VK_ZOOM: c_int = 0xFB;
+        pub const VK_NONAME: c_int = 0xFC;
+        pub const VK_PA1: c_int = 0xFD;
+        pub const VK_OEM_CLEAR: c_int = 0xFE;
+        pub const WS_OVERLAPPED: DWORD = 0x00000000;
+        pub const WS_POPUP: DWORD = 0x80000000;
+        pub const WS_CHILD: DWORD = 0x40000000;
+        pub const WS_MINIMIZE: DWORD = 0x20000000;
+        pub const WS_VISIBLE: DWORD = 0x10000000;
+        pub const WS_DISABLED: DWORD = 0x08000000;
+        pub const WS_CLIPSIBLINGS: DWORD = 0x04000000;
+        pub const WS_CLIPCHILDREN: DWORD = 0x02000000;
+        pub const WS_MAXIMIZE: DWORD = 0x01000000;
+        pub const WS_CAPTION: DWORD = 0x00C00000;
+        pub const WS_BORDER: DWORD = 0x00800000;
+        pub const WS_DLGFRAME: DWORD = 0x00400000;
+        pub const WS_VSCROLL: DWORD = 0x00200000;
+        pub const WS_HSCROLL: DWORD = 0x00100000;
+        pub const WS_SYSMENU: DWORD = 0x00080000;
+        pub const WS_THICKFRAME: DWORD = 0x00040000;
+        pub const WS_GROUP: DWORD = 0x00020000;
+        pub const WS_TABSTOP: DWORD = 0x00010000;
+        pub const WS_MINIMIZEBOX: DWORD = 0x00020000;
+        pub const WS_MAXIMIZEBOX: DWORD = 0x00010000;
+        pub const WS_EX_DLGMODALFRAME: DWORD = 0x00000001;
+        pub const WS_EX_NOPARENTNOTIFY: DWORD = 0x00000004;
+        pub const WS_EX_TOPMOST: DWORD = 0x00000008;
+        pub const WS_EX_ACCEPTFILES: DWORD = 0x00000010;
+        pub const WS_EX_TRANSPARENT: DWORD = 0x00000020;
+        pub const WS_EX_MDICHILD: DWORD = 0x00000040;
+        pub const WS_EX_TOOLWINDOW: DWORD = 0x00000080;
+        pub const WS_EX_WINDOWEDGE: DWORD = 0x00000100;
+        pub const WS_EX_CLIENTEDGE: DWORD = 0x00000200;
+        pub const WS_EX_CONTEXTHELP: DWORD = 0x00000400;
+        pub const WS_EX_RIGHT: DWORD = 0x00001000;
+        pub const WS_EX_LEFT: DWORD = 0x00000000;
+        pub const WS_EX_RTLREADING: DWORD = 0x00002000;
+        pub const WS_EX_LTRREADING: DWORD = 0x00000000;
+        pub const WS_EX_LEFTSCROLLBAR: DWORD = 0x00004000;
+        pub const WS_EX_RIGHTSCROLLBAR: DWORD = 0x00000000;
+        pub const WS_EX_CONTROLPARENT: DWORD = 0x00010000;
+        pub const WS_EX_STATICEDGE: DWORD = 0x00020000;
+        pub const WS_EX_APPWINDOW: DWORD = 0x00040000;
+        pub const WS_EX_LAYERED: DWORD = 0x00080000;
+        pub const WS_EX_NOINHERITLAYOUT: DWORD = 0x00100000;
+        pub const WS_EX_NOREDIRECTIONBITMAP: DWORD = 0x00200000;
+        pub const WS_EX_LAYOUTRTL: DWORD = 0x00400000;
+        pub const WS_EX_COMPOSITED: DWORD = 0x02000000;
+        pub const WS_EX_NOACTIVATE: DWORD = 0x08000000;
+        pub const MF_SEPARATOR: UINT = 0x00000800;
+        pub const MF_ENABLED: UINT = 0x00000000;
+        pub const MF_UNCHECKED: UINT = 0x00000000;
+        pub const MF_CHECKED: UINT = 0x00000008;
+        pub const MF_STRING: UINT = 0x00000000;
+        pub const MF_BITMAP: UINT = 0x00000004;
+        pub const MF_OWNERDRAW: UINT = 0x00000100;
+        pub const MF_MENUBARBREAK: UINT = 0x00000020;
+        pub const MF_MENUBREAK: UINT = 0x00000040;
+        pub const MF_UNHILITE: UINT = 0x00000000;
+        pub const MF_HILITE: UINT = 0x00000080;
+        pub const MF_DEFAULT: UINT = 0x00001000;
+        pub const MF_RIGHTJUSTIFY: UINT = 0x00004000;
+        pub const MFT_STRING: UINT = MF_STRING;
+        pub const MFT_BITMAP: UINT = MF_BITMAP;
+        pub const MFT_MENUBARBREAK: UINT = MF_MENUBARBREAK;
+        pub const MFT_MENUBREAK: UINT = MF_MENUBREAK;
+        pub const MFT_OWNERDRAW: UINT = MF_OWNERDRAW;
+        pub const MFT_RADIOCHECK: UINT = 0x00000200;
+        pub const MFT_SEPARATOR: UINT = MF_SEPARATOR;
+        pub const MFT_RIGHTORDER: UINT = 0x00002000;
+        pub const MFT_RIGHTJUSTIFY: UINT = MF_RIGHTJUSTIFY;
+        pub const MFS_GRAYED: UINT = 0x00000003;
+        pub const MFS_DISABLED: UINT = MFS_GRAYED;
+        pub const MFS_CHECKED: UINT = MF_CHECKED;
+        pub const MFS_HILITE: UINT = MF_HILITE;
+        pub const MFS_ENABLED: UINT = MF_ENABLED;
+        pub const MFS_UNCHECKED: UINT = MF_UNCHECKED;
+        pub const MFS_UNHILITE: UINT = MF_UNHILITE;
+        pub const MFS_DEFAULT: UINT = MF_DEFAULT;
+        pub const IDOK: c_int = 1;
+        pub const IDCANCEL: c_int = 2;
+        pub const IDABORT: c_int = 3;
+        pub const IDRETRY: c_int = 4;
+        pub const IDIGNORE: c_int = 5;
+        pub const IDYES: c_int = 6;
+        pub const IDNO: c_int = 7;
+        pub const IDCLOSE: c_int = 8;
+        pub const IDHELP: c_int = 9;
+        pub const IDTRYAGAIN: c_int = 10;
+        pub const IDCONTINUE: c_int = 11;
+        pub const IDTIMEOUT: c_int = 32000;
+        pub const SS_LEFT: DWORD = 0x00000000;
+        pub const SS_CENTER: DWORD = 0x00000001;
+        pub const SS_RIGHT: DWORD = 0x00000002;
+        pub const SS_ICON: DWORD = 0x00000003;
+        pub const SS_BLACKRECT: DWORD = 0x00000004;
+        pub const SS_GRAYRECT: DWORD = 0x00000005;
+        pub const SS_WHITERECT: DWORD = 0x00000006;
+        pub const SS_BLACKFRAME: DWORD = 0x00000007;
+        pub const SS_GRAYFRAME: DWORD = 0x00000008;
+        pub const SS_WHITEFRAME: DWORD = 0x00000009;
+        pub const SS_USERITEM: DWORD = 0x0000000A;
+        pub const SS_SIMPLE: DWORD = 0x0000000B;
+        pub const SS_LEFTNOWORDWRAP: DWORD = 0x0000000C;
+        pub const SS_OWNERDRAW: DWORD = 0x0000000D;
+        pub const SS_BITMAP: DWORD = 0x0000000E;
+        pub const SS_ENHMETAFILE: DWORD = 0x0000000F;
+        pub const SS_ETCHEDHORZ: DWORD = 0x00000010;
+        pub const SS_ETCHEDVERT: DWORD = 0x00000011;
+        pub const SS_ETCHEDFRAME: DWORD = 0x00000012;
+        pub const SS_TYPEMASK: DWORD = 0x0000001F;
+        pub const SS_REALSIZECONTROL: DWORD = 0x00000040;
+        pub const SS_NOPREFIX: DWORD = 0x00000080;
+        pub const SS_NOTIFY: DWORD = 0x00000100;
+        pub const SS_CENTERIMAGE: DWORD = 0x00000200;
+        pub const SS_RIGHTJUST: DWORD = 0x00000400;
+        pub const SS_REALSIZEIMAGE: DWORD = 0x00000800;
+        pub const SS_SUNKEN: DWORD = 0x00001000;
+        pub const SS_EDITCONTROL: DWORD = 0x00002000;
+        pub const SS_ENDELLIPSIS: DWORD = 0x00004000;
+        pub const SS_PATHELLIPSIS: DWORD = 0x00008000;
+        pub const SS_WORDELLIPSIS: DWORD = 0x0000C000;
+        pub const SS_ELLIPSISMASK: DWORD = 0x0000C000;
+        pub const ES_LEFT: DWORD = 0x0000;
+        pub const ES_CENTER: DWORD = 0x0001;
+        pub const ES_RIGHT: DWORD = 0x0002;
+        pub const ES_MULTILINE: DWORD = 0x0004;
+        pub const ES_UPPERCASE: DWORD = 0x0008;
+        pub const ES_LOWERCASE: DWORD = 0x0010;
+        pub const ES_PASSWORD: DWORD = 0x0020;
+        pub const ES_AUTOVSCROLL: DWORD = 0x0040;
+        pub const ES_AUTOHSCROLL: DWORD = 0x0080;
+        pub const ES_NOHIDESEL: DWORD = 0x0100;
+        pub const ES_OEMCONVERT: DWORD = 0x0400;
+        pub const ES_READONLY: DWORD = 0x0800;
+        pub const ES_WANTRETURN: DWORD = 0x1000;
+        pub const ES_NUMBER: DWORD = 0x2000;
+        pub const DS_ABSALIGN: DWORD = 0x01;
+        pub const DS_SYSMODAL: DWORD = 0x02;
+        pub const DS_LOCALEDIT: DWORD = 0x20;
+        pub const DS_SETFONT: DWORD = 0x40;
+        pub const DS_MODALFRAME: DWORD = 0x80;
+        pub const DS_NOIDLEMSG: DWORD = 0x100;
+        pub const DS_SETFOREGROUND: DWORD = 0x200;
+        pub const DS_3DLOOK: DWORD = 0x0004;
+        pub const DS_FIXEDSYS: DWORD = 0x0008;
+        pub const DS_NOFAILCREATE: DWORD = 0x0010;
+        pub const DS_CONTROL: DWORD = 0x0400;
+        pub const DS_CENTER: DWORD = 0x0800;
+        pub const DS_CENTERMOUSE: DWORD = 0x1000;
+        pub const DS_CONTEXTHELP: DWORD = 0x2000;
+        pub const DS_SHELLFONT: DWORD = DS_SETFONT | DS_FIXEDSYS;
+        pub const DS_USEPIXELS: DWORD = 0x8000;
+    }
+
+    pub(crate) mod wingdi {
+        use super::ctypes::c_int;
+        use super::minwindef::DWORD;
+        pub const ANSI_CHARSET: DWORD = 0;
+        pub const DEFAULT_CHARSET: DWORD = 1;
+        pub const SYMBOL_CHARSET: DWORD = 2;
+        pub const SHIFTJIS_CHARSET: DWORD = 128;
+        pub const HANGEUL_CHARSET: DWORD = 129;
+        pub const HANGUL_CHARSET: DWORD = 129;
+        pub const GB2312_CHARSET: DWORD = 134;
+        pub const CHINESEBIG5_CHARSET: DWORD = 136;
+        pub const OEM_CHARSET: DWORD = 255;
+        pub const JOHAB_CHARSET: DWORD = 130;
+        pub const HEBREW_CHARSET: DWORD = 177;
+        pub const ARABIC_CHARSET: DWORD = 178;
+        pub const GREEK_CHARSET: DWORD = 161;
+        pub const TURKISH_CHARSET: DWORD = 162;
+        pub const VIETNAMESE_CHARSET: DWORD = 163;
+        pub const THAI_CHARSET: DWORD = 222;
+        pub const EASTEUROPE_CHARSET: DWORD = 238;
+        pub const RUSSIAN_CHARSET: DWORD = 204;
+        pub const MAC_CHARSET: DWORD = 77;
+        pub const BALTIC_CHARSET: DWORD = 186;
+        pub const FW_DONTCARE: c_int = 0;
+        pub const FW_THIN: c_int = 100;
+        pub const FW_EXTRALIGHT: c_int = 200;
+        pub const FW_LIGHT: c_int = 300;
+        pub const FW_NORMAL: c_int = 400;
+        pub const FW_MEDIUM: c_int = 500;
+        pub const FW_SEMIBOLD: c_int = 600;
+        pub const FW_BOLD: c_int = 700;
+        pub const FW_EXTRABOLD: c_int = 800;
+        pub const FW_HEAVY: c_int = 900;
+        pub const FW_ULTRALIGHT: c_int = FW_EXTRALIGHT;
+        pub const FW_REGULAR: c_int = FW_NORMAL;
+        pub const FW_DEMIBOLD: c_int = FW_SEMIBOLD;
+        pub const FW_ULTRABOLD: c_int = FW_EXTRABOLD;
+        pub const FW_BLACK: c_int = FW_HEAVY;
+    }
+}
 
 type CowStr = Cow<'static, str>;
 type CowPath = Cow<'static, Path>;
 
-#[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Debug)]
+// Forwards to the `log` crate with the `log` feature (which `tracing` picks up
+// too), and compiles to nothing otherwise.
+macro_rules! log {
+    ($level:ident, $($arg:tt)*) => {{
+        #[cfg(feature = "log")]
+        log::$level!($($arg)*);
+        #[cfg(not(feature = "log"))]
+        if false {
+            let _ = format_args!($($arg)*);
+        }
+    }};
+}
+
+#[derive(Copy, Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Debug)]
 pub struct Lang(WORD, WORD);
 
+impl Lang {
+    pub(crate) fn from_lang_id(lang_id: WORD) -> Self {
+        Lang(lang_id & 0x3FF, lang_id >> 10)
+    }
+
+    pub(crate) fn lang_id(self) -> WORD {
+        (self.1 << 10) | self.0
+    }
+}
+
 pub mod lang {
     use super::Lang;
-    use winapi::shared::ntdef::*;
+    use crate::sys::ntdef::*;
 
     pub const LANG_ENU: Lang = Lang(LANG_ENGLISH, SUBLANG_ENGLISH_US);
     pub const PRESET_LANG_1: &[Lang] = &[LANG_ENU];
@@ -50,9 +543,118 @@ pub mod lang {
         LANG_ENU, LANG_CHS, LANG_CHT, LANG_CSY, LANG_DEU, LANG_ESN, LANG_FRA, LANG_ITA, LANG_JPN,
         LANG_KOR, LANG_PLK, LANG_PTB, LANG_RUS, LANG_TRK,
     ];
+
+    pub const LANG_ARA: Lang = Lang(LANG_ARABIC, SUBLANG_ARABIC_SAUDI_ARABIA);
+    pub const LANG_BGR: Lang = Lang(LANG_BULGARIAN, SUBLANG_BULGARIAN_BULGARIA);
+    pub const LANG_DAN: Lang = Lang(LANG_DANISH, SUBLANG_DANISH_DENMARK);
+    pub const LANG_ELL: Lang = Lang(LANG_GREEK, SUBLANG_GREEK_GREECE);
+    pub const LANG_ENG: Lang = Lang(LANG_ENGLISH, SUBLANG_ENGLISH_UK);
+    pub const LANG_ESM: Lang = Lang(LANG_SPANISH, SUBLANG_SPANISH_MEXICAN);
+    pub const LANG_ETI: Lang = Lang(LANG_ESTONIAN, SUBLANG_ESTONIAN_ESTONIA);
+    pub const LANG_FIN: Lang = Lang(LANG_FINNISH, SUBLANG_FINNISH_FINLAND);
+    pub const LANG_FRC: Lang = Lang(LANG_FRENCH, SUBLANG_FRENCH_CANADIAN);
+    pub const LANG_HEB: Lang = Lang(LANG_HEBREW, SUBLANG_HEBREW_ISRAEL);
+    pub const LANG_HRV: Lang = Lang(LANG_CROATIAN, SUBLANG_CROATIAN_CROATIA);
+    pub const LANG_HUN: Lang = Lang(LANG_HUNGARIAN, SUBLANG_HUNGARIAN_HUNGARY);
+    pub const LANG_LTH: Lang = Lang(LANG_LITHUANIAN, SUBLANG_LITHUANIAN);
+    pub const LANG_LVI: Lang = Lang(LANG_LATVIAN, SUBLANG_LATVIAN_LATVIA);
+    pub const LANG_NLD: Lang = Lang(LANG_DUTCH, SUBLANG_DUTCH);
+    pub const LANG_NOR: Lang = Lang(LANG_NORWEGIAN, SUBLANG_NORWEGIAN_BOKMAL);
+    pub const LANG_PTG: Lang = Lang(LANG_PORTUGUESE, SUBLANG_PORTUGUESE);
+    pub const LANG_ROM: Lang = Lang(LANG_ROMANIAN, SUBLANG_ROMANIAN_ROMANIA);
+    pub const LANG_SKY: Lang = Lang(LANG_SLOVAK, SUBLANG_SLOVAK_SLOVAKIA);
+    pub const LANG_SLV: Lang = Lang(LANG_SLOVENIAN, SUBLANG_SLOVENIAN_SLOVENIA);
+    pub const LANG_SRM: Lang = Lang(LANG_SERBIAN, SUBLANG_SERBIAN_SERBIA_LATIN);
+    pub const LANG_SVE: Lang = Lang(LANG_SWEDISH, SUBLANG_SWEDISH);
+    pub const LANG_THA: Lang = Lang(LANG_THAI, SUBLANG_THAI_THAILAND);
+    pub const LANG_UKR: Lang = Lang(LANG_UKRAINIAN, SUBLANG_UKRAINIAN_UKRAINE);
+
+    // The fully localized language set Windows itself ships.
+    pub const PRESET_LANG_FULL_MUI: &[Lang] = &[
+        LANG_ENU, LANG_ARA, LANG_BGR, LANG_CHS, LANG_CHT, LANG_CSY, LANG_DAN, LANG_DEU, LANG_ELL,
+        LANG_ENG, LANG_ESM, LANG_ESN, LANG_ETI, LANG_FIN, LANG_FRA, LANG_FRC, LANG_HEB, LANG_HRV,
+        LANG_HUN, LANG_ITA, LANG_JPN, LANG_KOR, LANG_LTH, LANG_LVI, LANG_NLD, LANG_NOR, LANG_PLK,
+        LANG_PTB, LANG_PTG, LANG_ROM, LANG_RUS, LANG_SKY, LANG_SLV, LANG_SRM, LANG_SVE, LANG_THA,
+        LANG_TRK, LANG_UKR,
+    ];
+
+    const KNOWN_LANGS: &[(Lang, &str, &str)] = &[
+        (LANG_ENU, "ENU", "en-US"),
+        (LANG_CHS, "CHS", "zh-CN"),
+        (LANG_CHT, "CHT", "zh-TW"),
+        (LANG_DEU, "DEU", "de-DE"),
+        (LANG_ESN, "ESN", "es-ES"),
+        (LANG_FRA, "FRA", "fr-FR"),
+        (LANG_ITA, "ITA", "it-IT"),
+        (LANG_JPN, "JPN", "ja-JP"),
+        (LANG_KOR, "KOR", "ko-KR"),
+        (LANG_RUS, "RUS", "ru-RU"),
+        (LANG_CSY, "CSY", "cs-CZ"),
+        (LANG_PLK, "PLK", "pl-PL"),
+        (LANG_PTB, "PTB", "pt-BR"),
+        (LANG_TRK, "TRK", "tr-TR"),
+        (LANG_ARA, "ARA", "ar-SA"),
+        (LANG_BGR, "BGR", "bg-BG"),
+        (LANG_DAN, "DAN", "da-DK"),
+        (LANG_ELL, "ELL", "el-GR"),
+        (LANG_ENG, "ENG", "en-GB"),
+        (LANG_ESM, "ESM", "es-MX"),
+        (LANG_ETI, "ETI", "et-EE"),
+        (LANG_FIN, "FIN", "fi-FI"),
+        (LANG_FRC, "FRC", "fr-CA"),
+        (LANG_HEB, "HEB", "he-IL"),
+        (LANG_HRV, "HRV", "hr-HR"),
+        (LANG_HUN, "HUN", "hu-HU"),
+        (LANG_LTH, "LTH", "lt-LT"),
+        (LANG_LVI, "LVI", "lv-LV"),
+        (LANG_NLD, "NLD", "nl-NL"),
+        (LANG_NOR, "NOR", "nb-NO"),
+        (LANG_PTG, "PTG", "pt-PT"),
+        (LANG_ROM, "ROM", "ro-RO"),
+        (LANG_SKY, "SKY", "sk-SK"),
+        (LANG_SLV, "SLV", "sl-SI"),
+        (LANG_SRM, "SRM", "sr-Latn-RS"),
+        (LANG_SVE, "SVE", "sv-SE"),
+        (LANG_THA, "THA", "th-TH"),
+        (LANG_UKR, "UKR", "uk-UA"),
+    ];
+
+    // Accepts a BCP-47 tag ("en-US"), a three letter code ("ENU") or a numeric LANGID ("0x0409").
+    pub fn from_tag(tag: &str) -> Option<Lang> {
+        let tag = tag.trim();
+        for &(lang, code, bcp47) in KNOWN_LANGS {
+            if tag.eq_ignore_ascii_case(code) || tag.eq_ignore_ascii_case(bcp47) {
+                return Some(lang);
+            }
+        }
+        let lang_id = if let Some(hex) = tag.strip_prefix("0x").or_else(|| tag.strip_prefix("0X")) {
+            u16::from_str_radix(hex, 16).ok()
+        } else {
+            tag.parse::<u16>().ok()
+        };
+        lang_id.map(Lang::from_lang_id)
+    }
+
+    pub fn tag(lang: Lang) -> Option<&'static str> {
+        KNOWN_LANGS
+            .iter()
+            .find(|&&(known, _, _)| known == lang)
+            .map(|&(_, _, bcp47)| bcp47)
+    }
+
+    // Keeps the languages of `preset` that also appear in `available`, in preset order.
+    pub fn intersect(preset: &[Lang], available: &[Lang]) -> Vec<Lang> {
+        preset
+            .iter()
+            .cloned()
+            .filter(|l| available.contains(l))
+            .collect()
+    }
 }
 
-#[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug)]
+#[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Debug)]
+#[cfg_attr(feature = "spec", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "spec", serde(transparent))]
 pub struct Id(WORD);
 
 impl From<WORD> for Id {
@@ -61,13 +663,73 @@ impl From<WORD> for Id {
     }
 }
 
+impl From<u8> for Id {
+    fn from(v: u8) -> Self {
+        Id(v.into())
+    }
+}
+
 impl From<isize> for Id {
     fn from(v: isize) -> Self {
-        let v: WORD = match v {
-            -1..=0xFFFF => v as u16,
-            _ => panic!("id out of bound, expected u16, actual value = {}", v),
-        };
-        Id(v)
+        match Id::checked(v as i64) {
+            Ok(id) => id,
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
+
+impl Id {
+    // -1 is accepted as the conventional "no id" value.
+    fn checked(v: i64) -> Result<Self, IdOutOfRangeError> {
+        match v {
+            -1..=0xFFFF => Ok(Id(v as u16)),
+            _ => Err(IdOutOfRangeError(v)),
+        }
+    }
+}
+
+macro_rules! impl_id_try_from {
+    ($($int_type:ty),*) => {
+        $(
+            impl std::convert::TryFrom<$int_type> for Id {
+                type Error = IdOutOfRangeError;
+
+                fn try_from(v: $int_type) -> Result<Self, Self::Error> {
+                    Id::checked(i64::try_from(v).unwrap_or(i64::MAX))
+                }
+            }
+
+            impl std::convert::TryFrom<$int_type> for IdOrName {
+                type Error = IdOutOfRangeError;
+
+                fn try_from(v: $int_type) -> Result<Self, Self::Error> {
+                    Id::try_from(v).map(IdOrName::Id)
+                }
+            }
+        )*
+    };
+}
+
+impl_id_try_from!(i8, i32, u32, usize);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct IdOutOfRangeError(i64);
+
+impl fmt::Display for IdOutOfRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "id out of bound, expected u16 or -1, actual value = {}",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for IdOutOfRangeError {}
+
+impl From<IdOutOfRangeError> for io::Error {
+    fn from(e: IdOutOfRangeError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
     }
 }
 
@@ -78,18 +740,124 @@ impl fmt::Display for Id {
     }
 }
 
-#[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Debug)]
+#[derive(Clone, PartialEq, PartialOrd, Eq, Ord, Hash, Debug)]
+#[cfg_attr(feature = "spec", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "spec", serde(untagged))]
 pub enum IdOrName {
     Id(Id),
     Name(CowStr),
 }
 
+impl IdOrName {
+    // Borrows the name instead of copying it, on stable too.
+    pub const fn name_static(name: &'static str) -> Self {
+        IdOrName::Name(Cow::Borrowed(name))
+    }
+}
+
+impl IdOrName {
+    // Like `str::parse`, but a name found in `symbols` (e.g. the `#define`s of a
+    // spec file) resolves to its id.
+    pub fn parse_with_symbols(s: &str, symbols: &[(&str, Id)]) -> Result<Self, io::Error> {
+        match symbols.iter().find(|(symbol, _)| *symbol == s) {
+            Some((_, id)) => Ok(IdOrName::Id(id.clone())),
+            None => s.parse(),
+        }
+    }
+}
+
+// Accepts a decimal or `0x` prefixed hexadecimal id, or -1, and takes anything
+// else not starting with a digit as a name.
+impl std::str::FromStr for IdOrName {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidInput, msg);
+        if s.is_empty() {
+            return Err(invalid("empty resource id or name".to_owned()));
+        }
+        if !s.starts_with(|ch: char| ch.is_ascii_digit() || ch == '-') {
+            return Ok(IdOrName::Name(interner::intern(Cow::Owned(s.to_owned()))));
+        }
+        let value = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(hex) => i64::from_str_radix(hex, 16),
+            None => s.parse::<i64>(),
+        }
+        .map_err(|_| invalid(format!("invalid resource id: {}", s)))?;
+        Ok(IdOrName::Id(Id::checked(value)?))
+    }
+}
+
+impl fmt::Display for IdOrName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IdOrName::Id(id) => write!(f, "{}", id),
+            IdOrName::Name(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+// The sizes of the square images in an ICO file, None if it isn't one. A
+// width byte of 0 stands for 256.
+fn ico_sizes(data: &[u8]) -> Option<Vec<u32>> {
+    let word = |pos: usize| Some(u16::from_le_bytes([*data.get(pos)?, *data.get(pos + 1)?]));
+    if word(0)? != 0 || word(2)? != 1 {
+        return None;
+    }
+    let count = word(4)? as usize;
+    let mut sizes = Vec::with_capacity(count);
+    for idx in 0..count {
+        let entry = data.get(6 + idx * 16..6 + (idx + 1) * 16)?;
+        let size = |byte: u8| if byte == 0 { 256 } else { byte as u32 };
+        if entry[0] == entry[1] {
+            sizes.push(size(entry[0]));
+        }
+    }
+    Some(sizes)
+}
+
+fn describe_type(resource: &dyn Resource) -> String {
+    match (resource.type_keyword(), resource.user_defined_type()) {
+        (_, Some(type_id)) => type_id.to_string(),
+        (Some(keyword), None) => keyword.to_string(),
+        (None, None) => "resource".to_string(),
+    }
+}
+
+fn describe_langs(langs: &[Lang]) -> String {
+    let tags: Vec<String> = langs
+        .iter()
+        .map(|&lang| match lang::tag(lang) {
+            Some(tag) => tag.to_owned(),
+            None => format!("0x{:04X}", lang.lang_id()),
+        })
+        .collect();
+    tags.join(", ")
+}
+
+// Marks a `&'static str` to be borrowed when converted into an `IdOrName`,
+// e.g. `.resource(StaticName("APP_ICON"), icon)`.
+#[derive(Clone, Copy, Debug)]
+pub struct StaticName(pub &'static str);
+
+impl From<StaticName> for IdOrName {
+    fn from(v: StaticName) -> Self {
+        IdOrName::name_static(v.0)
+    }
+}
+
 impl From<WORD> for IdOrName {
     fn from(v: WORD) -> Self {
         IdOrName::Id(Id(v))
     }
 }
 
+impl From<u8> for IdOrName {
+    fn from(v: u8) -> Self {
+        IdOrName::Id(Id::from(v))
+    }
+}
+
 impl From<isize> for IdOrName {
     fn from(v: isize) -> Self {
         IdOrName::Id(Id::from(v))
@@ -98,21 +866,21 @@ impl From<isize> for IdOrName {
 
 impl From<String> for IdOrName {
     fn from(v: String) -> Self {
-        IdOrName::Name(Cow::Owned(v))
+        IdOrName::Name(interner::intern(Cow::Owned(v)))
     }
 }
 
 #[cfg(not(feature = "unstable"))]
 impl<'a> From<&'a str> for IdOrName {
     fn from(v: &'a str) -> Self {
-        IdOrName::Name(Cow::Owned(v.to_owned()))
+        IdOrName::Name(interner::intern(Cow::Owned(v.to_owned())))
     }
 }
 
 #[cfg(feature = "unstable")]
 default impl<'a> From<&'a str> for IdOrName {
     fn from(v: &'a str) -> Self {
-        IdOrName::Name(Cow::Owned(v.to_owned()))
+        IdOrName::Name(interner::intern(Cow::Owned(v.to_owned())))
     }
 }
 
@@ -127,8 +895,10 @@ where
 }
 
 pub mod predefined_id {
+    use crate::sys::minwindef::WORD;
+    use crate::sys::winuser;
     use crate::Id;
-    use winapi::um::winuser;
+    use std::ops::RangeInclusive;
 
     pub const DEFAULT: Id = Id(-1 as _);
 
@@ -144,21 +914,423 @@ pub mod predefined_id {
     pub const TRY_AGAIN: Id = Id(winuser::IDTRYAGAIN as _);
     pub const CONTINUE: Id = Id(winuser::IDCONTINUE as _);
     pub const TIMEOUT: Id = Id(winuser::IDTIMEOUT as _);
+
+    // IDC_STATIC, for static controls the code never refers to.
+    pub const STATIC: Id = Id(-1 as _);
+
+    // Conventional ranges for each kind of id, as used by Visual Studio and
+    // MFC. Keeping kinds apart means a command never shares its id with a
+    // control, and the predefined dialog ids above stay free.
+    pub const RESOURCES: RangeInclusive<WORD> = 101..=0x6FFF;
+    pub const STRINGS: RangeInclusive<WORD> = 1..=0x7FFF;
+    pub const CONTROLS: RangeInclusive<WORD> = 1000..=0x7FFF;
+    pub const COMMANDS: RangeInclusive<WORD> = 0x8000..=0xDFFF;
+
+    // Hands out consecutive ids from a range, e.g.
+    // `let mut commands = IdAllocator::commands(); let open = commands.next();`
+    #[derive(Clone, Debug)]
+    pub struct IdAllocator(RangeInclusive<WORD>);
+
+    impl IdAllocator {
+        pub fn new(range: RangeInclusive<WORD>) -> Self {
+            IdAllocator(range)
+        }
+
+        pub fn resources() -> Self {
+            Self::new(RESOURCES)
+        }
+
+        pub fn strings() -> Self {
+            Self::new(STRINGS)
+        }
+
+        pub fn controls() -> Self {
+            Self::new(CONTROLS)
+        }
+
+        pub fn commands() -> Self {
+            Self::new(COMMANDS)
+        }
+    }
+
+    impl Iterator for IdAllocator {
+        type Item = Id;
+
+        fn next(&mut self) -> Option<Id> {
+            self.0.next().map(Id)
+        }
+    }
 }
 
 pub struct Build {
-    resources: BTreeMap<Lang, Vec<(IdOrName, Box<dyn Resource>)>>,
+    languages: BTreeSet<Lang>,
+    // languages whose sections come first in scripts, in this order
+    language_order: Vec<Lang>,
+    // languages given more than once when the build was created
+    duplicate_languages: BTreeSet<Lang>,
+    // each resource stored once, in registration order, with the language it
+    // is restricted to, or None if it applies to every language
+    resources: Vec<(Option<Lang>, IdOrName, Box<dyn Resource>)>,
+    // positions in `resources` by resource type and id, kept up to date as
+    // resources are added so checks don't have to compare every pair
+    index: HashMap<ResourceKey, Vec<usize>>,
+    code_pages: BTreeMap<Lang, UINT>,
+    dialect: Dialect,
+    line_ending: LineEnding,
+    order: ResourceOrder,
+    diagnostics: Diagnostics,
+    lints: BTreeMap<Lint, Severity>,
+    wide_text: bool,
+    designer_compatible: bool,
+    // hand-written scripts, and whether to copy them in instead of #including
+    rc_fragments: Vec<(std::path::PathBuf, bool)>,
+    // headers to #include, and whether to use <> instead of ""
+    headers: Vec<(String, bool)>,
+}
+
+// Relative resource paths are taken from the package directory; files a
+// build script generates into OUT_DIR are referred to through this, e.g.
+// `Icon::from_file(resw::out_dir_path("app.ico")?)`.
+pub fn out_dir_path(path: impl AsRef<Path>) -> io::Result<std::path::PathBuf> {
+    Ok(codegen::out_dir()?.join(path))
+}
+
+// Reads an environment variable resource content is taken from, such as a
+// build number or commit id set by CI, and tells cargo to rerun the build
+// script when it changes; otherwise the embedded value goes stale. Values that
+// aren't valid Unicode count as unset.
+pub fn env_var(name: &str) -> Option<String> {
+    if std::env::var_os("OUT_DIR").is_some() {
+        println!("cargo:rerun-if-env-changed={}", name);
+    }
+    std::env::var(name).ok()
+}
+
+// A condition on the crate a build script is run for, read from the
+// environment cargo sets for build scripts. `Feature` takes the feature name
+// as written in Cargo.toml, `Profile` the name cargo reports in PROFILE
+// ("debug" or "release"), and `Debug` holds when debug assertions are on.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Cfg {
+    Feature(&'static str),
+    Profile(&'static str),
+    Debug,
+    Not(Box<Cfg>),
+    All(Vec<Cfg>),
+    Any(Vec<Cfg>),
+}
+
+impl Cfg {
+    pub fn holds(&self) -> bool {
+        match self {
+            Cfg::Feature(name) => {
+                let var = format!("CARGO_FEATURE_{}", name.to_uppercase().replace('-', "_"));
+                std::env::var_os(var).is_some()
+            }
+            Cfg::Profile(profile) => std::env::var("PROFILE").is_ok_and(|p| p == *profile),
+            Cfg::Debug => std::env::var("CARGO_CFG_DEBUG_ASSERTIONS").is_ok(),
+            Cfg::Not(cfg) => !cfg.holds(),
+            Cfg::All(cfgs) => cfgs.iter().all(Cfg::holds),
+            Cfg::Any(cfgs) => cfgs.iter().any(Cfg::holds),
+        }
+    }
+}
+
+// `!Cfg::Debug` for release-only resources.
+impl std::ops::Not for Cfg {
+    type Output = Cfg;
+
+    fn not(self) -> Cfg {
+        Cfg::Not(Box::new(self))
+    }
+}
+
+// The checks run by Build::check, each at a severity set with Build::lint.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Lint {
+    // the same language passed twice when creating the build
+    DuplicateLanguage,
+    // the same type and id more than once in a language
+    DuplicateId,
+    // universal resources with texts not given for every language
+    MissingTranslation,
+    // icons, bitmaps and other referenced files that don't exist
+    MissingFile,
+    // texts longer than resource compilers accept
+    OverlongString,
+    // style bits that exclude each other, like WS_CHILD with WS_POPUP
+    StyleConflict,
+    // icon files without one of the sizes Windows shows icons at
+    IconSizes,
+}
+
+impl Lint {
+    pub const ALL: [Lint; 7] = [
+        Lint::DuplicateLanguage,
+        Lint::DuplicateId,
+        Lint::MissingTranslation,
+        Lint::MissingFile,
+        Lint::OverlongString,
+        Lint::StyleConflict,
+        Lint::IconSizes,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::DuplicateLanguage => "duplicate_language",
+            Lint::DuplicateId => "duplicate_id",
+            Lint::MissingTranslation => "missing_translation",
+            Lint::MissingFile => "missing_file",
+            Lint::OverlongString => "overlong_string",
+            Lint::StyleConflict => "style_conflict",
+            Lint::IconSizes => "icon_sizes",
+        }
+    }
+
+    // Lints only warn by default, so builds that went through before a lint
+    // was added keep doing so; `Build::lint` turns them into errors.
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Lint::DuplicateLanguage
+            | Lint::DuplicateId
+            | Lint::MissingTranslation
+            | Lint::MissingFile
+            | Lint::OverlongString
+            | Lint::StyleConflict
+            | Lint::IconSizes => Severity::Warn,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Severity {
+    Allow,
+    Warn,
+    Deny,
 }
 
+// A resource type, as the script keyword or the user defined type id, the
+// resource's own id, and the preprocessor condition it is written under.
+type ResourceKey = (
+    Option<&'static str>,
+    Option<IdOrName>,
+    IdOrName,
+    Option<resource::Condition>,
+);
+
 impl Build {
     pub fn new(languages: &[Lang]) -> Self {
-        let mut build = Build {
-            resources: BTreeMap::new(),
+        Self::with_languages(languages.iter().cloned())
+    }
+
+    // Languages listed more than once get a single section, and are reported
+    // by the DuplicateLanguage lint of `check`.
+    pub fn with_languages(languages: impl IntoIterator<Item = Lang>) -> Self {
+        let mut unique = BTreeSet::new();
+        let mut duplicate_languages = BTreeSet::new();
+        for language in languages {
+            if !unique.insert(language) {
+                duplicate_languages.insert(language);
+            }
+        }
+        Build {
+            languages: unique,
+            language_order: Vec::new(),
+            duplicate_languages,
+            resources: Vec::new(),
+            index: HashMap::new(),
+            code_pages: BTreeMap::new(),
+            dialect: Dialect::default(),
+            line_ending: LineEnding::default(),
+            order: ResourceOrder::default(),
+            diagnostics: Diagnostics::default(),
+            lints: BTreeMap::new(),
+            wide_text: false,
+            designer_compatible: false,
+            rc_fragments: Vec::new(),
+            headers: Vec::new(),
+        }
+    }
+
+    // Takes the languages from a comma separated list of BCP-47 tags (or
+    // anything else `lang::from_tag` accepts) in the environment variable
+    // `var`, e.g. `RESW_LANGS=en-US,de-DE,ja-JP`. When called from a build
+    // script, cargo is told to rerun it when the variable changes.
+    pub fn from_env(var: &str) -> Result<Self, io::Error> {
+        if std::env::var_os("OUT_DIR").is_some() {
+            println!("cargo:rerun-if-env-changed={}", var);
+        }
+        let list = std::env::var(var)
+            .map_err(|e| io::Error::new(io::ErrorKind::NotFound, format!("{}: {}", var, e)))?;
+        let mut languages = Vec::new();
+        for tag in list.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+            let lang = lang::from_tag(tag).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{}: unknown language `{}`", var, tag),
+                )
+            })?;
+            languages.push(lang);
+        }
+        Ok(Self::with_languages(languages))
+    }
+
+    // Builds from the files in `dir` (usually "resources"), with the type
+    // taken from the extension and the id from the file name: `app.ico`
+    // becomes the icon named app, `101.bmp` the bitmap with id 101. Files in
+    // a subdirectory named by a language tag (`de-DE/help.html`) are specific
+    // to that language, and those languages make up the build; without any,
+    // it is built for lang::PRESET_LANG_1. A manifest named other than by a
+    // number gets the id the loader looks for in what the package builds:
+    // 1 for a package with binaries, 2 for a DLL.
+    pub fn from_directory(dir: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let dir = &codegen::base_dir()?.join(dir);
+        if std::env::var_os("OUT_DIR").is_some() {
+            println!("cargo:rerun-if-changed={}", dir.display());
+        }
+        let mut universal = Vec::new();
+        let mut lang_specific = Vec::new();
+        for path in Self::sorted_dir_entries(dir)? {
+            if !path.is_dir() {
+                universal.push(path);
+                continue;
+            }
+            let tag = path.file_name().unwrap_or_default().to_string_lossy();
+            let lang = lang::from_tag(&tag).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{}: unknown language `{}`", path.display(), tag),
+                )
+            })?;
+            for file in Self::sorted_dir_entries(&path)? {
+                if file.is_dir() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{}: nested directories are not supported", file.display()),
+                    ));
+                }
+                lang_specific.push((lang, file));
+            }
+        }
+        let mut build = if lang_specific.is_empty() {
+            Self::with_one_language()
+        } else {
+            let languages: BTreeSet<Lang> = lang_specific.iter().map(|&(lang, _)| lang).collect();
+            Self::with_languages(languages)
         };
-        for language in languages.iter().cloned() {
-            build.resources.insert(language, Vec::new());
+        for path in universal {
+            build.add_file_by_convention(None, &path)?;
         }
-        build
+        for (lang, path) in lang_specific {
+            build.add_file_by_convention(Some(lang), &path)?;
+        }
+        Ok(build)
+    }
+
+    // Adds every file under `dir` as an HTML resource named by its path
+    // relative to `dir`, e.g. `css/site.css`, for pages loaded through
+    // `res://app.exe/index.html` that link to each other by relative URLs.
+    pub fn html_directory(mut self, dir: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let dir = &codegen::base_dir()?.join(dir);
+        let rerun = std::env::var_os("OUT_DIR").is_some();
+        if rerun {
+            println!("cargo:rerun-if-changed={}", dir.display());
+        }
+        let mut pending = vec![dir.clone()];
+        while let Some(current) = pending.pop() {
+            let mut subdirs = Vec::new();
+            for path in Self::sorted_dir_entries(&current)? {
+                if path.is_dir() {
+                    subdirs.push(path);
+                    continue;
+                }
+                let relative = path.strip_prefix(dir).unwrap_or(&path);
+                let components: Option<Vec<&str>> = relative
+                    .components()
+                    .map(|component| component.as_os_str().to_str())
+                    .collect();
+                let name = components.map(|c| c.join("/")).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{}: file name is not valid UTF-8", path.display()),
+                    )
+                })?;
+                if rerun {
+                    println!("cargo:rerun-if-changed={}", path.display());
+                }
+                self = self.resource(name, resource::HTML::from_file(&path));
+            }
+            // a directory's files come before those of its subdirectories
+            pending.extend(subdirs.into_iter().rev());
+        }
+        Ok(self)
+    }
+
+    // The entries of `dir` in name order, leaving out hidden files.
+    fn sorted_dir_entries(dir: &Path) -> Result<Vec<std::path::PathBuf>, io::Error> {
+        let annotate = |e: io::Error| io::Error::new(e.kind(), format!("{}: {}", dir.display(), e));
+        let mut paths = Vec::new();
+        for entry in std::fs::read_dir(dir).map_err(annotate)? {
+            let path = entry.map_err(annotate)?.path();
+            if !path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .starts_with('.')
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn add_file_by_convention(
+        &mut self,
+        language: Option<Lang>,
+        path: &Path,
+    ) -> Result<(), io::Error> {
+        let invalid = |msg: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: {}", path.display(), msg),
+            )
+        };
+        let stem = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .ok_or_else(|| invalid("file name is not valid UTF-8"))?;
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let id_or_name = stem
+            .parse::<IdOrName>()
+            .map_err(|e| invalid(&e.to_string()))?;
+        let (id_or_name, resource): (IdOrName, Box<dyn Resource>) = match extension.as_str() {
+            "ico" => (id_or_name, Box::new(resource::Icon::from_file(path))),
+            "cur" => (id_or_name, Box::new(resource::Cursor::from_file(path))),
+            "bmp" => (id_or_name, Box::new(resource::Bitmap::from_file(path))),
+            "html" | "htm" => (id_or_name, Box::new(resource::HTML::from_file(path))),
+            "rgs" => (id_or_name, Box::new(resource::Registry::from_file(path))),
+            "wav" => (id_or_name, Box::new(resource::Wave::from_file(path))),
+            "avi" => (id_or_name, Box::new(resource::Avi::from_file(path))),
+            "png" => (id_or_name, Box::new(resource::Png::from_file(path))),
+            "manifest" => {
+                let id_or_name = match id_or_name {
+                    IdOrName::Name(_) if Self::package_has_bins(&codegen::base_dir()?) => {
+                        IdOrName::from(resource::Manifest::CREATEPROCESS_ID)
+                    }
+                    IdOrName::Name(_) => IdOrName::from(resource::Manifest::ISOLATIONAWARE_ID),
+                    id => id,
+                };
+                (id_or_name, Box::new(resource::Manifest::from_file(path)))
+            }
+            _ => return Err(invalid("no resource type is known for this extension")),
+        };
+        self.push_resource(language, id_or_name, resource);
+        Ok(())
     }
 
     pub fn with_one_language() -> Self {
@@ -182,18 +1354,131 @@ impl Build {
         Self::new(lang::PRESET_LANG_9)
     }
 
+    pub fn with_full_mui_languages(available: &[Lang]) -> Self {
+        Self::new(&lang::intersect(lang::PRESET_LANG_FULL_MUI, available))
+    }
+
+    // Universal resources apply to the new language too, whether added before
+    // or after this call. Does nothing if the language is already present.
+    pub fn add_language(mut self, language: Lang) -> Self {
+        self.languages.insert(language);
+        self
+    }
+
+    // Puts these languages' sections first in scripts, in this order, e.g.
+    // `language_order([lang::LANG_ENU])` for the default language; the rest
+    // follow by LANGID. Languages not in the build are ignored.
+    pub fn language_order(mut self, order: impl IntoIterator<Item = Lang>) -> Self {
+        self.language_order = order.into_iter().collect();
+        self
+    }
+
+    // Writes this language's resources under `#pragma code_page(code_page)`.
+    // Text is written as wide strings there, but typefaces and resource names
+    // stay narrow and have to be ASCII under any code page other than 65001.
+    pub fn lang_code_page(mut self, language: Lang, code_page: UINT) -> Self {
+        self.code_pages.insert(language, code_page);
+        self
+    }
+
+    pub fn dialect(mut self, dialect: Dialect) -> Self {
+        self.dialect = dialect;
+        self
+    }
+
+    // Applies to the scripts and to every other file generated from the build.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    // Orders the resources of each language in generated scripts; languages
+    // always come in the same order.
+    pub fn resource_order(mut self, order: ResourceOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    // Writes every caption, menu item, control text and string table entry as
+    // a wide string, not only the ones with non-ASCII text.
+    pub fn wide_text(mut self, wide_text: bool) -> Self {
+        self.wide_text = wide_text;
+        self
+    }
+
+    // Adds the TEXTINCLUDE and GUIDELINES DESIGNINFO sections Visual Studio's
+    // resource editor expects, so the generated script can be opened there to
+    // look at the dialogs. Changes made in the editor are not read back.
+    pub fn designer_compatible(mut self, designer_compatible: bool) -> Self {
+        self.designer_compatible = designer_compatible;
+        self
+    }
+
+    // Adds `#include "header"` after the generated header comment, for ids
+    // defined in C headers that included scripts refer to. The name is written
+    // as given and looked up by the resource compiler, relative to the
+    // generated script or its include directories.
+    pub fn include_header(mut self, header: impl Into<String>) -> Self {
+        self.headers.push((header.into(), false));
+        self
+    }
+
+    // Adds `#include <header>`, e.g. `include_system_header("winres.h")`.
+    pub fn include_system_header(mut self, header: impl Into<String>) -> Self {
+        self.headers.push((header.into(), true));
+        self
+    }
+
+    // Adds an `#include` of a hand-written script to the end of the generated
+    // one, for resources still maintained by hand. The path is taken relative
+    // to the package directory, like the paths of other resources.
+    pub fn include_rc_file(self, path: impl AsRef<Path>) -> Self {
+        self.add_rc_fragment(path.as_ref(), false)
+    }
+
+    // Like include_rc_file, copying the script's text in instead, so the
+    // generated script stands alone.
+    pub fn inline_rc_file(self, path: impl AsRef<Path>) -> Self {
+        self.add_rc_fragment(path.as_ref(), true)
+    }
+
+    fn add_rc_fragment(mut self, path: &Path, inline: bool) -> Self {
+        if std::env::var_os("OUT_DIR").is_some() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+        self.rc_fragments.push((path.to_owned(), inline));
+        self
+    }
+
+    pub fn diagnostics(mut self, diagnostics: Diagnostics) -> Self {
+        self.diagnostics = diagnostics;
+        self
+    }
+
+    // Overrides the severity `check` gives to `lint`.
+    pub fn lint(mut self, lint: Lint, severity: Severity) -> Self {
+        self.lints.insert(lint, severity);
+        self
+    }
+
+    pub fn lint_severity(&self, lint: Lint) -> Severity {
+        self.lints
+            .get(&lint)
+            .cloned()
+            .unwrap_or_else(|| lint.default_severity())
+    }
+
     pub fn resource(
         mut self,
         id_or_name: impl Into<IdOrName>,
         resource: impl Resource + Clone,
     ) -> Self {
         let id_or_name: IdOrName = id_or_name.into();
-        for (_lang, lang_specific_resources) in self.resources.iter_mut() {
-            lang_specific_resources.push((id_or_name.clone(), Box::new(resource.clone())));
-        }
+        self.push_resource(None, id_or_name, Box::new(resource));
         self
     }
 
+    // Adds `language` to the build if it isn't there yet, as `add_language` does.
     pub fn lang_specific_resource(
         mut self,
         language: Lang,
@@ -201,43 +1486,600 @@ impl Build {
         resource: impl Resource,
     ) -> Self {
         let id_or_name: IdOrName = id_or_name.into();
-        let lang_specific_resources = self.resources.entry(language).or_default();
-        lang_specific_resources.push((id_or_name, Box::new(resource)));
+        self.languages.insert(language);
+        self.push_resource(Some(language), id_or_name, Box::new(resource));
         self
     }
-}
 
-pub trait Resource: 'static {
-    fn write_script_segment(
-        &self,
-        _w: &mut dyn io::Write,
-        _l: Lang,
-        _id_or_name: IdOrName,
-    ) -> io::Result<()> {
-        unimplemented!()
+    // Adds the resource only when `cfg` holds for the crate being built, e.g.
+    // `.resource_if(Cfg::Debug, "DEBUGDLG", dialog)` for a debug-only dialog.
+    pub fn resource_if(
+        self,
+        cfg: Cfg,
+        id_or_name: impl Into<IdOrName>,
+        resource: impl Resource + Clone,
+    ) -> Self {
+        if cfg.holds() {
+            self.resource(id_or_name, resource)
+        } else {
+            self
+        }
     }
-}
 
-#[macro_use]
-pub mod resource {
-    use crate::{CowPath, Resource};
-    use std::path::Path;
-    use std::rc::Rc;
+    pub fn lang_specific_resource_if(
+        self,
+        cfg: Cfg,
+        language: Lang,
+        id_or_name: impl Into<IdOrName>,
+        resource: impl Resource,
+    ) -> Self {
+        if cfg.holds() {
+            self.lang_specific_resource(language, id_or_name, resource)
+        } else {
+            self
+        }
+    }
 
-    fn create_path_only_resource_from_file<T, R: From<CowPath>>(
-        path: impl AsRef<Path>,
-        ty: impl FnOnce(Rc<R>) -> T,
-    ) -> T {
+    // Calls `f` once for each language of the build so far and adds what it
+    // returns as that language's resource; `None` leaves the language out.
+    pub fn resource_with<R: Resource>(
+        mut self,
+        id_or_name: impl Into<IdOrName>,
+        mut f: impl FnMut(Lang) -> Option<R>,
+    ) -> Self {
+        let id_or_name: IdOrName = id_or_name.into();
+        let languages: Vec<Lang> = self.languages.iter().cloned().collect();
+        for language in languages {
+            if let Some(resource) = f(language) {
+                self.push_resource(Some(language), id_or_name.clone(), Box::new(resource));
+            }
+        }
+        self
+    }
+
+    fn push_resource(
+        &mut self,
+        applies_to: Option<Lang>,
+        id_or_name: IdOrName,
+        resource: Box<dyn Resource>,
+    ) {
+        if let Some(key) = Self::resource_key(&id_or_name, resource.as_ref()) {
+            self.index
+                .entry(key)
+                .or_default()
+                .push(self.resources.len());
+        }
+        self.resources.push((applies_to, id_or_name, resource));
+    }
+
+    // String tables are left out, their id isn't written to the script and
+    // their strings are checked one by one instead.
+    fn resource_key(id_or_name: &IdOrName, resource: &dyn Resource) -> Option<ResourceKey> {
+        let keyword = resource.type_keyword();
+        let user_defined_type = resource.user_defined_type();
+        if keyword.is_none() && user_defined_type.is_none() {
+            return None;
+        }
+        let id_or_name = match keyword {
+            Some(resource::StringTable::TYPE_KEYWORD) => return None,
+            // written as VERSIONINFO 1 whatever it is registered as
+            Some(resource::VersionInfo::TYPE_KEYWORD) => IdOrName::Id(Id(1)),
+            _ => id_or_name.clone(),
+        };
+        let condition = resource.condition().cloned();
+        Some((keyword, user_defined_type, id_or_name, condition))
+    }
+
+    // Checks that no two resources of the same type and id end up in the same
+    // language, which resource compilers reject with little context, and that
+    // every universal resource has its texts in all of the build's languages.
+    // Both are errors here whatever their lint severity; other lints don't run.
+    pub fn validate(&self) -> Result<(), io::Error> {
+        self.check_with(|lint| match lint {
+            Lint::DuplicateId | Lint::MissingTranslation => Severity::Deny,
+            _ => Severity::Allow,
+        })
+    }
+
+    // Runs every lint at its configured severity. Warnings are reported to the
+    // build's diagnostics, and if any denied lint finds something the result
+    // is an error listing all of them.
+    pub fn check(&self) -> Result<(), io::Error> {
+        self.check_with(|lint| self.lint_severity(lint))
+    }
+
+    fn check_with(&self, severity_of: impl Fn(Lint) -> Severity) -> Result<(), io::Error> {
+        let mut denied = Vec::new();
+        for &lint in Lint::ALL.iter() {
+            let severity = severity_of(lint);
+            if severity == Severity::Allow {
+                continue;
+            }
+            let mut problems = match lint {
+                Lint::DuplicateLanguage => self.duplicate_languages(),
+                Lint::DuplicateId => self.duplicate_ids(),
+                Lint::MissingTranslation => self.missing_translations(),
+                Lint::MissingFile => self.missing_files()?,
+                Lint::OverlongString => self.overlong_strings(),
+                Lint::StyleConflict => self.style_conflicts(),
+                Lint::IconSizes => self.icon_sizes()?,
+            };
+            problems.sort();
+            for problem in problems {
+                let message = format!("{} [{}]", problem, lint.name());
+                match severity {
+                    Severity::Deny => denied.push(message),
+                    _ => self.diagnostics.report(message)?,
+                }
+            }
+        }
+        if denied.is_empty() {
+            return Ok(());
+        }
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            denied.join("\n"),
+        ))
+    }
+
+    fn duplicate_languages(&self) -> Vec<String> {
+        if self.duplicate_languages.is_empty() {
+            return Vec::new();
+        }
+        let langs: Vec<Lang> = self.duplicate_languages.iter().cloned().collect();
+        vec![format!(
+            "languages given more than once: {}",
+            describe_langs(&langs)
+        )]
+    }
+
+    fn duplicate_ids(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (key, positions) in self.index.iter() {
+            let (keyword, user_defined_type, id_or_name, condition) = key;
+            // an unconditional resource is there whatever the condition
+            let unconditional = match condition {
+                Some(_) => {
+                    let key = (
+                        *keyword,
+                        user_defined_type.clone(),
+                        id_or_name.clone(),
+                        None,
+                    );
+                    self.index
+                        .get(&key)
+                        .map_or(&[][..], |positions| &positions[..])
+                }
+                None => &[],
+            };
+            if positions.len() + unconditional.len() < 2 {
+                continue;
+            }
+            let mut universal = 0;
+            let mut per_lang: BTreeMap<Lang, usize> = BTreeMap::new();
+            for &pos in positions.iter().chain(unconditional) {
+                match self.resources[pos].0 {
+                    None => universal += 1,
+                    Some(lang) => *per_lang.entry(lang).or_default() += 1,
+                }
+            }
+            let clashing: Vec<Lang> = self
+                .languages
+                .iter()
+                .cloned()
+                .filter(|lang| universal + per_lang.get(lang).cloned().unwrap_or(0) > 1)
+                .collect();
+            if clashing.is_empty() {
+                continue;
+            }
+            let type_name = match (keyword, user_defined_type) {
+                (_, Some(type_id)) => type_id.to_string(),
+                (Some(keyword), None) => keyword.to_string(),
+                (None, None) => unreachable!(),
+            };
+            let condition = match condition {
+                Some(condition) => format!(" under {}", condition),
+                None => String::new(),
+            };
+            problems.push(format!(
+                "{} {}{} is defined more than once for {}",
+                type_name,
+                id_or_name,
+                condition,
+                describe_langs(&clashing)
+            ));
+        }
+        problems.extend(self.duplicate_string_ids());
+        problems
+    }
+
+    fn duplicate_string_ids(&self) -> Vec<String> {
+        let mut clashing: BTreeMap<Id, Vec<Lang>> = BTreeMap::new();
+        for &lang in self.languages.iter() {
+            let mut defined: Vec<(Option<&resource::Condition>, Id)> = Vec::new();
+            for (applies_to, _, resource) in self.resources.iter() {
+                if applies_to.is_some_and(|applies_to| applies_to != lang) {
+                    continue;
+                }
+                let condition = resource.condition();
+                for (id, _) in resource.defined_strings(lang) {
+                    // an unconditional string clashes under any condition
+                    let clashes = defined.iter().any(|(c, defined_id)| {
+                        *defined_id == id && (*c == condition || c.is_none() || condition.is_none())
+                    });
+                    defined.push((condition, id.clone()));
+                    if clashes {
+                        let langs = clashing.entry(id).or_default();
+                        if !langs.contains(&lang) {
+                            langs.push(lang);
+                        }
+                    }
+                }
+            }
+        }
+        clashing
+            .into_iter()
+            .map(|(id, langs)| {
+                format!(
+                    "STRINGTABLE string {} is defined more than once for {}",
+                    id,
+                    describe_langs(&langs)
+                )
+            })
+            .collect()
+    }
+
+    fn missing_translations(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        // A text missing in some languages drops the menu item, caption or
+        // string from those languages' resources without any error later.
+        for (applies_to, id_or_name, resource) in self.resources.iter() {
+            if applies_to.is_some() {
+                continue;
+            }
+            let mut covered: BTreeMap<String, Vec<Lang>> = BTreeMap::new();
+            for &lang in self.languages.iter() {
+                for (item, _) in resource.translatable_texts(lang) {
+                    covered.entry(item).or_default().push(lang);
+                }
+            }
+            for (item, langs) in covered {
+                if langs.len() == self.languages.len() {
+                    continue;
+                }
+                let missing: Vec<Lang> = self
+                    .languages
+                    .iter()
+                    .cloned()
+                    .filter(|lang| !langs.contains(lang))
+                    .collect();
+                problems.push(format!(
+                    "{} {}: {} has no text for {}",
+                    describe_type(resource.as_ref()),
+                    id_or_name,
+                    item,
+                    describe_langs(&missing)
+                ));
+            }
+        }
+        problems
+    }
+
+    fn missing_files(&self) -> Result<Vec<String>, io::Error> {
+        let mut resolver = codegen::PathResolver::new()?;
+        let mut problems = Vec::new();
+        for (_, id_or_name, resource) in self.resources.iter() {
+            for path in resource.referenced_files() {
+                if !resolver.resolve(path).is_file() {
+                    problems.push(format!(
+                        "{} {}: {} does not exist",
+                        describe_type(resource.as_ref()),
+                        id_or_name,
+                        path.display()
+                    ));
+                }
+            }
+        }
+        Ok(problems)
+    }
+
+    // Explorer, the taskbar and the title bar pick these sizes; a missing one
+    // gets scaled from another and looks blurry or blocky.
+    const RECOMMENDED_ICON_SIZES: [u32; 4] = [16, 32, 48, 256];
+
+    fn icon_sizes(&self) -> Result<Vec<String>, io::Error> {
+        let mut resolver = codegen::PathResolver::new()?;
+        let mut problems = Vec::new();
+        for (_, id_or_name, resource) in self.resources.iter() {
+            if resource.kind() != ResourceKind::Icon {
+                continue;
+            }
+            for path in resource.referenced_files() {
+                // missing files are the MissingFile lint's business
+                let data = match std::fs::read(resolver.resolve(path)) {
+                    Ok(data) => data,
+                    Err(_) => continue,
+                };
+                let describe = |problem: String| {
+                    format!("ICON {}: {} {}", id_or_name, path.display(), problem)
+                };
+                match ico_sizes(&data) {
+                    Some(sizes) => {
+                        let missing: Vec<String> = Self::RECOMMENDED_ICON_SIZES
+                            .iter()
+                            .filter(|size| !sizes.contains(size))
+                            .map(|size| format!("{0}x{0}", size))
+                            .collect();
+                        if !missing.is_empty() {
+                            problems.push(describe(format!("has no {} image", missing.join(", "))));
+                        }
+                    }
+                    None => problems.push(describe("is not an ICO file".to_owned())),
+                }
+            }
+        }
+        Ok(problems)
+    }
+
+    // Resource compilers reject string literals longer than this.
+    const MAX_STRING_LEN: usize = 4097;
+
+    fn overlong_strings(&self) -> Vec<String> {
+        let mut found: BTreeMap<String, Vec<Lang>> = BTreeMap::new();
+        for (lang, id_or_name, resource) in self.entries() {
+            for (item, text) in resource.translatable_texts(lang) {
+                let len = text.encode_utf16().count();
+                if len > Self::MAX_STRING_LEN {
+                    let problem = format!(
+                        "{} {}: {} is {} characters long, more than {}",
+                        describe_type(resource),
+                        id_or_name,
+                        item,
+                        len,
+                        Self::MAX_STRING_LEN
+                    );
+                    found.entry(problem).or_default().push(lang);
+                }
+            }
+        }
+        found
+            .into_iter()
+            .map(|(problem, langs)| format!("{} ({})", problem, describe_langs(&langs)))
+            .collect()
+    }
+
+    fn style_conflicts(&self) -> Vec<String> {
+        let mut found = BTreeSet::new();
+        for (lang, id_or_name, resource) in self.entries() {
+            for conflict in resource.style_conflicts(lang) {
+                found.insert(format!(
+                    "{} {}: {}",
+                    describe_type(resource),
+                    id_or_name,
+                    conflict
+                ));
+            }
+        }
+        found.into_iter().collect()
+    }
+
+    // A few lines for build logs: the languages, then each resource type with
+    // the number of resources and their ids, language specific ones marked
+    // with their language.
+    pub fn summary(&self) -> String {
+        let mut by_type: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (applies_to, id_or_name, resource) in self.resources.iter() {
+            let id = match applies_to {
+                Some(lang) => format!("{} [{}]", id_or_name, describe_langs(&[*lang])),
+                None => id_or_name.to_string(),
+            };
+            by_type
+                .entry(describe_type(resource.as_ref()))
+                .or_default()
+                .push(id);
+        }
+        let languages = self.ordered_languages();
+        let mut summary = format!("languages: {}\n", describe_langs(&languages));
+        for (type_name, ids) in by_type {
+            summary += &format!("{}: {} ({})\n", type_name, ids.len(), ids.join(", "));
+        }
+        summary
+    }
+
+    // The resources written for one language, in registration order.
+    fn resources_for(&self, lang: Lang) -> impl Iterator<Item = (&IdOrName, &dyn Resource)> {
+        self.resources
+            .iter()
+            .filter(move |(applies_to, _, _)| applies_to.is_none() || *applies_to == Some(lang))
+            .map(|(_, id_or_name, resource)| (id_or_name, resource.as_ref()))
+    }
+
+    // The language a registered resource is specific to, None for universal
+    // resources.
+    fn applies_to(&self, resource: &dyn Resource) -> Option<Lang> {
+        self.resources
+            .iter()
+            .find(|(_, _, r)| std::ptr::addr_eq(r.as_ref(), resource))
+            .and_then(|&(applies_to, _, _)| applies_to)
+    }
+
+    // The build's languages in script order.
+    fn ordered_languages(&self) -> Vec<Lang> {
+        let mut languages = Vec::new();
+        let listed = self
+            .language_order
+            .iter()
+            .filter(|lang| self.languages.contains(lang));
+        for &lang in listed.chain(self.languages.iter()) {
+            if !languages.contains(&lang) {
+                languages.push(lang);
+            }
+        }
+        languages
+    }
+
+    // The resources of every language in script order: by language, then as
+    // the build's ResourceOrder puts them.
+    fn entries(&self) -> impl Iterator<Item = (Lang, &IdOrName, &dyn Resource)> {
+        self.ordered_languages().into_iter().flat_map(move |lang| {
+            let mut resources: Vec<_> = self.resources_for(lang).collect();
+            self.order.sort(&mut resources);
+            resources
+                .into_iter()
+                .map(move |(id_or_name, resource)| (lang, id_or_name, resource))
+        })
+    }
+}
+
+// What a `dyn Resource` is, for code dispatching on the type of resource.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum ResourceKind {
+    Bitmap,
+    Cursor,
+    Font,
+    Html,
+    Icon,
+    MessageTable,
+    StringTable,
+    Accelerators,
+    Menu,
+    Dialog,
+    VersionInfo,
+    RcData,
+    UserDefined(IdOrName),
+    // resources not identifying their type
+    Other,
+}
+
+impl ResourceKind {
+    pub fn from_type_keyword(keyword: &str) -> Option<Self> {
+        use resource::*;
+        let kind = match keyword {
+            Bitmap::TYPE_KEYWORD => ResourceKind::Bitmap,
+            Cursor::TYPE_KEYWORD => ResourceKind::Cursor,
+            Font::TYPE_KEYWORD => ResourceKind::Font,
+            HTML::TYPE_KEYWORD => ResourceKind::Html,
+            Icon::TYPE_KEYWORD => ResourceKind::Icon,
+            MessageTable::TYPE_KEYWORD => ResourceKind::MessageTable,
+            StringTable::TYPE_KEYWORD => ResourceKind::StringTable,
+            Accelerators::TYPE_KEYWORD => ResourceKind::Accelerators,
+            Menu::TYPE_KEYWORD | "MENU" => ResourceKind::Menu,
+            Dialog::TYPE_KEYWORD | "DIALOG" => ResourceKind::Dialog,
+            VersionInfo::TYPE_KEYWORD => ResourceKind::VersionInfo,
+            RcInline::TYPE_KEYWORD => ResourceKind::RcData,
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    // The keyword this crate writes for the kind, None for user defined types.
+    pub fn type_keyword(&self) -> Option<&'static str> {
+        use resource::*;
+        let keyword = match self {
+            ResourceKind::Bitmap => Bitmap::TYPE_KEYWORD,
+            ResourceKind::Cursor => Cursor::TYPE_KEYWORD,
+            ResourceKind::Font => Font::TYPE_KEYWORD,
+            ResourceKind::Html => HTML::TYPE_KEYWORD,
+            ResourceKind::Icon => Icon::TYPE_KEYWORD,
+            ResourceKind::MessageTable => MessageTable::TYPE_KEYWORD,
+            ResourceKind::StringTable => StringTable::TYPE_KEYWORD,
+            ResourceKind::Accelerators => Accelerators::TYPE_KEYWORD,
+            ResourceKind::Menu => Menu::TYPE_KEYWORD,
+            ResourceKind::Dialog => Dialog::TYPE_KEYWORD,
+            ResourceKind::VersionInfo => VersionInfo::TYPE_KEYWORD,
+            ResourceKind::RcData => RcInline::TYPE_KEYWORD,
+            ResourceKind::UserDefined(_) | ResourceKind::Other => return None,
+        };
+        Some(keyword)
+    }
+}
+
+impl fmt::Debug for Build {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.summary())
+    }
+}
+
+pub trait Resource: 'static {
+    fn write_script_segment(
+        &self,
+        _w: &mut dyn io::Write,
+        _l: Lang,
+        _id_or_name: IdOrName,
+    ) -> io::Result<()> {
+        unimplemented!()
+    }
+
+    fn referenced_files(&self) -> Vec<&Path> {
+        Vec::new()
+    }
+
+    // The script keyword of this resource type, e.g. "ICON" or "MENUEX".
+    fn type_keyword(&self) -> Option<&'static str> {
+        None
+    }
+
+    // The type id of resources written as raw data of an arbitrary type.
+    fn user_defined_type(&self) -> Option<IdOrName> {
+        None
+    }
+
+    fn kind(&self) -> ResourceKind {
+        if let Some(type_id) = self.user_defined_type() {
+            return ResourceKind::UserDefined(type_id);
+        }
+        self.type_keyword()
+            .and_then(ResourceKind::from_type_keyword)
+            .unwrap_or(ResourceKind::Other)
+    }
+
+    // (context, text) pairs a translator should see for this language.
+    fn translatable_texts(&self, _l: Lang) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    // Style bits set together that can't both take effect, as found by
+    // Build::check.
+    fn style_conflicts(&self, _l: Lang) -> Vec<String> {
+        Vec::new()
+    }
+
+    // The STRINGTABLE entries this resource defines for this language, which
+    // dialog texts given by string id are taken from.
+    fn defined_strings(&self, _l: Lang) -> Vec<(Id, String)> {
+        Vec::new()
+    }
+
+    // The preprocessor condition this resource is written under, if any;
+    // resources under different conditions don't clash.
+    fn condition(&self) -> Option<&resource::Condition> {
+        None
+    }
+}
+
+#[macro_use]
+#[allow(clippy::write_with_newline)]
+pub mod resource {
+    use crate::{CowPath, Resource};
+    use std::path::Path;
+    use std::rc::Rc;
+
+    fn create_path_only_resource_from_file<T, R: From<CowPath>>(
+        path: impl AsRef<Path>,
+        ty: impl FnOnce(Rc<R>) -> T,
+    ) -> T {
         use std::borrow::Cow;
         ty(Rc::new(R::from(Cow::Owned(path.as_ref().to_owned()))))
     }
 
     macro_rules! define_path_only_resource {
         ($type_name:ident, $res_type_keyword:literal) => {
-            #[derive(Clone)]
+            #[derive(Clone, PartialEq, Debug)]
             pub struct $type_name(Rc<CowPath>);
 
             impl $type_name {
+                pub const TYPE_KEYWORD: &'static str = $res_type_keyword;
+
                 pub fn from_file(path: impl AsRef<Path>) -> Self {
                     create_path_only_resource_from_file(path, $type_name)
                 }
@@ -259,17 +2101,67 @@ pub mod resource {
                     )?;
                     Ok(())
                 }
+
+                fn referenced_files(&self) -> Vec<&Path> {
+                    vec![self.0.as_ref()]
+                }
+
+                fn type_keyword(&self) -> Option<&'static str> {
+                    Some($res_type_keyword)
+                }
+            }
+        };
+    }
+
+    // A file stored under a conventional type name that some API looks for.
+    macro_rules! define_typed_file_resource {
+        ($type_name:ident, $res_type_name:literal) => {
+            #[derive(Clone, PartialEq, Debug)]
+            pub struct $type_name(Rc<CowPath>);
+
+            impl $type_name {
+                pub const TYPE_NAME: &'static str = $res_type_name;
+
+                pub fn from_file(path: impl AsRef<Path>) -> Self {
+                    create_path_only_resource_from_file(path, $type_name)
+                }
+            }
+
+            impl Resource for $type_name {
+                fn write_script_segment(
+                    &self,
+                    w: &mut dyn std::io::Write,
+                    l: crate::Lang,
+                    id_or_name: crate::IdOrName,
+                ) -> Result<(), std::io::Error> {
+                    let type_id = crate::IdOrName::name_static($res_type_name);
+                    crate::codegen::write_user_defined_file_resource(
+                        w,
+                        l,
+                        id_or_name,
+                        &type_id,
+                        self.0.as_ref(),
+                    )
+                }
+
+                fn referenced_files(&self) -> Vec<&Path> {
+                    vec![self.0.as_ref()]
+                }
+
+                fn user_defined_type(&self) -> Option<crate::IdOrName> {
+                    Some(crate::IdOrName::name_static($res_type_name))
+                }
             }
         };
     }
 
     macro_rules! define_builder_generated_resource {
         ($type_name:ident, $data_type:path, $builder_type:path, $res_type_keyword:literal) => {
-            #[derive(Clone)]
+            #[derive(Clone, PartialEq, Debug)]
             pub struct $type_name(pub(crate) Rc<$data_type>);
 
             impl $type_name {
-                pub(crate) const TYPE_KEYWORD: &'static str = $res_type_keyword;
+                pub const TYPE_KEYWORD: &'static str = $res_type_keyword;
 
                 pub fn from_builder() -> $builder_type {
                     <$builder_type as crate::PrivDefault>::priv_default()
@@ -292,6 +2184,22 @@ pub mod resource {
                     self.0.as_ref().write_resource_segment(w, l)?;
                     Ok(())
                 }
+
+                fn type_keyword(&self) -> Option<&'static str> {
+                    Some($res_type_keyword)
+                }
+
+                fn translatable_texts(&self, l: crate::Lang) -> Vec<(String, String)> {
+                    self.0.as_ref().translatable_texts(l)
+                }
+
+                fn style_conflicts(&self, l: crate::Lang) -> Vec<String> {
+                    self.0.as_ref().style_conflicts(l)
+                }
+
+                fn defined_strings(&self, l: crate::Lang) -> Vec<(crate::Id, String)> {
+                    self.0.as_ref().defined_strings(l)
+                }
             }
         };
     }
@@ -338,6 +2246,25 @@ pub mod resource {
                     self
                 }
             }
+
+            impl crate::ExtraInfoBuilder for $builder_type {
+                fn extra_info(
+                    self,
+                    characteristics: Option<DWORD>,
+                    version: Option<DWORD>,
+                ) -> Self {
+                    <$builder_type>::extra_info(self, characteristics, version)
+                }
+
+                fn lang_specific_extra_info(
+                    self,
+                    lang: crate::Lang,
+                    characteristics: Option<DWORD>,
+                    version: Option<DWORD>,
+                ) -> Self {
+                    <$builder_type>::lang_specific_extra_info(self, lang, characteristics, version)
+                }
+            }
         };
     }
 
@@ -374,6 +2301,25 @@ pub mod resource {
                     self
                 }
             }
+
+            impl crate::ExtraInfoBuilder for $builder_type {
+                fn extra_info(
+                    self,
+                    characteristics: Option<DWORD>,
+                    version: Option<DWORD>,
+                ) -> Self {
+                    <$builder_type>::extra_info(self, characteristics, version)
+                }
+
+                fn lang_specific_extra_info(
+                    self,
+                    lang: crate::Lang,
+                    characteristics: Option<DWORD>,
+                    version: Option<DWORD>,
+                ) -> Self {
+                    <$builder_type>::lang_specific_extra_info(self, lang, characteristics, version)
+                }
+            }
         };
     }
 
@@ -385,6 +2331,18 @@ pub mod resource {
                     $type_name(Rc::new(self.0))
                 }
             }
+
+            impl crate::ResourceBuilder for $builder_type {
+                type Resource = $type_name;
+
+                fn new() -> Self {
+                    <Self as crate::PrivDefault>::priv_default()
+                }
+
+                fn build(self) -> $type_name {
+                    <$builder_type>::build(self)
+                }
+            }
         };
     }
 
@@ -402,7 +2360,7 @@ pub mod resource {
 
     macro_rules! define_builder_or_path_generated_resource {
         ($type_name:ident, $data_type:path, $builder_type:path) => {
-            #[derive(Clone)]
+            #[derive(Clone, PartialEq, Debug)]
             pub struct $type_name(pub(crate) Rc<$data_type>);
 
             impl $type_name {
@@ -415,31 +2373,22 @@ pub mod resource {
                 }
             }
 
-            impl Resource for $type_name {}
-        };
-    }
-
-    macro_rules! unimplemented_resouce_data_write_segment {
-        ($type_name:ident) => {
-            impl $type_name {
-                pub(crate) fn is_missing_for_lang(&self, _l: crate::Lang) -> bool {
-                    true
-                }
-
-                pub(crate) fn write_resource_header_extras(
+            impl Resource for $type_name {
+                fn write_script_segment(
                     &self,
-                    _w: &mut dyn std::io::Write,
-                    _l: crate::Lang,
+                    w: &mut dyn std::io::Write,
+                    l: crate::Lang,
+                    id_or_name: crate::IdOrName,
                 ) -> Result<(), std::io::Error> {
-                    unimplemented!()
+                    self.0.as_ref().write_script_segment(w, l, id_or_name)
                 }
 
-                pub(crate) fn write_resource_segment(
-                    &self,
-                    _w: &mut dyn std::io::Write,
-                    _l: crate::Lang,
-                ) -> Result<(), std::io::Error> {
-                    unimplemented!()
+                fn referenced_files(&self) -> Vec<&Path> {
+                    self.0.as_ref().referenced_files()
+                }
+
+                fn user_defined_type(&self) -> Option<crate::IdOrName> {
+                    self.0.as_ref().type_id.clone()
                 }
             }
         };
@@ -450,35 +2399,149 @@ pub mod resource {
     define_path_only_resource!(Font, "FONT");
     define_path_only_resource!(HTML, "HTML");
     define_path_only_resource!(Icon, "ICON");
-    define_path_only_resource!(MessageTable, "MESSAGETABLE");
 
-    define_builder_generated_resource!(
-        StringTable,
-        crate::string_table::StringTableData,
-        crate::string_table::StringTableBuilder,
-        "STRINGTABLE"
-    );
+    impl HTML {
+        // Writes generated content to OUT_DIR, named after its hash, and
+        // refers to that file. An unchanged file isn't rewritten, so the
+        // resources aren't recompiled for nothing.
+        pub fn from_string(content: impl AsRef<str>) -> Result<Self, std::io::Error> {
+            let path = crate::codegen::out_dir_file("html", "html", content.as_ref().as_bytes())?;
+            Ok(HTML::from_file(path))
+        }
+    }
 
-    define_builder_generated_resource!(
-        Accelerators,
-        crate::accelerators::AcceleratorsData,
-        crate::accelerators::AcceleratorsBuilder,
-        "ACCELERATORS"
-    );
+    // A message table for FormatMessage, from a file compiled by mc.exe or
+    // from messages given to the builder.
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct MessageTable(pub(crate) Rc<crate::message_table::MessageTableData>);
 
-    define_builder_generated_resource!(
-        Menu,
-        crate::menu::MenuData,
-        crate::menu::MenuBuilder,
-        "MENUEX"
-    );
+    impl MessageTable {
+        pub const TYPE_KEYWORD: &'static str = "MESSAGETABLE";
 
-    define_builder_generated_resource!(
-        Dialog,
-        crate::dialog::DialogData,
-        crate::dialog::DialogBuilder,
-        "DIALOGEX"
-    );
+        pub fn from_builder() -> crate::message_table::MessageTableBuilder {
+            <crate::message_table::MessageTableBuilder as crate::PrivDefault>::priv_default()
+        }
+
+        pub fn from_file(path: impl AsRef<Path>) -> Self {
+            create_path_only_resource_from_file(path, MessageTable)
+        }
+
+        // The messages of a parsed .mc file, with the texts of all its languages.
+        pub fn from_mc_file(
+            file: &crate::message_compiler::McFile,
+        ) -> Result<Self, std::io::Error> {
+            use crate::message_table::Severity;
+            let mut builder = MessageTable::from_builder();
+            for message in file.messages.iter() {
+                let severity = match message.severity {
+                    0 => Severity::Success,
+                    1 => Severity::Informational,
+                    2 => Severity::Warning,
+                    3 => Severity::Error,
+                    v => {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!("severity {} of message {} is out of range", v, message.code),
+                        ))
+                    }
+                };
+                let mut text = crate::MultiLangText::empty();
+                for (&lang, lang_text) in message.texts.iter() {
+                    text = text.lang(lang, lang_text.clone());
+                }
+                builder = builder.message(message.code, severity, message.facility, text);
+            }
+            Ok(builder.build())
+        }
+    }
+
+    impl Resource for MessageTable {
+        fn write_script_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+            id_or_name: crate::IdOrName,
+        ) -> Result<(), std::io::Error> {
+            let path = match self.0.as_ref() {
+                crate::message_table::MessageTableData::External(path) => path.to_path_buf(),
+                crate::message_table::MessageTableData::Messages(messages) => {
+                    let data = match crate::message_table::encode(messages, l)? {
+                        Some(data) => data,
+                        None => return Ok(()),
+                    };
+                    crate::codegen::out_dir_file("messages", "bin", &data)?
+                }
+                crate::message_table::MessageTableData::Invalid(error) => {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        error.clone(),
+                    ))
+                }
+            };
+            crate::codegen::write_path_only_resource(w, l, id_or_name, Self::TYPE_KEYWORD, &path)
+        }
+
+        fn referenced_files(&self) -> Vec<&Path> {
+            match self.0.as_ref() {
+                crate::message_table::MessageTableData::External(path) => vec![path.as_ref()],
+                crate::message_table::MessageTableData::Messages(_)
+                | crate::message_table::MessageTableData::Invalid(_) => Vec::new(),
+            }
+        }
+
+        fn type_keyword(&self) -> Option<&'static str> {
+            Some(Self::TYPE_KEYWORD)
+        }
+
+        fn translatable_texts(&self, l: crate::Lang) -> Vec<(String, String)> {
+            match self.0.as_ref() {
+                crate::message_table::MessageTableData::External(_)
+                | crate::message_table::MessageTableData::Invalid(_) => Vec::new(),
+                crate::message_table::MessageTableData::Messages(messages) => messages
+                    .iter()
+                    .filter_map(|message| {
+                        let text = message.text.0.get(l)?;
+                        Some((format!("message 0x{:08X}", message.id), text.to_string()))
+                    })
+                    .collect(),
+            }
+        }
+    }
+
+    // for PlaySound with SND_RESOURCE
+    define_typed_file_resource!(Wave, "WAVE");
+    // for animation controls, Animate_Open with a resource id
+    define_typed_file_resource!(Avi, "AVI");
+    // no API loads these directly, but it is the name loaders commonly expect
+    define_typed_file_resource!(Png, "PNG");
+
+    define_builder_generated_resource!(
+        StringTable,
+        crate::string_table::StringTableData,
+        crate::string_table::StringTableBuilder,
+        "STRINGTABLE"
+    );
+
+    define_builder_generated_resource!(
+        Accelerators,
+        crate::accelerators::AcceleratorsData,
+        crate::accelerators::AcceleratorsBuilder,
+        "ACCELERATORS"
+    );
+
+    define_builder_generated_resource!(
+        Menu,
+        crate::menu::MenuData,
+        crate::menu::MenuBuilder,
+        "MENUEX"
+    );
+
+    define_builder_generated_resource!(
+        Dialog,
+        crate::dialog::DialogData,
+        crate::dialog::DialogBuilder,
+        "DIALOGEX"
+    );
 
     define_builder_generated_resource!(
         VersionInfo,
@@ -500,11 +2563,375 @@ pub mod resource {
         crate::user_defined::UserDefinedBuilder
     );
 
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct Precompiled(Rc<crate::res_file::ResEntry>);
+
+    impl Precompiled {
+        pub fn from_entry(entry: crate::res_file::ResEntry) -> Self {
+            Precompiled(Rc::new(entry))
+        }
+    }
+
+    impl Resource for Precompiled {
+        fn write_script_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+            id_or_name: crate::IdOrName,
+        ) -> Result<(), std::io::Error> {
+            let entry = self.0.as_ref();
+            crate::codegen::write_user_defined_resource_header(w, l, id_or_name, &entry.type_id)?;
+            crate::codegen::write_extra_info(
+                w,
+                Some(&crate::ExtraInfo {
+                    characteristics: Some(entry.characteristics),
+                    version: Some(entry.version),
+                }),
+            )?;
+            write!(w, "\n")?;
+            crate::codegen::write_raw_data_block(w, &entry.data)?;
+            Ok(())
+        }
+
+        fn user_defined_type(&self) -> Option<crate::IdOrName> {
+            Some(self.0.type_id.clone())
+        }
+    }
+
+    // An ATL registry script (.rgs), stored under the "REGISTRY" type for COM
+    // servers that register themselves from their own resources.
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct Registry(pub(crate) Rc<crate::registry::RegistryData>);
+
+    impl Registry {
+        pub const TYPE_NAME: &'static str = "REGISTRY";
+
+        pub fn from_builder() -> crate::registry::RegistryBuilder {
+            <crate::registry::RegistryBuilder as crate::PrivDefault>::priv_default()
+        }
+
+        pub fn from_file(path: impl AsRef<Path>) -> Self {
+            create_path_only_resource_from_file(path, Registry)
+        }
+    }
+
+    impl Resource for Registry {
+        fn write_script_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+            id_or_name: crate::IdOrName,
+        ) -> Result<(), std::io::Error> {
+            let type_id = crate::IdOrName::name_static(Self::TYPE_NAME);
+            match self.0.as_ref() {
+                crate::registry::RegistryData::External(path) => {
+                    crate::codegen::write_user_defined_file_resource(
+                        w, l, id_or_name, &type_id, path,
+                    )
+                }
+                crate::registry::RegistryData::Script(script) => {
+                    crate::codegen::write_user_defined_resource_header(w, l, id_or_name, &type_id)?;
+                    write!(w, "\n")?;
+                    crate::codegen::write_raw_data_block(w, script.as_bytes())
+                }
+            }
+        }
+
+        fn referenced_files(&self) -> Vec<&Path> {
+            match self.0.as_ref() {
+                crate::registry::RegistryData::External(path) => vec![path.as_ref()],
+                crate::registry::RegistryData::Script(_) => Vec::new(),
+            }
+        }
+
+        fn user_defined_type(&self) -> Option<crate::IdOrName> {
+            Some(crate::IdOrName::name_static(Self::TYPE_NAME))
+        }
+    }
+
+    // An application manifest, stored under RT_MANIFEST. The loader reads the
+    // one with id 1 for executables and id 2 for DLLs, e.g.
+    // `.resource(Manifest::CREATEPROCESS_ID, Manifest::from_file("app.manifest"))`.
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct Manifest(Rc<CowPath>);
+
+    impl Manifest {
+        pub const TYPE_ID: crate::WORD = 24;
+
+        pub const CREATEPROCESS_ID: crate::WORD = 1;
+        pub const ISOLATIONAWARE_ID: crate::WORD = 2;
+        // for DLLs that are only loaded with LoadLibrary
+        pub const ISOLATIONAWARE_NOSTATICIMPORT_ID: crate::WORD = 3;
+
+        pub fn from_file(path: impl AsRef<Path>) -> Self {
+            create_path_only_resource_from_file(path, Manifest)
+        }
+    }
+
+    impl Resource for Manifest {
+        fn write_script_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+            id_or_name: crate::IdOrName,
+        ) -> Result<(), std::io::Error> {
+            let type_id = crate::IdOrName::from(Self::TYPE_ID);
+            crate::codegen::write_user_defined_file_resource(w, l, id_or_name, &type_id, &self.0)
+        }
+
+        fn referenced_files(&self) -> Vec<&Path> {
+            vec![self.0.as_ref()]
+        }
+
+        fn user_defined_type(&self) -> Option<crate::IdOrName> {
+            Some(crate::IdOrName::from(Self::TYPE_ID))
+        }
+    }
+
+    // Strings MFC adds to a dialog's combo boxes and list boxes when it
+    // initializes the dialog; registered under the dialog's id. The dialog
+    // manager itself ignores this resource.
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct DlgInit(pub(crate) Rc<crate::dlg_init::DlgInitData>);
+
+    impl DlgInit {
+        pub const TYPE_ID: crate::WORD = 240;
+
+        pub fn from_builder() -> crate::dlg_init::DlgInitBuilder {
+            <crate::dlg_init::DlgInitBuilder as crate::PrivDefault>::priv_default()
+        }
+    }
+
+    impl Resource for DlgInit {
+        fn write_script_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+            id_or_name: crate::IdOrName,
+        ) -> Result<(), std::io::Error> {
+            let data = self.0.as_ref();
+            if data.is_missing_for_lang(l) {
+                return Ok(());
+            }
+            let type_id = crate::IdOrName::from(Self::TYPE_ID);
+            crate::codegen::write_user_defined_resource_header(w, l, id_or_name, &type_id)?;
+            write!(w, "\n")?;
+            crate::codegen::write_raw_data_block(w, &data.encode(l)?)
+        }
+
+        fn user_defined_type(&self) -> Option<crate::IdOrName> {
+            Some(crate::IdOrName::from(Self::TYPE_ID))
+        }
+
+        fn translatable_texts(&self, l: crate::Lang) -> Vec<(String, String)> {
+            self.0.translatable_texts(l)
+        }
+    }
+
+    // Script text written out verbatim, after a LANGUAGE statement for the
+    // language being written, for constructs there is no typed API for yet.
+    // The id it is registered under is not used; the text carries its own.
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct RawRc(Rc<crate::OptionLangSpecific<crate::CowStr>>);
+
+    impl RawRc {
+        pub fn new(text: impl Into<crate::CowStr>) -> Self {
+            let mut texts = crate::OptionLangSpecific::default();
+            texts.insert_universal(text.into());
+            RawRc(Rc::new(texts))
+        }
+
+        // Text for `lang` only, used instead of the text given to `new`.
+        // Without text for a language nothing is written for it.
+        pub fn lang_specific(lang: crate::Lang, text: impl Into<crate::CowStr>) -> Self {
+            RawRc(Rc::new(crate::OptionLangSpecific::default())).and_lang_specific(lang, text)
+        }
+
+        pub fn and_lang_specific(
+            mut self,
+            lang: crate::Lang,
+            text: impl Into<crate::CowStr>,
+        ) -> Self {
+            Rc::make_mut(&mut self.0).insert_lang_specific(lang, text.into());
+            self
+        }
+    }
+
+    impl std::str::FromStr for RawRc {
+        type Err = std::convert::Infallible;
+
+        fn from_str(text: &str) -> Result<Self, Self::Err> {
+            Ok(RawRc::new(text.to_owned()))
+        }
+    }
+
+    impl Resource for RawRc {
+        fn write_script_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+            _id_or_name: crate::IdOrName,
+        ) -> Result<(), std::io::Error> {
+            let text = match self.0.get(l) {
+                Some(text) => text,
+                None => return Ok(()),
+            };
+            write!(w, "LANGUAGE 0x{:x}, 0x{:x}\n", l.0, l.1)?;
+            w.write_all(text.as_bytes())?;
+            if !text.ends_with('\n') {
+                write!(w, "\n")?;
+            }
+            Ok(())
+        }
+    }
+
+    // The preprocessor condition a Conditional resource is written under.
+    // Conditions are checked when they are built, so that writing one can't
+    // break the script.
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    pub struct Condition(ConditionKind);
+
+    #[derive(Clone, PartialEq, Eq, Hash, Debug)]
+    enum ConditionKind {
+        Defined(crate::CowStr),
+        NotDefined(crate::CowStr),
+        // an `#if` expression, e.g. "WINVER >= 0x0A00"
+        If(crate::CowStr),
+    }
+
+    impl Condition {
+        pub fn defined(name: impl Into<crate::CowStr>) -> Result<Self, std::io::Error> {
+            let name = name.into();
+            Self::check_name(&name)?;
+            Ok(Condition(ConditionKind::Defined(name)))
+        }
+
+        pub fn not_defined(name: impl Into<crate::CowStr>) -> Result<Self, std::io::Error> {
+            let name = name.into();
+            Self::check_name(&name)?;
+            Ok(Condition(ConditionKind::NotDefined(name)))
+        }
+
+        pub fn expr(expr: impl Into<crate::CowStr>) -> Result<Self, std::io::Error> {
+            let expr = expr.into();
+            if expr.trim().is_empty() || expr.contains(['\n', '\r']) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("`{}` is not a one-line #if expression", expr.escape_debug()),
+                ));
+            }
+            Ok(Condition(ConditionKind::If(expr)))
+        }
+
+        fn check_name(name: &str) -> Result<(), std::io::Error> {
+            let valid = name.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+                && name
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+            if !valid {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("`{}` is not a preprocessor symbol", name.escape_debug()),
+                ));
+            }
+            Ok(())
+        }
+    }
+
+    // Wraps a resource in `#ifdef`/`#ifndef`/`#if` ... `#endif`, so the same
+    // generated script can serve several configurations, e.g.
+    // `.resource(100u16, Conditional::ifdef("_DEBUG", console_dialog)?)`.
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct Conditional<R> {
+        condition: Condition,
+        resource: R,
+    }
+
+    impl<R: Resource> Conditional<R> {
+        pub fn new(condition: Condition, resource: R) -> Self {
+            Conditional {
+                condition,
+                resource,
+            }
+        }
+
+        pub fn ifdef(name: impl Into<crate::CowStr>, resource: R) -> Result<Self, std::io::Error> {
+            Ok(Self::new(Condition::defined(name)?, resource))
+        }
+
+        pub fn ifndef(name: impl Into<crate::CowStr>, resource: R) -> Result<Self, std::io::Error> {
+            Ok(Self::new(Condition::not_defined(name)?, resource))
+        }
+    }
+
+    impl<R: Resource> Resource for Conditional<R> {
+        fn write_script_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+            id_or_name: crate::IdOrName,
+        ) -> Result<(), std::io::Error> {
+            let mut segment = Vec::new();
+            self.resource
+                .write_script_segment(&mut segment, l, id_or_name)?;
+            if segment.is_empty() {
+                return Ok(());
+            }
+            write!(w, "{}\n", self.condition)?;
+            w.write_all(&segment)?;
+            write!(w, "#endif\n")?;
+            Ok(())
+        }
+
+        fn referenced_files(&self) -> Vec<&Path> {
+            self.resource.referenced_files()
+        }
+
+        fn type_keyword(&self) -> Option<&'static str> {
+            self.resource.type_keyword()
+        }
+
+        fn user_defined_type(&self) -> Option<crate::IdOrName> {
+            self.resource.user_defined_type()
+        }
+
+        fn kind(&self) -> crate::ResourceKind {
+            self.resource.kind()
+        }
+
+        fn translatable_texts(&self, l: crate::Lang) -> Vec<(String, String)> {
+            self.resource.translatable_texts(l)
+        }
+
+        fn style_conflicts(&self, l: crate::Lang) -> Vec<String> {
+            self.resource.style_conflicts(l)
+        }
+
+        fn defined_strings(&self, l: crate::Lang) -> Vec<(crate::Id, String)> {
+            self.resource.defined_strings(l)
+        }
+
+        fn condition(&self) -> Option<&Condition> {
+            Some(&self.condition)
+        }
+    }
+
+    impl std::fmt::Display for Condition {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            match &self.0 {
+                ConditionKind::Defined(name) => write!(f, "#ifdef {}", name),
+                ConditionKind::NotDefined(name) => write!(f, "#ifndef {}", name),
+                ConditionKind::If(expr) => write!(f, "#if {}", expr),
+            }
+        }
+    }
+
     // we won't support:
     // obsolete items: plugplay vxd
     // special items: textinclude typelib
 }
 
+#[derive(Clone, PartialEq, Debug)]
 struct OptionLangSpecific<T>(BTreeMap<Option<Lang>, T>);
 
 impl<T> OptionLangSpecific<T> {
@@ -547,6 +2974,7 @@ impl<T> Default for OptionLangSpecific<T> {
     }
 }
 
+#[derive(PartialEq, Debug)]
 struct VecLangSpecific<T>(Vec<(Option<Lang>, T)>);
 
 impl<T> Default for VecLangSpecific<T> {
@@ -567,18 +2995,18 @@ impl<T> VecLangSpecific<T> {
     fn iter(&self, lang: Lang) -> impl Iterator<Item = &T> {
         self.0
             .iter()
-            .filter(move |&&(ref iter_lang, ref _iter_val)| {
-                iter_lang == &None || iter_lang == &Some(lang)
-            })
-            .map(|&(ref _iter_lang, ref iter_val)| iter_val)
+            .filter(move |(iter_lang, _iter_val)| iter_lang.is_none() || *iter_lang == Some(lang))
+            .map(|(_iter_lang, iter_val)| iter_val)
     }
 }
 
+#[derive(Clone, PartialEq, Debug)]
 pub struct ExtraInfo {
     pub characteristics: Option<DWORD>,
     pub version: Option<DWORD>,
 }
 
+#[derive(PartialEq, Debug)]
 pub struct MultiLangText(OptionLangSpecific<CowStr>);
 
 impl MultiLangText {
@@ -586,8 +3014,19 @@ impl MultiLangText {
         MultiLangText(OptionLangSpecific::default())
     }
 
+    // The languages among `languages` this text has no value for; always empty
+    // if it has a universal value.
+    pub fn missing_languages(&self, languages: &[Lang]) -> Vec<Lang> {
+        languages
+            .iter()
+            .cloned()
+            .filter(|&lang| self.0.get(lang).is_none())
+            .collect()
+    }
+
     pub fn lang(mut self, lang: Lang, str: impl Into<CowStr>) -> Self {
-        self.0.insert_lang_specific(lang, str.into());
+        self.0
+            .insert_lang_specific(lang, interner::intern(str.into()));
         self
     }
 }
@@ -598,28 +3037,123 @@ where
 {
     fn from(v: T) -> Self {
         let mut r = Self::empty();
-        r.0.insert_universal(v.into());
+        r.0.insert_universal(interner::intern(v.into()));
         r
     }
 }
 
+// Optional interning of the texts handed to builders. Once enabled, each
+// distinct owned string is leaked once and later copies borrow it, so imports
+// repeating the same captions across hundreds of thousands of entries stay
+// small. Meant for build scripts, which exit soon after generating.
+pub mod interner {
+    use crate::CowStr;
+    use std::borrow::Cow;
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+
+    thread_local! {
+        static INTERNED: RefCell<Option<HashSet<&'static str>>> = const { RefCell::new(None) };
+    }
+
+    // Enables interning on the current thread, for builders used afterwards.
+    pub fn enable() {
+        INTERNED.with(|i| {
+            i.borrow_mut().get_or_insert_with(HashSet::new);
+        })
+    }
+
+    pub fn is_enabled() -> bool {
+        INTERNED.with(|i| i.borrow().is_some())
+    }
+
+    pub(crate) fn intern(string: CowStr) -> CowStr {
+        let owned = match string {
+            Cow::Borrowed(_) => return string,
+            Cow::Owned(owned) => owned,
+        };
+        INTERNED.with(|i| match i.borrow_mut().as_mut() {
+            None => Cow::Owned(owned),
+            Some(interned) => {
+                if let Some(&existing) = interned.get(owned.as_str()) {
+                    return Cow::Borrowed(existing);
+                }
+                let leaked: &'static str = Box::leak(owned.into_boxed_str());
+                interned.insert(leaked);
+                Cow::Borrowed(leaked)
+            }
+        })
+    }
+}
+
 trait PrivDefault {
     fn priv_default() -> Self;
 }
 
+// Implemented by every resource builder, for helpers generic over the kind of
+// resource being built. Same as `from_builder()` and the inherent `build`.
+pub trait ResourceBuilder: Sized {
+    type Resource: Resource + Clone;
+
+    fn new() -> Self;
+
+    fn build(self) -> Self::Resource;
+}
+
+// Builders of resources whose script statement takes CHARACTERISTICS and
+// VERSION.
+pub trait ExtraInfoBuilder: ResourceBuilder {
+    fn extra_info(self, characteristics: Option<DWORD>, version: Option<DWORD>) -> Self;
+
+    fn lang_specific_extra_info(
+        self,
+        lang: Lang,
+        characteristics: Option<DWORD>,
+        version: Option<DWORD>,
+    ) -> Self;
+}
+
+#[allow(clippy::write_with_newline)]
 pub mod string_table {
-    use crate::{ExtraInfo, Id, Lang, OptionLangSpecific};
-    use winapi::shared::minwindef::DWORD;
+    use crate::sys::minwindef::DWORD;
+    use crate::{interner, CowStr, ExtraInfo, Id, Lang, OptionLangSpecific};
 
-    #[derive(Default)]
+    #[derive(Default, PartialEq, Debug)]
     struct StringTableItems {
         extra_info: Option<ExtraInfo>,
-        strings: Vec<(Id, String)>,
+        strings: Vec<(Id, CowStr)>,
+        // names given with named_string, for generate_key_module
+        symbols: Vec<(Id, String)>,
+        // base ids of plural strings, for generate_plural_module
+        plurals: Vec<Id>,
+        // the first plural string that couldn't be added, an error once the
+        // table is written
+        plural_error: Option<String>,
+    }
+
+    // The CLDR plural categories. Win32 string tables have no plurals, so a
+    // plural string keeps each form as a string of its own, at the base id
+    // plus the category's offset.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    pub enum PluralCategory {
+        Zero,
+        One,
+        Two,
+        Few,
+        Many,
+        Other,
+    }
+
+    impl PluralCategory {
+        pub fn offset(self) -> u16 {
+            self as u16
+        }
     }
 
-    #[derive(Default)]
+    #[derive(Default, PartialEq, Debug)]
     pub(crate) struct StringTableData(OptionLangSpecific<StringTableItems>);
 
+    #[derive(PartialEq, Debug)]
     pub struct StringTableBuilder(StringTableData);
 
     builder_implement_priv_default!(StringTableBuilder);
@@ -629,7 +3163,7 @@ pub mod string_table {
     impl StringTableBuilder {
         pub fn string(mut self, id: impl Into<Id>, string: impl AsRef<str>) -> Self {
             let id = id.into();
-            let string = string.as_ref().to_owned();
+            let string = interner::intern(CowStr::Owned(string.as_ref().to_owned()));
             let universal_items = (self.0).0.access_universal_mut();
             universal_items.strings.push((id, string));
             self
@@ -642,27 +3176,227 @@ pub mod string_table {
             string: impl AsRef<str>,
         ) -> Self {
             let id = id.into();
-            let string = string.as_ref().to_owned();
+            let string = interner::intern(CowStr::Owned(string.as_ref().to_owned()));
             let lang_items = (self.0).0.access_lang_specific_mut(lang);
             lang_items.strings.push((id, string));
             self
         }
-    }
 
-    unimplemented_resouce_data_write_segment!(StringTableData);
-}
-
-pub mod accelerators {
-    use crate::{ExtraInfo, Id, Lang, OptionLangSpecific};
-    use std::fmt;
-    use winapi::ctypes::c_int;
-    use winapi::shared::minwindef::DWORD;
-    use winapi::um::winuser;
+        // Like `string`, also naming the id `symbol` in the enum written by
+        // StringTable::generate_key_module.
+        pub fn named_string(
+            mut self,
+            symbol: impl Into<String>,
+            id: impl Into<Id>,
+            string: impl AsRef<str>,
+        ) -> Self {
+            let id = id.into();
+            let universal_items = (self.0).0.access_universal_mut();
+            universal_items.symbols.push((id.clone(), symbol.into()));
+            self.string(id, string)
+        }
 
-    #[derive(Clone, Copy)]
-    pub struct ASCIIKey(u8);
+        // Adds the forms of a plural string, e.g.
+        // `plural_string(10, &[(One, "{} file"), (Other, "{} files")])`; the
+        // `Other` form is required, as the fallback for the rest, and writing
+        // the table fails without it.
+        pub fn plural_string(
+            self,
+            base_id: impl Into<Id>,
+            forms: &[(PluralCategory, &str)],
+        ) -> Self {
+            self.plural_string_for(None, base_id.into(), forms)
+        }
 
-    impl ASCIIKey {
+        pub fn lang_specific_plural_string(
+            self,
+            lang: Lang,
+            base_id: impl Into<Id>,
+            forms: &[(PluralCategory, &str)],
+        ) -> Self {
+            self.plural_string_for(Some(lang), base_id.into(), forms)
+        }
+
+        fn plural_string_for(
+            mut self,
+            lang: Option<Lang>,
+            base_id: Id,
+            forms: &[(PluralCategory, &str)],
+        ) -> Self {
+            let error = if !forms
+                .iter()
+                .any(|(category, _)| *category == PluralCategory::Other)
+            {
+                Some(format!("plural string {} has no Other form", base_id))
+            } else if base_id
+                .0
+                .checked_add(PluralCategory::Other.offset())
+                .is_none()
+            {
+                Some(format!(
+                    "plural string {} has no room for its forms",
+                    base_id
+                ))
+            } else {
+                None
+            };
+            let items = match lang {
+                Some(lang) => (self.0).0.access_lang_specific_mut(lang),
+                None => (self.0).0.access_universal_mut(),
+            };
+            if let Some(error) = error {
+                items.plural_error.get_or_insert(error);
+                return self;
+            }
+            items.plurals.push(base_id.clone());
+            for &(category, form) in forms {
+                let id = Id(base_id.0 + category.offset());
+                let form = interner::intern(CowStr::Owned(form.to_owned()));
+                items.strings.push((id, form));
+            }
+            self
+        }
+    }
+
+    impl crate::resource::StringTable {
+        // Writes a Rust module with a `name` enum of the table's string ids, so
+        // code referring to a removed string no longer compiles. Ids named
+        // with named_string get a variant after their symbol (`IDS_FILE_NOT_FOUND`
+        // or `FileNotFound` become `FileNotFound`), the others `String{id}`.
+        pub fn generate_key_module(
+            &self,
+            name: &str,
+            path: &std::path::Path,
+        ) -> Result<(), std::io::Error> {
+            let mut keys: std::collections::BTreeMap<Id, Option<&str>> = Default::default();
+            for items in (self.0).0 .0.values() {
+                for (id, _) in items.strings.iter() {
+                    keys.entry(id.clone()).or_default();
+                }
+            }
+            for items in (self.0).0 .0.values() {
+                for (id, symbol) in items.symbols.iter() {
+                    keys.insert(id.clone(), Some(symbol));
+                }
+            }
+            let keys: Vec<(Id, Option<&str>)> = keys.into_iter().collect();
+            crate::codegen::write_file_atomically(path, |w| {
+                crate::codegen::write_string_keys_module(w, name, &keys)
+            })
+        }
+
+        // Writes a Rust module with a `name` enum of the table's plural
+        // strings, whose `load(n)` picks the form for `n` under the plural rules
+        // of the thread's UI language.
+        pub fn generate_plural_module(
+            &self,
+            name: &str,
+            path: &std::path::Path,
+        ) -> Result<(), std::io::Error> {
+            let mut base_ids = std::collections::BTreeSet::new();
+            for items in (self.0).0 .0.values() {
+                base_ids.extend(items.plurals.iter().cloned());
+            }
+            let base_ids: Vec<Id> = base_ids.into_iter().collect();
+            crate::codegen::write_file_atomically(path, |w| {
+                crate::codegen::write_plural_module(w, name, &base_ids)
+            })
+        }
+    }
+
+    impl StringTableData {
+        // Universal strings, each replaced by the language specific string with
+        // the same id if there is one, ordered by id.
+        fn strings_for_lang(&self, l: Lang) -> Vec<(&Id, &str)> {
+            let mut strings = std::collections::BTreeMap::new();
+            for key in [None, Some(l)] {
+                if let Some(items) = (self.0).0.get(&key) {
+                    for (id, text) in items.strings.iter() {
+                        strings.insert(id, text.as_ref());
+                    }
+                }
+            }
+            strings.into_iter().collect()
+        }
+
+        fn plural_error(&self, l: Lang) -> Option<&String> {
+            [None, Some(l)]
+                .iter()
+                .find_map(|key| (self.0).0.get(key).and_then(|i| i.plural_error.as_ref()))
+        }
+
+        pub(crate) fn is_missing_for_lang(&self, l: Lang) -> bool {
+            self.strings_for_lang(l).is_empty() && self.plural_error(l).is_none()
+        }
+
+        pub(crate) fn write_resource_header_extras(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: Lang,
+        ) -> Result<(), std::io::Error> {
+            if let Some(error) = self.plural_error(l) {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    error.clone(),
+                ));
+            }
+            let extra_info = [Some(l), None]
+                .iter()
+                .find_map(|key| (self.0).0.get(key).and_then(|i| i.extra_info.as_ref()));
+            crate::codegen::write_extra_info(w, extra_info)?;
+            Ok(())
+        }
+
+        pub(crate) fn write_resource_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: Lang,
+        ) -> Result<(), std::io::Error> {
+            write!(w, "{{\n")?;
+            for (id, text) in self.strings_for_lang(l) {
+                write!(w, "\t")?;
+                crate::codegen::write_id(w, id)?;
+                write!(w, ", ")?;
+                crate::codegen::write_text(w, text)?;
+                write!(w, "\n")?;
+            }
+            write!(w, "}}\n")?;
+            Ok(())
+        }
+
+        pub(crate) fn translatable_texts(&self, l: Lang) -> Vec<(String, String)> {
+            self.strings_for_lang(l)
+                .into_iter()
+                .map(|(id, text)| (format!("string {}", id), text.to_owned()))
+                .collect()
+        }
+
+        pub(crate) fn style_conflicts(&self, _l: Lang) -> Vec<String> {
+            Vec::new()
+        }
+
+        pub(crate) fn defined_strings(&self, l: Lang) -> Vec<(Id, String)> {
+            self.strings_for_lang(l)
+                .into_iter()
+                .map(|(id, text)| (id.clone(), text.to_owned()))
+                .collect()
+        }
+    }
+}
+
+#[allow(clippy::write_with_newline)]
+pub mod accelerators {
+    use crate::sys::ctypes::c_int;
+    use crate::sys::minwindef::DWORD;
+    use crate::sys::winuser;
+    use crate::{ExtraInfo, Id, Lang, OptionLangSpecific};
+    use std::fmt;
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    pub struct ASCIIKey(u8);
+
+    impl ASCIIKey {
+        #[allow(clippy::self_named_constructors)]
         pub fn ascii_key(v: u8) -> ASCIIKey {
             match v {
                 32u8..=126u8 => Some(ASCIIKey(v)),
@@ -679,7 +3413,7 @@ pub mod accelerators {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct VirtKey(c_int);
 
     impl VirtKey {
@@ -883,16 +3617,12 @@ pub mod accelerators {
 
     impl fmt::Display for VirtKey {
         fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-            match self.0 {
-                c => {
-                    write!(f, "{}", c).map_err(|_| fmt::Error)?;
-                }
-            }
+            write!(f, "{}", self.0).map_err(|_| fmt::Error)?;
             Ok(())
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub enum Modifier {
         None,
         Ctrl,
@@ -921,7 +3651,7 @@ pub mod accelerators {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub enum ASCIIModifier {
         None,
         Ctrl,
@@ -942,9 +3672,9 @@ pub mod accelerators {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     enum Key {
-        ASCII {
+        Ascii {
             ascii_key: ASCIIKey,
             modifier: ASCIIModifier,
         },
@@ -954,7 +3684,7 @@ pub mod accelerators {
         },
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct Event {
         key: Key,
         noinvert: bool,
@@ -970,7 +3700,7 @@ pub mod accelerators {
 
         pub const fn ascii_key_event(ascii_key: ASCIIKey, modifier: ASCIIModifier) -> Self {
             Event {
-                key: Key::ASCII {
+                key: Key::Ascii {
                     ascii_key,
                     modifier,
                 },
@@ -985,15 +3715,111 @@ pub mod accelerators {
         }
     }
 
-    #[derive(Default)]
+    impl VirtKey {
+        // Key names as shown after a tab in menu items, e.g. "O", "F5", "Del".
+        fn from_shortcut_name(name: &str) -> Option<VirtKey> {
+            let mut chars = name.chars();
+            if let (Some(ch), None) = (chars.next(), chars.next()) {
+                if ch.is_ascii_alphanumeric() {
+                    return Some(VirtKey(ch.to_ascii_uppercase() as c_int));
+                }
+            }
+            if let Some(n) = name
+                .strip_prefix(['F', 'f'])
+                .and_then(|n| n.parse::<c_int>().ok())
+            {
+                return match n {
+                    1..=24 => Some(VirtKey(winuser::VK_F1 + n - 1)),
+                    _ => None,
+                };
+            }
+            let key = match name.to_ascii_lowercase().as_str() {
+                "+" | "plus" => VirtKey::OEM_PLUS,
+                "-" | "minus" => VirtKey::OEM_MINUS,
+                "," | "comma" => VirtKey::OEM_COMMA,
+                "." | "period" => VirtKey::OEM_PERIOD,
+                "ins" | "insert" => VirtKey::INSERT,
+                "del" | "delete" => VirtKey::DELETE,
+                "home" => VirtKey::HOME,
+                "end" => VirtKey::END,
+                "pgup" | "pageup" => VirtKey::PRIOR,
+                "pgdn" | "pagedown" => VirtKey::NEXT,
+                "left" => VirtKey::LEFT,
+                "right" => VirtKey::RIGHT,
+                "up" => VirtKey::UP,
+                "down" => VirtKey::DOWN,
+                "esc" | "escape" => VirtKey::ESCAPE,
+                "enter" | "return" => VirtKey::RETURN,
+                "tab" => VirtKey::TAB,
+                "space" => VirtKey::SPACE,
+                "bksp" | "backspace" => VirtKey::BACK,
+                "pause" | "break" => VirtKey::PAUSE,
+                _ => return None,
+            };
+            Some(key)
+        }
+    }
+
+    // Parses shortcuts the way menus spell them: modifiers and a key joined by
+    // `+`, as in "Ctrl+Shift+S", "Alt+F4" or "Ctrl++". The key always becomes a
+    // virtual key, and like any VIRTKEY accelerator it only fires with exactly
+    // the modifiers named held: "Ctrl+O" doesn't fire with Shift down.
+    impl std::str::FromStr for Event {
+        type Err = std::io::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            let invalid = || {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("unrecognized shortcut `{}`", s),
+                )
+            };
+            let shortcut = s.trim();
+            let (modifiers, key) = if shortcut == "+" {
+                ("", "+")
+            } else if let Some(modifiers) = shortcut.strip_suffix("++") {
+                (modifiers, "+")
+            } else {
+                match shortcut.rsplit_once('+') {
+                    Some((modifiers, key)) => (modifiers, key),
+                    None => ("", shortcut),
+                }
+            };
+            let (mut ctrl, mut alt, mut shift) = (false, false, false);
+            for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+                let flag = match modifier.trim().to_ascii_lowercase().as_str() {
+                    "ctrl" | "control" => &mut ctrl,
+                    "alt" => &mut alt,
+                    "shift" => &mut shift,
+                    _ => return Err(invalid()),
+                };
+                *flag = true;
+            }
+            let virt_key = VirtKey::from_shortcut_name(key.trim()).ok_or_else(invalid)?;
+            let modifier = match (ctrl, alt, shift) {
+                (false, false, false) => Modifier::None,
+                (true, false, false) => Modifier::Ctrl,
+                (false, true, false) => Modifier::Alt,
+                (false, false, true) => Modifier::Shift,
+                (true, true, false) => Modifier::CtrlAlt,
+                (true, false, true) => Modifier::CtrlShift,
+                (false, true, true) => Modifier::AltShift,
+                (true, true, true) => Modifier::CtrlAltShift,
+            };
+            Ok(Event::virt_key_event(virt_key, modifier))
+        }
+    }
+
+    #[derive(Default, PartialEq, Debug)]
     struct AcceleratorsItems {
         extra_info: Option<ExtraInfo>,
         events: Vec<(Id, Event)>,
     }
 
-    #[derive(Default)]
+    #[derive(Default, PartialEq, Debug)]
     pub(crate) struct AcceleratorsData(OptionLangSpecific<AcceleratorsItems>);
 
+    #[derive(PartialEq, Debug)]
     pub struct AcceleratorsBuilder(AcceleratorsData);
 
     builder_implement_priv_default!(AcceleratorsBuilder);
@@ -1016,11 +3842,48 @@ pub mod accelerators {
         }
     }
 
+    impl crate::resource::Accelerators {
+        // Writes a Rust module with a `{name}Command` enum of the table's command
+        // ids and a `{name}Accelerators` wrapper that loads the table registered
+        // as `id_or_name` and runs TranslateAcceleratorW in the message loop.
+        pub fn generate_runtime_module(
+            &self,
+            name: &str,
+            id_or_name: impl Into<crate::IdOrName>,
+            path: &std::path::Path,
+        ) -> Result<(), std::io::Error> {
+            let mut ids: Vec<Id> = (self.0)
+                .0
+                 .0
+                .values()
+                .flat_map(|items| items.events.iter().map(|(id, _)| id.clone()))
+                .collect();
+            ids.sort();
+            ids.dedup();
+            let id_or_name = id_or_name.into();
+            crate::codegen::write_file_atomically(path, |w| {
+                crate::codegen::write_accelerators_runtime_module(w, name, &id_or_name, &ids)
+            })
+        }
+    }
+
     impl AcceleratorsData {
         pub(crate) fn is_missing_for_lang(&self, l: crate::Lang) -> bool {
             self.0.get(l).is_none()
         }
 
+        pub(crate) fn translatable_texts(&self, _l: crate::Lang) -> Vec<(String, String)> {
+            Vec::new()
+        }
+
+        pub(crate) fn style_conflicts(&self, _l: crate::Lang) -> Vec<String> {
+            Vec::new()
+        }
+
+        pub(crate) fn defined_strings(&self, _l: crate::Lang) -> Vec<(crate::Id, String)> {
+            Vec::new()
+        }
+
         pub(crate) fn write_resource_header_extras(
             &self,
             w: &mut dyn std::io::Write,
@@ -1041,7 +3904,7 @@ pub mod accelerators {
             for (id, event) in items.events.iter() {
                 let noinvert = if event.noinvert { ", NOINVERT" } else { "" };
                 match event.key {
-                    Key::ASCII {
+                    Key::Ascii {
                         ascii_key,
                         modifier,
                     } => {
@@ -1066,14 +3929,15 @@ pub mod accelerators {
     }
 }
 
+#[allow(clippy::write_with_newline)]
 pub mod menu {
+    use crate::sys::ctypes::c_int;
+    use crate::sys::minwindef::UINT;
+    use crate::sys::winuser;
     use crate::MultiLangText;
     use crate::{CowStr, Id, OptionLangSpecific};
-    use winapi::ctypes::c_int;
-    use winapi::shared::minwindef::UINT;
-    use winapi::um::winuser;
 
-    #[derive(Clone, Copy, Default, PartialEq)]
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
     pub struct MenuType(UINT);
 
     impl MenuType {
@@ -1090,7 +3954,7 @@ pub mod menu {
 
     bitflags_bitor_method!(MenuType);
 
-    #[derive(Clone, Copy, Default, PartialEq)]
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
     pub struct MenuState(UINT);
 
     impl MenuState {
@@ -1106,12 +3970,13 @@ pub mod menu {
 
     bitflags_bitor_method!(MenuState);
 
-    #[derive(Default)]
+    #[derive(Default, PartialEq, Debug)]
     struct PopupData {
         help_id: Option<c_int>,
         items: Vec<MenuItem>,
     }
 
+    #[derive(PartialEq, Debug)]
     struct MenuItem {
         id: Option<Id>,
         text: OptionLangSpecific<CowStr>,
@@ -1120,9 +3985,10 @@ pub mod menu {
         popup: Option<PopupData>,
     }
 
-    #[derive(Default)]
+    #[derive(Default, PartialEq, Debug)]
     pub(crate) struct MenuData(Vec<MenuItem>);
 
+    #[derive(PartialEq, Debug)]
     pub struct MenuBuilder(MenuData);
 
     builder_implement_priv_default!(MenuBuilder);
@@ -1147,6 +4013,7 @@ pub mod menu {
         }
     }
 
+    #[derive(PartialEq, Debug)]
     pub struct PopupBuilder(PopupData);
     builder_implement_priv_default!(PopupBuilder);
 
@@ -1252,6 +4119,68 @@ pub mod menu {
 
     use std::io::Error as IOError;
 
+    impl crate::resource::Menu {
+        // An accelerator table for the shortcuts items show after a tab, as in
+        // "&Open\tCtrl+O", so the menu stays the one place they are listed.
+        // Languages with texts of their own get a table of their own.
+        pub fn shortcut_accelerators(&self) -> Result<crate::resource::Accelerators, IOError> {
+            fn collect_languages(items: &[MenuItem], languages: &mut Vec<crate::Lang>) {
+                for item in items {
+                    languages.extend(item.text.0.keys().flatten());
+                    if let Some(popup) = &item.popup {
+                        collect_languages(&popup.items, languages);
+                    }
+                }
+            }
+            fn collect_shortcuts(
+                items: &[MenuItem],
+                lang: Option<crate::Lang>,
+                shortcuts: &mut Vec<(Id, crate::accelerators::Event)>,
+            ) -> Result<(), IOError> {
+                for item in items {
+                    let text = match lang {
+                        Some(lang) => item.text.get(lang),
+                        None => item.text.0.get(&None),
+                    };
+                    if let (Some(id), Some(text)) = (&item.id, text) {
+                        if let Some((_, hint)) = text.rsplit_once('\t') {
+                            let event = hint.parse().map_err(|e| {
+                                IOError::new(
+                                    std::io::ErrorKind::InvalidInput,
+                                    format!("menu item {}: {}", id, e),
+                                )
+                            })?;
+                            shortcuts.push((id.clone(), event));
+                        }
+                    }
+                    if let Some(popup) = &item.popup {
+                        collect_shortcuts(&popup.items, lang, shortcuts)?;
+                    }
+                }
+                Ok(())
+            }
+            let items = &(self.0).0;
+            let mut languages = Vec::new();
+            collect_languages(items, &mut languages);
+            languages.sort();
+            languages.dedup();
+            let mut builder = crate::resource::Accelerators::from_builder();
+            let mut shortcuts = Vec::new();
+            collect_shortcuts(items, None, &mut shortcuts)?;
+            for (id, event) in shortcuts {
+                builder = builder.event(id, event);
+            }
+            for lang in languages {
+                let mut shortcuts = Vec::new();
+                collect_shortcuts(items, Some(lang), &mut shortcuts)?;
+                for (id, event) in shortcuts {
+                    builder = builder.lang_specific_event(lang, id, event);
+                }
+            }
+            Ok(builder.build())
+        }
+    }
+
     impl MenuData {
         pub(crate) fn is_missing_for_lang(&self, lang: crate::Lang) -> bool {
             for item in self.0.iter() {
@@ -1270,6 +4199,51 @@ pub mod menu {
             Ok(())
         }
 
+        // Items without an id are identified by their position, e.g. "popup 0/2".
+        fn collect_translatable_texts(
+            lang: crate::Lang,
+            items: &[MenuItem],
+            path: &str,
+            texts: &mut Vec<(String, String)>,
+        ) {
+            for (idx, item) in items.iter().enumerate() {
+                let position = if path.is_empty() {
+                    idx.to_string()
+                } else {
+                    format!("{}/{}", path, idx)
+                };
+                if let Some(text) = item.text.get(lang) {
+                    let kind = if item.popup.is_some() {
+                        "popup"
+                    } else {
+                        "item"
+                    };
+                    let context = match &item.id {
+                        Some(id) => format!("{} {}", kind, id),
+                        None => format!("{} {}", kind, position),
+                    };
+                    texts.push((context, text.to_string()));
+                }
+                if let Some(popup) = &item.popup {
+                    Self::collect_translatable_texts(lang, &popup.items, &position, texts);
+                }
+            }
+        }
+
+        pub(crate) fn translatable_texts(&self, l: crate::Lang) -> Vec<(String, String)> {
+            let mut texts = Vec::new();
+            Self::collect_translatable_texts(l, &self.0, "", &mut texts);
+            texts
+        }
+
+        pub(crate) fn style_conflicts(&self, _l: crate::Lang) -> Vec<String> {
+            Vec::new()
+        }
+
+        pub(crate) fn defined_strings(&self, _l: crate::Lang) -> Vec<(crate::Id, String)> {
+            Vec::new()
+        }
+
         fn write_menu_item_resouce_segment(
             w: &mut dyn std::io::Write,
             lang: crate::Lang,
@@ -1287,7 +4261,7 @@ pub mod menu {
             let is_popup = item.popup.is_some();
             let kind = if is_popup { "POPUP" } else { "MENUITEM" };
             write!(w, "{} ", kind)?;
-            crate::codegen::write_narrow_str(w, text)?;
+            crate::codegen::write_text(w, text)?;
             let exist_id = item.id.is_some();
             let exist_ty = item.ty != MenuType::default();
             let exist_state = item.state != MenuState::default();
@@ -1322,10 +4296,7 @@ pub mod menu {
                 write!(w, ", ")?;
             }
             if exist_help_id {
-                crate::codegen::write_c_int(
-                    w,
-                    item.popup.as_ref().unwrap().help_id.clone().unwrap(),
-                )?;
+                crate::codegen::write_c_int(w, item.popup.as_ref().unwrap().help_id.unwrap())?;
             }
             write!(w, "\n")?;
             if is_popup {
@@ -1360,8 +4331,8 @@ pub mod menu {
     }
 }
 
-use winapi::ctypes::c_int;
-#[derive(Clone, Copy, Default)]
+use crate::sys::ctypes::c_int;
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
 pub struct Rect {
     x: c_int,
     y: c_int,
@@ -1378,13 +4349,46 @@ impl Rect {
             height,
         }
     }
+
+    // Dialog templates store each field as a signed 16-bit number, anything
+    // else would wrap when the dialog is created. `what` names the owner in
+    // the error, e.g. "control 1001".
+    pub(crate) fn check(&self, what: &str) -> Result<(), io::Error> {
+        let fields = [
+            ("x", self.x),
+            ("y", self.y),
+            ("width", self.width),
+            ("height", self.height),
+        ];
+        for (i, &(name, v)) in fields.iter().enumerate() {
+            let min = if i < 2 { i16::MIN as c_int } else { 0 };
+            if v < min || v > i16::MAX as c_int {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} has {} {}, outside {}..={}",
+                        what,
+                        name,
+                        v,
+                        min,
+                        i16::MAX
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
 }
 
-use winapi::ctypes::c_long;
-use winapi::shared::minwindef::TRUE;
-use winapi::shared::minwindef::{BOOL, BYTE};
-use winapi::um::wingdi;
+use crate::sys::ctypes::c_long;
+use crate::sys::minwindef::TRUE;
+use crate::sys::minwindef::{BOOL, BYTE};
+use crate::sys::wingdi;
 
+// Everything DIALOGEX's FONT statement takes: pointsize, typeface, weight,
+// italic and charset. Pitch, family and quality can't be given there, the
+// dialog template has no room for them.
+#[derive(PartialEq, Debug)]
 struct Font {
     typeface: CowStr,
     size: FontSize,
@@ -1393,15 +4397,48 @@ struct Font {
     charset: FontCharset,
 }
 
+impl Font {
+    pub(crate) fn check(&self) -> Result<(), io::Error> {
+        self.size.check()?;
+        self.weight.check()
+    }
+}
+
+#[derive(PartialEq, Debug)]
 pub struct FontSize(c_int);
 
 impl FontSize {
+    // Dialog fonts are sized in points, anything outside this range is a mistake.
+    pub const MAX_PT: c_int = 72;
+
+    // Sizes outside of 1 to MAX_PT fail writing the dialog.
     pub fn pt(v: c_int) -> Self {
         FontSize(v)
     }
+
+    // Like pt, rejecting sizes outside of 1 to MAX_PT right away.
+    pub fn try_pt(v: c_int) -> Result<Self, io::Error> {
+        let size = FontSize(v);
+        size.check()?;
+        Ok(size)
+    }
+
+    fn check(&self) -> Result<(), io::Error> {
+        if !(1..=Self::MAX_PT).contains(&self.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "dialog font size out of range, expected 1 to {} pt, actual value = {}",
+                    Self::MAX_PT,
+                    self.0
+                ),
+            ));
+        }
+        Ok(())
+    }
 }
 
-#[derive(Default)]
+#[derive(Default, PartialEq, Debug)]
 pub struct FontWeight(c_long);
 
 impl FontWeight {
@@ -1420,16 +4457,43 @@ impl FontWeight {
     // pub const DEMI_BOLD: FontWeight = FontWeight(wingdi::FW_DEMIBOLD); // alias of SEMIBOLD
     // pub const ULTRA_BOLD: FontWeight = FontWeight(wingdi::FW_ULTRABOLD); // alias of EXTRABOLD
     // pub const BLACK: FontWeight = FontWeight(wingdi::FW_BLACK); // alias of HEAVY
+
+    // Weights between the named ones, e.g. 350; LOGFONT weights go up to 1000,
+    // heavier ones fail writing the dialog.
+    pub fn custom(v: u32) -> Self {
+        FontWeight(std::convert::TryFrom::try_from(v).unwrap_or(c_long::MAX))
+    }
+
+    // Like custom, rejecting weights above 1000 right away.
+    pub fn try_custom(v: u32) -> Result<Self, io::Error> {
+        let weight = Self::custom(v);
+        weight.check()?;
+        Ok(weight)
+    }
+
+    fn check(&self) -> Result<(), io::Error> {
+        if !(0..=1000).contains(&self.0) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "font weight out of range, expected 0 to 1000, actual value = {}",
+                    self.0
+                ),
+            ));
+        }
+        Ok(())
+    }
 }
 
-#[derive(Default)]
+#[derive(Default, PartialEq, Debug)]
 pub struct FontItalic(BOOL);
 
 impl FontItalic {
     // const NORMAL: FontItalic = FontItalic(FALSE); // zero, use FontItalic::default()
-    const ITALIC: FontItalic = FontItalic(TRUE);
+    pub const ITALIC: FontItalic = FontItalic(TRUE);
 }
 
+#[derive(PartialEq, Debug)]
 pub struct FontCharset(BYTE);
 
 impl Default for FontCharset {
@@ -1461,17 +4525,18 @@ impl FontCharset {
     pub const BALTIC: FontCharset = FontCharset(wingdi::BALTIC_CHARSET as _);
 }
 
+#[allow(clippy::write_with_newline)]
 pub mod dialog {
+    use crate::sys::ctypes::c_int;
+    use crate::sys::minwindef::DWORD;
+    use crate::sys::winuser;
     use crate::MultiLangText;
     use crate::Rect;
     use crate::{CowStr, ExtraInfo, Id, IdOrName};
     use crate::{Font, FontCharset, FontItalic, FontSize, FontWeight};
     use crate::{OptionLangSpecific, VecLangSpecific};
-    use winapi::ctypes::c_int;
-    use winapi::shared::minwindef::DWORD;
-    use winapi::um::winuser;
 
-    #[derive(Clone, Copy, Default)]
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
     pub struct WindowStyle(pub(crate) Option<DWORD>, pub(crate) Option<DWORD>);
 
     impl WindowStyle {
@@ -1587,7 +4652,7 @@ pub mod dialog {
         }
     }
 
-    #[derive(Clone, Copy, Default)]
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
     pub struct DialogStyle(WindowStyle);
 
     impl DialogStyle {
@@ -1645,9 +4710,16 @@ pub mod dialog {
         }
     }
 
-    #[derive(Clone, Copy, Default)]
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
     pub struct ControlStyle(WindowStyle);
 
+    impl ControlStyle {
+        // Style bits of the control's own class, for custom controls.
+        pub fn custom(style: DWORD) -> Self {
+            ControlStyle(WindowStyle(Some(style), None))
+        }
+    }
+
     impl From<WindowStyle> for ControlStyle {
         fn from(v: WindowStyle) -> Self {
             ControlStyle(v)
@@ -1668,9 +4740,10 @@ pub mod dialog {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct StaticControlStyle(ControlStyle);
 
+    #[derive(PartialEq, Debug)]
     pub struct StaticControlContentType(DWORD);
     impl StaticControlContentType {
         pub const LEFT: StaticControlContentType = StaticControlContentType(winuser::SS_LEFT);
@@ -1756,7 +4829,7 @@ pub mod dialog {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct ButtonControlStyle(ControlStyle);
 
     impl From<WindowStyle> for ButtonControlStyle {
@@ -1771,9 +4844,45 @@ pub mod dialog {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct EditControlStyle(ControlStyle);
 
+    impl EditControlStyle {
+        //pub const LEFT: EditControlStyle = EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_LEFT), None))); // zero, use the default
+        pub const CENTER: EditControlStyle =
+            EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_CENTER), None)));
+        pub const RIGHT: EditControlStyle =
+            EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_RIGHT), None)));
+        pub const MULTILINE: EditControlStyle =
+            EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_MULTILINE), None)));
+        pub const UPPERCASE: EditControlStyle =
+            EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_UPPERCASE), None)));
+        pub const LOWERCASE: EditControlStyle =
+            EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_LOWERCASE), None)));
+        pub const PASSWORD: EditControlStyle =
+            EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_PASSWORD), None)));
+        pub const AUTO_VERTICAL_SCROLL: EditControlStyle = EditControlStyle(ControlStyle(
+            WindowStyle(Some(winuser::ES_AUTOVSCROLL), None),
+        ));
+        pub const AUTO_HORIZONTAL_SCROLL: EditControlStyle = EditControlStyle(ControlStyle(
+            WindowStyle(Some(winuser::ES_AUTOHSCROLL), None),
+        ));
+        pub const NO_HIDE_SELECTION: EditControlStyle =
+            EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_NOHIDESEL), None)));
+        pub const OEM_CONVERT: EditControlStyle = EditControlStyle(ControlStyle(WindowStyle(
+            Some(winuser::ES_OEMCONVERT),
+            None,
+        )));
+        pub const READ_ONLY: EditControlStyle =
+            EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_READONLY), None)));
+        pub const WANT_RETURN: EditControlStyle = EditControlStyle(ControlStyle(WindowStyle(
+            Some(winuser::ES_WANTRETURN),
+            None,
+        )));
+        pub const NUMBER: EditControlStyle =
+            EditControlStyle(ControlStyle(WindowStyle(Some(winuser::ES_NUMBER), None)));
+    }
+
     impl From<WindowStyle> for EditControlStyle {
         fn from(v: WindowStyle) -> Self {
             EditControlStyle(ControlStyle(v))
@@ -1786,7 +4895,7 @@ pub mod dialog {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct ScrollBarControlStyle(ControlStyle);
 
     impl From<WindowStyle> for ScrollBarControlStyle {
@@ -1801,7 +4910,7 @@ pub mod dialog {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct ComboBoxControlStyle(ControlStyle);
 
     impl From<WindowStyle> for ComboBoxControlStyle {
@@ -1816,7 +4925,7 @@ pub mod dialog {
         }
     }
 
-    #[derive(Clone, Copy)]
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct ListBoxControlStyle(ControlStyle);
 
     impl From<WindowStyle> for ListBoxControlStyle {
@@ -1831,11 +4940,15 @@ pub mod dialog {
         }
     }
 
+    #[derive(PartialEq, Debug)]
     enum IdOrLangSpecificStr {
         LangSpecificStr(OptionLangSpecific<CowStr>),
         Id(Option<Id>),
+        // text taken from the build's string table
+        StringId(Id),
     }
 
+    #[derive(PartialEq, Debug)]
     pub struct Control {
         template: Option<ControlTemplate>,
         text_or_image: Option<IdOrLangSpecificStr>,
@@ -1856,6 +4969,7 @@ pub mod dialog {
         }
     }
 
+    #[derive(PartialEq, Debug)]
     pub struct ControlTemplate {
         name: &'static str,
         use_text: bool,
@@ -1900,6 +5014,13 @@ pub mod dialog {
             self
         }
 
+        // Writes the text of string `id` from the build's string tables, in
+        // each language.
+        pub fn text_id(mut self, id: impl Into<Id>) -> Self {
+            self.0.text_or_image = Some(IdOrLangSpecificStr::StringId(id.into()));
+            self
+        }
+
         pub fn image_id(mut self, id: impl Into<Id>) -> Self {
             self.0.text_or_image = Some(IdOrLangSpecificStr::Id(Some(id.into())));
             self
@@ -1922,6 +5043,11 @@ pub mod dialog {
             self
         }
 
+        pub fn text_id(mut self, id: impl Into<Id>) -> Self {
+            self.0.text_or_image = Some(IdOrLangSpecificStr::StringId(id.into()));
+            self
+        }
+
         pub fn rect(mut self, rect: Rect) -> Self {
             self.0.rect = Some(rect);
             self
@@ -1979,6 +5105,33 @@ pub mod dialog {
         }
     }
 
+    // A CONTROL statement for any window class, e.g. "EDIT" or one the
+    // application registers itself.
+    pub struct CustomControl(Control);
+
+    impl ControlTrait for CustomControl {
+        fn into_control(self) -> Control {
+            self.0
+        }
+    }
+
+    impl CustomControl {
+        pub fn text(mut self, text: impl Into<MultiLangText>) -> Self {
+            self.0.text_or_image = Some(IdOrLangSpecificStr::LangSpecificStr(text.into().0));
+            self
+        }
+
+        pub fn rect(mut self, rect: Rect) -> Self {
+            self.0.rect = Some(rect);
+            self
+        }
+
+        pub fn style(mut self, style: impl Into<ControlStyle>) -> Self {
+            *self.0.style.get_or_insert_with(Default::default) |= style.into();
+            self
+        }
+    }
+
     define_control_class!(StaticControlTemplate, StaticControl);
     define_control_class!(ButtonControlTemplate, ButtonControl);
     define_control_class!(EditControlTemplate, EditControl);
@@ -2101,21 +5254,41 @@ pub mod dialog {
         pub fn from_template<T: ControlTemplateTrait>(template: T) -> T::ControlType {
             template.instantiate_control()
         }
+
+        pub fn custom(class: impl Into<CowStr>) -> CustomControl {
+            CustomControl(Control {
+                template: None,
+                text_or_image: None,
+                rect: None,
+                class: Some(crate::interner::intern(class.into())),
+                style: None,
+            })
+        }
     }
 
-    #[derive(Default)]
+    #[derive(Default, PartialEq, Debug)]
     pub(crate) struct DialogData {
         rect: OptionLangSpecific<Rect>,
         help_id: OptionLangSpecific<c_int>,
         extra_info: OptionLangSpecific<ExtraInfo>,
         caption: OptionLangSpecific<CowStr>,
+        // a caption taken from the build's string table instead
+        caption_id: Option<Id>,
         class: Option<IdOrName>,
         style: Option<DialogStyle>,
         font: OptionLangSpecific<Font>,
         menu: Option<IdOrName>,
         controls: VecLangSpecific<(Id, Control)>,
+        // the ids auto_control hands out, DEFAULT_AUTO_IDS unless set
+        auto_ids: Option<std::ops::RangeInclusive<u16>>,
+        // the names controls added with auto_control were given
+        symbols: Vec<(String, Id)>,
+        // the first auto_control call that couldn't be honored, an error once
+        // the dialog is written
+        auto_control_error: Option<String>,
     }
 
+    #[derive(PartialEq, Debug)]
     pub struct DialogBuilder(DialogData);
 
     builder_implement_priv_default!(DialogBuilder);
@@ -2128,6 +5301,14 @@ pub mod dialog {
 
         pub fn caption(mut self, caption_text: MultiLangText) -> Self {
             self.0.caption = caption_text.0;
+            self.0.caption_id = None;
+            self.style(WindowStyle::CAPTION)
+        }
+
+        // Captions the dialog with string `id` from the build's string tables.
+        pub fn caption_id(mut self, id: impl Into<Id>) -> Self {
+            self.0.caption = OptionLangSpecific::default();
+            self.0.caption_id = Some(id.into());
             self.style(WindowStyle::CAPTION)
         }
 
@@ -2146,7 +5327,7 @@ pub mod dialog {
             charset: FontCharset,
         ) -> Self {
             self.0.font.insert_universal(Font {
-                typeface: typeface.into(),
+                typeface: crate::interner::intern(typeface.into()),
                 size,
                 weight,
                 italic,
@@ -2167,7 +5348,7 @@ pub mod dialog {
             self.0.font.insert_lang_specific(
                 lang,
                 Font {
-                    typeface: typeface.into(),
+                    typeface: crate::interner::intern(typeface.into()),
                     size,
                     weight,
                     italic,
@@ -2196,6 +5377,45 @@ pub mod dialog {
             self
         }
 
+        // The ids auto_control picks from.
+        pub fn auto_id_range(mut self, ids: std::ops::RangeInclusive<u16>) -> Self {
+            self.0.auto_ids = Some(ids);
+            self
+        }
+
+        // Adds the control with the lowest id of the auto_id_range no control
+        // added so far uses, and records it under `symbol` for Dialog::control_id.
+        // Controls with fixed ids are best added first: one added later with an
+        // id already handed out fails writing the dialog, as do a symbol used
+        // twice or a range run out of ids.
+        pub fn auto_control(
+            mut self,
+            symbol: impl Into<String>,
+            control: impl ControlTrait,
+        ) -> Self {
+            let symbol = symbol.into();
+            if self.0.symbols.iter().any(|(s, _)| *s == symbol) {
+                return self.auto_control_error(format!("control symbol {} is used twice", symbol));
+            }
+            let ids = self.0.auto_ids.clone().unwrap_or(DEFAULT_AUTO_IDS);
+            let id = ids
+                .map(Id::from)
+                .find(|id| self.0.controls.0.iter().all(|(_, (used, _))| used != id));
+            let id = match id {
+                Some(id) => id,
+                None => {
+                    return self.auto_control_error(format!("no control id left for {}", symbol))
+                }
+            };
+            self.0.symbols.push((symbol, id.clone()));
+            self.control(id, control)
+        }
+
+        fn auto_control_error(mut self, error: String) -> Self {
+            self.0.auto_control_error.get_or_insert(error);
+            self
+        }
+
         pub fn rect(mut self, rect: Rect) -> Self {
             self.0.rect.insert_universal(rect);
             self
@@ -2207,34 +5427,179 @@ pub mod dialog {
         }
     }
 
-    impl DialogData {
-        pub(crate) fn is_missing_for_lang(&self, _l: crate::Lang) -> bool {
-            false
+    // Where Visual Studio starts numbering controls, below the command ids.
+    const DEFAULT_AUTO_IDS: std::ops::RangeInclusive<u16> = crate::predefined_id::CONTROLS;
+
+    impl crate::resource::Dialog {
+        // The id auto_control gave the control recorded under `symbol`.
+        pub fn control_id(&self, symbol: &str) -> Option<Id> {
+            self.symbols()
+                .into_iter()
+                .find(|(s, _)| *s == symbol)
+                .map(|(_, id)| id)
         }
 
-        pub(crate) fn write_resource_header_extras(
+        // Every control added with auto_control and its id, in the form
+        // IdOrName::parse_with_symbols takes.
+        pub fn symbols(&self) -> Vec<(&str, Id)> {
+            self.0
+                .symbols
+                .iter()
+                .map(|(symbol, id)| (symbol.as_str(), id.clone()))
+                .collect()
+        }
+
+        // Writes a Rust module with a `{name}Control` enum of the dialog's control
+        // ids and, if asked, a `{name}Handler` trait plus a generic dialog procedure
+        // dispatching WM_COMMAND and WM_NOTIFY to it by control. Controls using
+        // the ignorable id -1 (static text, mostly) are left out.
+        pub fn generate_control_module(
             &self,
-            w: &mut dyn std::io::Write,
-            lang: crate::Lang,
+            name: &str,
+            path: &std::path::Path,
+            dialog_proc: bool,
         ) -> Result<(), std::io::Error> {
-            let mut rect = self.rect.get(lang).cloned();
-            let rect = rect.get_or_insert_with(Default::default);
-            write!(w, " ")?;
-            crate::codegen::write_rect(w, rect)?;
-            if let Some(&help_id) = self.help_id.get(lang) {
-                write!(w, ", ")?;
-                crate::codegen::write_c_int(w, help_id)?;
+            let mut controls: Vec<(Id, &'static str)> = Vec::new();
+            for (_, (id, control)) in self.0.controls.0.iter() {
+                if *id == crate::predefined_id::DEFAULT || controls.iter().any(|(c, _)| c == id) {
+                    continue;
+                }
+                let kind = control.template.as_ref().map_or("CONTROL", |t| t.name);
+                controls.push((id.clone(), kind));
             }
-            crate::codegen::write_extra_info(w, self.extra_info.get(lang))?;
+            controls.sort();
+            crate::codegen::write_file_atomically(path, |w| {
+                crate::codegen::write_dialog_control_module(w, name, &controls, dialog_proc)
+            })
+        }
+    }
+
+    impl DialogData {
+        pub(crate) fn is_missing_for_lang(&self, _l: crate::Lang) -> bool {
+            false
+        }
+
+        pub(crate) fn translatable_texts(&self, lang: crate::Lang) -> Vec<(String, String)> {
+            let mut texts = Vec::new();
             if let Some(caption) = self.caption.get(lang) {
-                write!(w, "\nCAPTION ")?;
-                crate::codegen::write_narrow_str(w, caption)?;
-            }
-            if let Some(class) = self.class.as_ref() {
-                write!(w, "\nCLASS ")?;
-                crate::codegen::write_id_or_name(w, class)?;
+                texts.push(("caption".to_owned(), caption.to_string()));
             }
-            if let Some(font) = self.font.get(lang) {
+            for (id, control) in self.controls.iter(lang) {
+                if let Some(IdOrLangSpecificStr::LangSpecificStr(text)) = &control.text_or_image {
+                    if let Some(text) = text.get(lang).filter(|text| !text.is_empty()) {
+                        texts.push((format!("control {}", id), text.to_string()));
+                    }
+                }
+            }
+            texts
+        }
+
+        pub(crate) fn style_conflicts(&self, lang: crate::Lang) -> Vec<String> {
+            fn window_style_conflicts(style: &WindowStyle) -> Option<&'static str> {
+                let bits = style.0.unwrap_or(0);
+                if bits & winuser::WS_CHILD != 0 && bits & winuser::WS_POPUP != 0 {
+                    return Some("WS_CHILD and WS_POPUP");
+                }
+                None
+            }
+            let mut conflicts = Vec::new();
+            if let Some(DialogStyle(style)) = self.style.as_ref() {
+                if let Some(pair) = window_style_conflicts(style) {
+                    conflicts.push(format!("dialog has both {}", pair));
+                }
+                let bits = style.0.unwrap_or(0);
+                if bits & winuser::DS_CENTER != 0 && bits & winuser::DS_CENTERMOUSE != 0 {
+                    conflicts.push("dialog has both DS_CENTER and DS_CENTERMOUSE".to_owned());
+                }
+                // a caption makes a DS_CONTROL dialog, meant to be embedded as a
+                // child, draw its own title bar inside the parent
+                if bits & winuser::DS_CONTROL != 0
+                    && bits & winuser::WS_CAPTION == winuser::WS_CAPTION
+                {
+                    conflicts.push("dialog has both DS_CONTROL and WS_CAPTION".to_owned());
+                }
+            }
+            for (id, control) in self.controls.iter(lang) {
+                if let Some(ControlStyle(style)) = control.style.as_ref() {
+                    if let Some(pair) = window_style_conflicts(style) {
+                        conflicts.push(format!("control {} has both {}", id, pair));
+                    }
+                    // edit controls ignore ES_PASSWORD when multiline
+                    let is_edit = control.template.as_ref().map(|t| t.name) == Some("EDITTEXT")
+                        || control
+                            .class
+                            .as_ref()
+                            .is_some_and(|class| class.eq_ignore_ascii_case("EDIT"));
+                    let bits = style.0.unwrap_or(0);
+                    if is_edit
+                        && bits & winuser::ES_PASSWORD != 0
+                        && bits & winuser::ES_MULTILINE != 0
+                    {
+                        conflicts.push(format!(
+                            "control {} has both ES_PASSWORD and ES_MULTILINE",
+                            id
+                        ));
+                    }
+                }
+            }
+            conflicts
+        }
+
+        pub(crate) fn defined_strings(&self, _l: crate::Lang) -> Vec<(crate::Id, String)> {
+            Vec::new()
+        }
+
+        pub(crate) fn write_resource_header_extras(
+            &self,
+            w: &mut dyn std::io::Write,
+            lang: crate::Lang,
+        ) -> Result<(), std::io::Error> {
+            if let Some(error) = &self.auto_control_error {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    error.clone(),
+                ));
+            }
+            // Two controls sharing an id can't be told apart by GetDlgItem,
+            // only IDC_STATIC is meant to be shared.
+            let mut ids = Vec::new();
+            for (id, _) in self.controls.iter(lang) {
+                if *id == crate::predefined_id::STATIC {
+                    continue;
+                }
+                if ids.contains(&id) {
+                    return Err(std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("control id {} is used twice", id),
+                    ));
+                }
+                ids.push(id);
+            }
+            let mut rect = self.rect.get(lang).cloned();
+            let rect = rect.get_or_insert_with(Default::default);
+            rect.check("dialog")?;
+            if let Some(font) = self.font.get(lang) {
+                font.check()?;
+            }
+            write!(w, " ")?;
+            crate::codegen::write_rect(w, rect)?;
+            if let Some(&help_id) = self.help_id.get(lang) {
+                write!(w, ", ")?;
+                crate::codegen::write_c_int(w, help_id)?;
+            }
+            crate::codegen::write_extra_info(w, self.extra_info.get(lang))?;
+            if let Some(caption) = self.caption.get(lang) {
+                write!(w, "\nCAPTION ")?;
+                crate::codegen::write_text(w, caption)?;
+            } else if let Some(caption_id) = self.caption_id.as_ref() {
+                write!(w, "\nCAPTION ")?;
+                crate::codegen::write_text(w, &crate::codegen::table_string(lang, caption_id)?)?;
+            }
+            if let Some(class) = self.class.as_ref() {
+                write!(w, "\nCLASS ")?;
+                crate::codegen::write_id_or_name(w, class)?;
+            }
+            if let Some(font) = self.font.get(lang) {
                 write!(w, "\nFONT ")?;
                 crate::codegen::write_font(w, font)?;
             }
@@ -2261,6 +5626,9 @@ pub mod dialog {
                 use_keyword: None,
             };
             for (id, control) in self.controls.iter(lang) {
+                if let Some(rect) = control.rect.as_ref() {
+                    rect.check(&format!("control {}", id))?;
+                }
                 let template = control.template.as_ref().unwrap_or(&default_template);
                 write!(w, "\t{} ", template.name)?;
                 if template.use_text {
@@ -2271,6 +5639,10 @@ pub mod dialog {
                                 .unwrap_or(&crate::predefined_id::DEFAULT);
                             crate::codegen::write_id(w, text_or_image_id)?;
                         }
+                        Some(crate::dialog::IdOrLangSpecificStr::StringId(string_id)) => {
+                            let text = crate::codegen::table_string(lang, string_id)?;
+                            crate::codegen::write_text(w, &text)?;
+                        }
                         _ => {
                             let text =
                                 if let Some(crate::dialog::IdOrLangSpecificStr::LangSpecificStr(
@@ -2281,16 +5653,16 @@ pub mod dialog {
                                 } else {
                                     None
                                 };
-                            crate::codegen::write_mandatory_narrow_str(w, text)?;
+                            crate::codegen::write_mandatory_text(w, text)?;
                         }
                     }
                     write!(w, ", ")?;
                 }
                 crate::codegen::write_id(w, id)?;
-                let style = control.style.clone().unwrap_or_default().0;
+                let style = control.style.unwrap_or_default().0;
                 if template.use_keyword.is_none() {
                     write!(w, ", ")?;
-                    crate::codegen::write_mandatory_narrow_str(w, control.class.as_ref())?;
+                    crate::codegen::write_mandatory_text(w, control.class.as_ref())?;
                     write!(w, ", ")?;
                     crate::codegen::write_mandatory_dword(w, style.0.as_ref())?;
                 }
@@ -2317,17 +5689,117 @@ pub mod dialog {
     }
 }
 
+#[allow(clippy::write_with_newline)]
 pub mod version_info {
-    use crate::CowStr;
+    use crate::sys::minwindef::{DWORD, WORD};
     use crate::OptionLangSpecific;
-    use winapi::shared::minwindef::{DWORD, WORD};
+    use crate::{interner, CowStr};
 
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct Version([WORD; 4]);
+
+    impl Version {
+        pub const fn new(major: WORD, minor: WORD, patch: WORD, build: WORD) -> Self {
+            Version([major, minor, patch, build])
+        }
+
+        // Up to four dot separated numbers, e.g. "1.2.3.4"; missing trailing
+        // parts are zero.
+        pub fn parse(s: &str) -> Result<Self, std::io::Error> {
+            let invalid = || {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("invalid version: {}", s),
+                )
+            };
+            let mut parts = [0; 4];
+            for (idx, part) in s.split('.').enumerate() {
+                if idx == parts.len() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(invalid());
+                }
+                parts[idx] = part.parse().map_err(|_| invalid())?;
+            }
+            Ok(Version(parts))
+        }
+
+        // The package version Cargo gives build scripts, with any pre-release
+        // or build metadata dropped, e.g. "1.2.3-beta.1" gives 1, 2, 3, 0.
+        pub fn from_cargo_pkg_version() -> Result<Self, std::io::Error> {
+            let version = std::env::var("CARGO_PKG_VERSION").map_err(|_| {
+                std::io::Error::new(
+                    std::io::ErrorKind::NotFound,
+                    "CARGO_PKG_VERSION isn't set, not run by cargo?",
+                )
+            })?;
+            let release = version.split(['-', '+']).next().unwrap();
+            Version::parse(release)
+        }
+    }
+
+    impl std::str::FromStr for Version {
+        type Err = std::io::Error;
+
+        fn from_str(s: &str) -> Result<Self, Self::Err> {
+            Version::parse(s)
+        }
+    }
+
+    impl std::convert::TryFrom<&str> for Version {
+        type Error = std::io::Error;
+
+        fn try_from(s: &str) -> Result<Self, Self::Error> {
+            Version::parse(s)
+        }
+    }
+
+    impl From<(WORD, WORD, WORD, WORD)> for Version {
+        fn from((major, minor, patch, build): (WORD, WORD, WORD, WORD)) -> Self {
+            Version([major, minor, patch, build])
+        }
+    }
+
+    #[derive(Clone, Copy, Default, PartialEq, Debug)]
     pub struct FileFlags(DWORD);
+
+    impl FileFlags {
+        pub const DEBUG: FileFlags = FileFlags(0x01);
+        pub const PRERELEASE: FileFlags = FileFlags(0x02);
+        pub const PATCHED: FileFlags = FileFlags(0x04);
+        pub const PRIVATE_BUILD: FileFlags = FileFlags(0x08);
+        pub const INFO_INFERRED: FileFlags = FileFlags(0x10);
+        pub const SPECIAL_BUILD: FileFlags = FileFlags(0x20);
+    }
+
+    bitflags_bitor_method!(FileFlags);
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct FileOS(DWORD);
+
+    impl FileOS {
+        pub const UNKNOWN: FileOS = FileOS(0);
+        pub const DOS: FileOS = FileOS(0x0001_0000);
+        pub const NT: FileOS = FileOS(0x0004_0000);
+        pub const WINDOWS16: FileOS = FileOS(0x0001);
+        pub const WINDOWS32: FileOS = FileOS(0x0004);
+        pub const DOS_WINDOWS16: FileOS = FileOS(0x0001_0001);
+        pub const DOS_WINDOWS32: FileOS = FileOS(0x0001_0004);
+        pub const NT_WINDOWS32: FileOS = FileOS(VOS_NT_WINDOWS32);
+    }
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
     pub struct FileType(DWORD);
 
-    #[derive(Default)]
+    impl FileType {
+        pub const UNKNOWN: FileType = FileType(0);
+        pub const APP: FileType = FileType(1);
+        pub const DLL: FileType = FileType(2);
+        pub const DRV: FileType = FileType(3);
+        pub const FONT: FileType = FileType(4);
+        pub const VXD: FileType = FileType(5);
+        pub const STATIC_LIB: FileType = FileType(7);
+    }
+
+    #[derive(Default, PartialEq, Debug)]
     pub(crate) struct VersionInfoData {
         fixed_file_version: Option<Version>,
         fixed_product_version: Option<Version>,
@@ -2346,21 +5818,307 @@ pub mod version_info {
         private_build: Option<OptionLangSpecific<CowStr>>,
         special_build: Option<OptionLangSpecific<CowStr>>,
         comments: Option<OptionLangSpecific<CowStr>>,
+        // further StringFileInfo values, by key, in the order first set
+        custom_strings: Vec<(CowStr, OptionLangSpecific<CowStr>)>,
+        // the first custom key that can't be used, an error once the version
+        // info is written
+        custom_key_error: Option<String>,
+        // the languages listed in VarFileInfo instead of the build's
+        translations: Option<Vec<crate::Lang>>,
     }
 
     //we only support Unicode as charset here.
 
+    #[derive(PartialEq, Debug)]
     pub struct VersionInfoBuilder(VersionInfoData);
 
     builder_implement_priv_default!(VersionInfoBuilder);
     builder_build_method!(VersionInfoBuilder, crate::resource::VersionInfo);
-    unimplemented_resouce_data_write_segment!(VersionInfoData);
+
+    impl VersionInfoBuilder {
+        pub fn fixed_file_version(mut self, version: Version) -> Self {
+            self.0.fixed_file_version = Some(version);
+            self
+        }
+
+        pub fn fixed_product_version(mut self, version: Version) -> Self {
+            self.0.fixed_product_version = Some(version);
+            self
+        }
+
+        pub fn fixed_file_flags(mut self, flags: FileFlags) -> Self {
+            self.0.fixed_file_flags = Some(flags);
+            self
+        }
+
+        pub fn fixed_file_os(mut self, os: FileOS) -> Self {
+            self.0.fixed_file_os = Some(os);
+            self
+        }
+
+        pub fn fixed_file_type(mut self, file_type: FileType) -> Self {
+            self.0.fixed_file_type = Some(file_type);
+            self
+        }
+
+        // Lists these languages in the Translation value, and writes
+        // StringFileInfo blocks for them, instead of the build's languages.
+        pub fn translations(mut self, langs: impl IntoIterator<Item = crate::Lang>) -> Self {
+            let mut translations = Vec::new();
+            for lang in langs {
+                if !translations.contains(&lang) {
+                    translations.push(lang);
+                }
+            }
+            self.0.translations = Some(translations);
+            self
+        }
+    }
+
+    fn required(field: &mut OptionLangSpecific<CowStr>) -> &mut OptionLangSpecific<CowStr> {
+        field
+    }
+
+    fn optional(field: &mut Option<OptionLangSpecific<CowStr>>) -> &mut OptionLangSpecific<CowStr> {
+        field.get_or_insert_with(Default::default)
+    }
+
+    // Each StringFileInfo value is set for all languages, or for one language,
+    // which then uses it instead of the universal value.
+    macro_rules! version_info_string_setters {
+        ($($setter:ident, $lang_specific_setter:ident, $field:ident, $access:ident;)*) => {
+            impl VersionInfoBuilder {
+                $(
+                    pub fn $setter(mut self, text: impl Into<CowStr>) -> Self {
+                        $access(&mut self.0.$field).insert_universal(interner::intern(text.into()));
+                        self
+                    }
+
+                    pub fn $lang_specific_setter(
+                        mut self,
+                        lang: crate::Lang,
+                        text: impl Into<CowStr>,
+                    ) -> Self {
+                        $access(&mut self.0.$field)
+                            .insert_lang_specific(lang, interner::intern(text.into()));
+                        self
+                    }
+                )*
+            }
+        };
+    }
+
+    version_info_string_setters! {
+        product_name, lang_specific_product_name, product_name, required;
+        product_version, lang_specific_product_version, product_version, required;
+        file_description, lang_specific_file_description, file_description, required;
+        file_version, lang_specific_file_version, file_version, required;
+        internal_name, lang_specific_internal_name, internal_name, required;
+        original_filename, lang_specific_original_filename, original_filename, required;
+        company_name, lang_specific_company_name, company_name, required;
+        legal_copyright, lang_specific_legal_copyright, legal_copyright, optional;
+        legal_trademarks, lang_specific_legal_trademarks, legal_trademarks, optional;
+        private_build, lang_specific_private_build, private_build, optional;
+        special_build, lang_specific_special_build, special_build, optional;
+        comments, lang_specific_comments, comments, optional;
+    }
+
+    const STANDARD_KEYS: [&str; 12] = [
+        "Comments",
+        "CompanyName",
+        "FileDescription",
+        "FileVersion",
+        "InternalName",
+        "LegalCopyright",
+        "LegalTrademarks",
+        "OriginalFilename",
+        "PrivateBuild",
+        "ProductName",
+        "ProductVersion",
+        "SpecialBuild",
+    ];
+
+    impl VersionInfoBuilder {
+        fn custom_string_field(&mut self, key: CowStr) -> Option<&mut OptionLangSpecific<CowStr>> {
+            // the key is written into the script as is
+            let error = if key.is_empty() || key.contains(|ch: char| ch == '"' || ch.is_control()) {
+                Some(format!("version info key {:?} can't be written", key))
+            } else if STANDARD_KEYS.contains(&key.as_ref()) {
+                Some(format!("version info key {} has its own setter", key))
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                self.0.custom_key_error.get_or_insert(error);
+                return None;
+            }
+            let fields = &mut self.0.custom_strings;
+            let idx = match fields.iter().position(|(k, _)| *k == key) {
+                Some(idx) => idx,
+                None => {
+                    fields.push((interner::intern(key), Default::default()));
+                    fields.len() - 1
+                }
+            };
+            Some(&mut fields[idx].1)
+        }
+
+        // A StringFileInfo value under a key of one's own, e.g. "CommitHash".
+        // Standard keys and ones a script can't hold fail writing the version
+        // info.
+        pub fn custom_string(mut self, key: impl Into<CowStr>, text: impl Into<CowStr>) -> Self {
+            if let Some(field) = self.custom_string_field(key.into()) {
+                field.insert_universal(interner::intern(text.into()));
+            }
+            self
+        }
+
+        pub fn lang_specific_custom_string(
+            mut self,
+            lang: crate::Lang,
+            key: impl Into<CowStr>,
+            text: impl Into<CowStr>,
+        ) -> Self {
+            if let Some(field) = self.custom_string_field(key.into()) {
+                field.insert_lang_specific(lang, interner::intern(text.into()));
+            }
+            self
+        }
+    }
+
+    // VS_FF_DEBUG through VS_FF_SPECIALBUILD
+    const VS_FFI_FILEFLAGSMASK: DWORD = 0x3F;
+    const VOS_NT_WINDOWS32: DWORD = 0x0004_0004;
+
+    fn write_version(
+        w: &mut dyn std::io::Write,
+        statement: &str,
+        version: Option<&Version>,
+    ) -> Result<(), std::io::Error> {
+        if let Some(Version([major, minor, patch, build])) = version {
+            write!(
+                w,
+                "\n{} {}, {}, {}, {}",
+                statement, major, minor, patch, build
+            )?;
+        }
+        Ok(())
+    }
+
+    impl VersionInfoData {
+        pub(crate) fn is_missing_for_lang(&self, _l: crate::Lang) -> bool {
+            false
+        }
+
+        pub(crate) fn write_resource_header_extras(
+            &self,
+            w: &mut dyn std::io::Write,
+            _l: crate::Lang,
+        ) -> Result<(), std::io::Error> {
+            if let Some(error) = &self.custom_key_error {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    error.clone(),
+                ));
+            }
+            write_version(w, "FILEVERSION", self.fixed_file_version.as_ref())?;
+            write_version(w, "PRODUCTVERSION", self.fixed_product_version.as_ref())?;
+            if let Some(FileFlags(flags)) = self.fixed_file_flags {
+                write!(w, "\nFILEFLAGSMASK ")?;
+                crate::codegen::write_dword(w, VS_FFI_FILEFLAGSMASK)?;
+                write!(w, "\nFILEFLAGS ")?;
+                crate::codegen::write_dword(w, flags)?;
+            }
+            // without FILEOS the file would claim to be for an unknown system
+            let os = self
+                .fixed_file_os
+                .as_ref()
+                .map_or(VOS_NT_WINDOWS32, |os| os.0);
+            write!(w, "\nFILEOS ")?;
+            crate::codegen::write_dword(w, os)?;
+            if let Some(FileType(file_type)) = self.fixed_file_type {
+                write!(w, "\nFILETYPE ")?;
+                crate::codegen::write_dword(w, file_type)?;
+            }
+            Ok(())
+        }
+
+        // A StringFileInfo block for each language of the build, and
+        // Translation entries pointing at them.
+        pub(crate) fn write_resource_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+        ) -> Result<(), std::io::Error> {
+            let langs = match &self.translations {
+                Some(translations) => translations.clone(),
+                None => crate::codegen::build_languages_or(l),
+            };
+            write!(w, "{{\n")?;
+            let blocks: Vec<_> = langs
+                .iter()
+                .map(|&lang| (lang, self.string_values(lang)))
+                .filter(|(_, values)| !values.is_empty())
+                .collect();
+            if !blocks.is_empty() {
+                crate::codegen::write_string_file_info(w, &blocks)?;
+            }
+            crate::codegen::write_var_file_info(w, &langs)?;
+            write!(w, "}}\n")?;
+            Ok(())
+        }
+
+        // The StringFileInfo values of a language's block, by key, each the
+        // language's own value or else the universal one.
+        pub(crate) fn string_values(&self, l: crate::Lang) -> Vec<(&str, &str)> {
+            let fields = [
+                self.comments.as_ref(),
+                Some(&self.company_name),
+                Some(&self.file_description),
+                Some(&self.file_version),
+                Some(&self.internal_name),
+                self.legal_copyright.as_ref(),
+                self.legal_trademarks.as_ref(),
+                Some(&self.original_filename),
+                self.private_build.as_ref(),
+                Some(&self.product_name),
+                Some(&self.product_version),
+                self.special_build.as_ref(),
+            ];
+            let custom_fields = self
+                .custom_strings
+                .iter()
+                .map(|(key, field)| (key.as_ref(), Some(field)));
+            STANDARD_KEYS
+                .iter()
+                .copied()
+                .zip(fields.iter().copied())
+                .chain(custom_fields)
+                .filter_map(|(key, field)| Some((key, field?.get(l)?.as_ref())))
+                .collect()
+        }
+
+        pub(crate) fn translatable_texts(&self, _l: crate::Lang) -> Vec<(String, String)> {
+            Vec::new()
+        }
+
+        pub(crate) fn style_conflicts(&self, _l: crate::Lang) -> Vec<String> {
+            Vec::new()
+        }
+
+        pub(crate) fn defined_strings(&self, _l: crate::Lang) -> Vec<(crate::Id, String)> {
+            Vec::new()
+        }
+    }
 }
 
+#[allow(clippy::write_with_newline)]
 pub mod rc_inline {
+    use crate::codegen::DataLiteral;
+    use crate::sys::minwindef::{DWORD, WORD};
     use crate::{ExtraInfo, OptionLangSpecific};
-    use winapi::shared::minwindef::{DWORD, WORD};
 
+    #[derive(Clone, PartialEq, Debug)]
     enum RcInlineItem {
         U16(WORD),
         U32(DWORD),
@@ -2368,365 +6126,6003 @@ pub mod rc_inline {
         WStr(Vec<u16>),
     }
 
-    #[derive(Default)]
+    #[derive(Clone, Default, PartialEq, Debug)]
     pub(crate) struct RcInlineData {
         extra_info: OptionLangSpecific<ExtraInfo>,
         items: OptionLangSpecific<Vec<RcInlineItem>>,
     }
 
+    #[derive(PartialEq, Debug)]
     pub struct RcInlineBuilder(RcInlineData);
     builder_implement_priv_default!(RcInlineBuilder);
     builder_extra_info_methods2!(RcInlineBuilder);
     builder_build_method!(RcInlineBuilder, crate::resource::RcInline);
-    unimplemented_resouce_data_write_segment!(RcInlineData);
-}
 
-pub mod user_defined {
-    use crate::rc_inline::RcInlineData;
-    use crate::CowPath;
+    impl crate::resource::RcInline {
+        // Embeds the file's bytes, read now. A relative path is taken relative
+        // to the package directory, like the paths of other resources.
+        pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self, std::io::Error> {
+            let path = crate::codegen::base_dir()?.join(path);
+            if std::env::var_os("OUT_DIR").is_some() {
+                println!("cargo:rerun-if-changed={}", path.display());
+            }
+            Self::from_reader(std::fs::File::open(&path)?)
+        }
 
-    pub(crate) enum UserDefinedData {
-        RcInline(RcInlineData),
-        External(CowPath),
+        pub fn from_reader(mut reader: impl std::io::Read) -> Result<Self, std::io::Error> {
+            use std::io::Read;
+            let mut items = Vec::new();
+            loop {
+                let mut chunk = Vec::with_capacity(MAX_LITERAL_LEN);
+                (&mut reader)
+                    .take(MAX_LITERAL_LEN as u64)
+                    .read_to_end(&mut chunk)?;
+                if chunk.is_empty() {
+                    break;
+                }
+                items.push(RcInlineItem::Str(chunk));
+            }
+            let mut data = RcInlineData::default();
+            data.items.insert_universal(items);
+            Ok(crate::resource::RcInline(std::rc::Rc::new(data)))
+        }
     }
 
-    impl Default for UserDefinedData {
-        fn default() -> Self {
-            UserDefinedData::RcInline(Default::default())
+    impl RcInlineData {
+        pub(crate) fn from_bytes(data: &[u8]) -> Self {
+            let mut inline_data = RcInlineData::default();
+            inline_data.items.insert_universal(
+                data.chunks(MAX_LITERAL_LEN)
+                    .map(|chunk| RcInlineItem::Str(chunk.to_vec()))
+                    .collect(),
+            );
+            inline_data
         }
     }
 
-    impl From<CowPath> for UserDefinedData {
-        fn from(path: CowPath) -> Self {
-            UserDefinedData::External(path)
+    // Items are written in the order added. A language given items of its
+    // own uses them instead of the universal ones.
+    impl RcInlineBuilder {
+        fn push_item(mut self, lang: Option<crate::Lang>, item: RcInlineItem) -> Self {
+            let items = match lang {
+                Some(lang) => self.0.items.access_lang_specific_mut(lang),
+                None => self.0.items.access_universal_mut(),
+            };
+            items.push(item);
+            self
         }
-    }
 
-    pub struct UserDefinedBuilder(UserDefinedData);
-    builder_implement_priv_default!(UserDefinedBuilder);
-    builder_build_method!(UserDefinedBuilder, crate::resource::UserDefined);
-}
+        pub fn word(self, value: WORD) -> Self {
+            self.push_item(None, RcInlineItem::U16(value))
+        }
 
-impl Build {
-    pub fn generate_rc_file(self, path: &std::path::Path) -> Result<(), io::Error> {
-        use std::fs::File;
-        let mut file = File::create(path)?;
-        codegen::write_header(&mut file)?;
+        pub fn dword(self, value: DWORD) -> Self {
+            self.push_item(None, RcInlineItem::U32(value))
+        }
 
-        for (lang, resource_list) in self.resources {
-            for (id_or_name, resource) in resource_list {
-                resource.write_script_segment(&mut file, lang, id_or_name)?;
-            }
+        // Strings are stored without a terminating null, add "\0" if needed.
+        pub fn narrow_str(self, text: &str) -> Self {
+            self.bytes(text.as_bytes())
         }
 
-        Ok(())
-    }
+        pub fn wide_str(self, text: &str) -> Self {
+            self.push_item(None, RcInlineItem::WStr(text.encode_utf16().collect()))
+        }
 
-    pub fn compile_rc_file(path: &std::path::Path) -> Result<(), io::Error> {
-        embed_resource::compile(path, embed_resource::NONE);
-        Ok(())
-    }
+        pub fn bytes(self, data: &[u8]) -> Self {
+            self.push_item(None, RcInlineItem::Str(data.to_vec()))
+        }
 
-    pub fn compile(self) -> Result<(), io::Error> {
-        use std::path::PathBuf;
-        let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR variable is not set");
-        let mut rc_file_path = PathBuf::from(out_dir);
-        rc_file_path.push("resource.rc");
-        self.generate_rc_file(&rc_file_path)?;
-        println!("rerun-if-changed={}", rc_file_path.display());
-        Self::compile_rc_file(&rc_file_path)?;
+        pub fn lang_specific_word(self, lang: crate::Lang, value: WORD) -> Self {
+            self.push_item(Some(lang), RcInlineItem::U16(value))
+        }
 
-        Ok(())
-    }
-}
+        pub fn lang_specific_dword(self, lang: crate::Lang, value: DWORD) -> Self {
+            self.push_item(Some(lang), RcInlineItem::U32(value))
+        }
 
-mod codegen {
-    use crate::resource;
-    use crate::CowStr;
-    use crate::{Id, IdOrName};
-    use std::io::{Error as IOError, Write};
+        pub fn lang_specific_narrow_str(self, lang: crate::Lang, text: &str) -> Self {
+            self.lang_specific_bytes(lang, text.as_bytes())
+        }
 
-    pub(crate) fn write_header(w: &mut dyn Write) -> Result<(), IOError> {
-        write!(
-            w,
-            "// Resource script automatically generated by RESW-RS.\n"
-        )?;
-        write!(w, "// Do not edit this file manually.\n")?;
-        write!(w, "\n")?;
-        write!(w, "#pragma code_page(65001)\n")?;
-        Ok(())
-    }
+        pub fn lang_specific_wide_str(self, lang: crate::Lang, text: &str) -> Self {
+            self.push_item(
+                Some(lang),
+                RcInlineItem::WStr(text.encode_utf16().collect()),
+            )
+        }
 
-    pub(crate) fn write_c_numeric(
-        w: &mut dyn Write,
-        c_numeric: impl std::fmt::Display,
-    ) -> Result<(), IOError> {
-        if std::mem::size_of_val(&c_numeric) > 2 {
-            write!(w, "{}L", c_numeric)
-        } else {
-            write!(w, "{}", c_numeric)
+        pub fn lang_specific_bytes(self, lang: crate::Lang, data: &[u8]) -> Self {
+            self.push_item(Some(lang), RcInlineItem::Str(data.to_vec()))
         }
     }
 
-    pub(crate) fn write_c_uchar(
-        w: &mut dyn Write,
-        c_uchar: winapi::ctypes::c_uchar,
-    ) -> Result<(), IOError> {
-        write_c_numeric(w, c_uchar)
-    }
+    // Resource compilers reject longer string literals; RCDATA items are
+    // simply concatenated, so longer strings are split.
+    const MAX_LITERAL_LEN: usize = 4000;
 
-    pub(crate) fn write_c_int(
-        w: &mut dyn Write,
-        c_int: winapi::ctypes::c_int,
-    ) -> Result<(), IOError> {
-        write_c_numeric(w, c_int)
-    }
+    impl RcInlineData {
+        pub(crate) fn is_missing_for_lang(&self, l: crate::Lang) -> bool {
+            self.items.get(l).is_none()
+        }
 
-    pub(crate) fn write_c_long(
-        w: &mut dyn Write,
-        c_long: winapi::ctypes::c_long,
-    ) -> Result<(), IOError> {
-        write_c_numeric(w, c_long)
-    }
+        pub(crate) fn write_resource_header_extras(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+        ) -> Result<(), std::io::Error> {
+            crate::codegen::write_extra_info(w, self.extra_info.get(l))
+        }
+
+        pub(crate) fn write_resource_segment(
+            &self,
+            w: &mut dyn std::io::Write,
+            l: crate::Lang,
+        ) -> Result<(), std::io::Error> {
+            let items = self.items.get(l).expect("unreachable!");
+            let mut literals = Vec::new();
+            for item in items.iter() {
+                let literal = match item {
+                    RcInlineItem::U16(value) => value.to_string(),
+                    // the suffix makes a DWORD for every compiler, windres too
+                    RcInlineItem::U32(value) => format!("{}L", value),
+                    RcInlineItem::Str(bytes) if !bytes.is_ascii() => {
+                        crate::codegen::push_data_literals(&mut literals, bytes)?;
+                        continue;
+                    }
+                    RcInlineItem::Str(bytes) => {
+                        for chunk in bytes.chunks(MAX_LITERAL_LEN) {
+                            let mut literal = Vec::new();
+                            crate::codegen::write_narrow_bytes(&mut literal, chunk)?;
+                            literals.push(DataLiteral::new(literal));
+                        }
+                        continue;
+                    }
+                    RcInlineItem::WStr(units) => {
+                        for chunk in units.chunks(MAX_LITERAL_LEN) {
+                            let mut literal = Vec::new();
+                            crate::codegen::write_wide_units(&mut literal, chunk)?;
+                            literals.push(DataLiteral::new(literal));
+                        }
+                        continue;
+                    }
+                };
+                literals.push(DataLiteral::new(literal.into_bytes()));
+            }
+            write!(w, "{{\n")?;
+            crate::codegen::write_data_literals(w, &literals)?;
+            write!(w, "}}\n")?;
+            Ok(())
+        }
+
+        pub(crate) fn translatable_texts(&self, _l: crate::Lang) -> Vec<(String, String)> {
+            Vec::new()
+        }
+
+        pub(crate) fn style_conflicts(&self, _l: crate::Lang) -> Vec<String> {
+            Vec::new()
+        }
+
+        pub(crate) fn defined_strings(&self, _l: crate::Lang) -> Vec<(crate::Id, String)> {
+            Vec::new()
+        }
+    }
+}
+
+#[allow(clippy::write_with_newline)]
+pub mod user_defined {
+    use crate::rc_inline::RcInlineData;
+    use crate::{interner, CowPath, CowStr, Id, IdOrName, OptionLangSpecific};
+    use std::borrow::Cow;
+    use std::io;
+    use std::path::Path;
+    use std::rc::Rc;
+
+    #[derive(Clone, PartialEq, Debug)]
+    pub(crate) enum UserDefinedContents {
+        RcInline(RcInlineData),
+        External(CowPath),
+    }
+
+    #[derive(Clone, Default, PartialEq, Debug)]
+    pub(crate) struct UserDefinedData {
+        pub(crate) type_id: Option<IdOrName>,
+        pub(crate) contents: OptionLangSpecific<UserDefinedContents>,
+    }
+
+    impl From<CowPath> for UserDefinedData {
+        fn from(path: CowPath) -> Self {
+            let mut contents = OptionLangSpecific::default();
+            contents.insert_universal(UserDefinedContents::External(path));
+            UserDefinedData {
+                type_id: None,
+                contents,
+            }
+        }
+    }
+
+    impl UserDefinedData {
+        pub(crate) fn write_script_segment(
+            &self,
+            w: &mut dyn io::Write,
+            l: crate::Lang,
+            id_or_name: IdOrName,
+        ) -> Result<(), io::Error> {
+            let contents = match self.contents.get(l) {
+                Some(contents) => contents,
+                None => return Ok(()),
+            };
+            let type_id = match &self.type_id {
+                Some(type_id) => type_id,
+                None => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        "user-defined resource has no resource type",
+                    ))
+                }
+            };
+            match contents {
+                UserDefinedContents::External(path) => {
+                    crate::codegen::write_user_defined_file_resource(
+                        w, l, id_or_name, type_id, path,
+                    )
+                }
+                UserDefinedContents::RcInline(data) => {
+                    if data.is_missing_for_lang(l) {
+                        return Ok(());
+                    }
+                    crate::codegen::write_user_defined_resource_header(w, l, id_or_name, type_id)?;
+                    data.write_resource_header_extras(w, l)?;
+                    write!(w, "\n")?;
+                    data.write_resource_segment(w, l)
+                }
+            }
+        }
+
+        pub(crate) fn referenced_files(&self) -> Vec<&Path> {
+            self.contents
+                .0
+                .values()
+                .filter_map(|contents| match contents {
+                    UserDefinedContents::External(path) => Some(path.as_ref()),
+                    UserDefinedContents::RcInline(_) => None,
+                })
+                .collect()
+        }
+    }
+
+    impl crate::resource::UserDefined {
+        // The type written on the header line, a name like "SHADER" or a
+        // number.
+        pub fn with_type(mut self, type_id: impl Into<IdOrName>) -> Self {
+            Rc::make_mut(&mut self.0).type_id = Some(type_id.into());
+            self
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    pub struct UserDefinedBuilder(UserDefinedData);
+    builder_implement_priv_default!(UserDefinedBuilder);
+    builder_build_method!(UserDefinedBuilder, crate::resource::UserDefined);
+
+    // The data is given for all languages, or for one language, which then
+    // uses it instead of the universal data.
+    impl UserDefinedBuilder {
+        // Resource compilers upper-case type names, e.g. "png" becomes "PNG".
+        pub fn type_name(mut self, name: impl Into<CowStr>) -> Self {
+            self.0.type_id = Some(IdOrName::Name(interner::intern(name.into())));
+            self
+        }
+
+        // Numbers up to 255 are the predefined types, e.g. 24 is RT_MANIFEST.
+        pub fn type_id(mut self, id: impl Into<Id>) -> Self {
+            self.0.type_id = Some(IdOrName::Id(id.into()));
+            self
+        }
+
+        pub fn data_bytes(mut self, data: &[u8]) -> Self {
+            let contents = UserDefinedContents::RcInline(RcInlineData::from_bytes(data));
+            self.0.contents.insert_universal(contents);
+            self
+        }
+
+        pub fn data_file(mut self, path: impl AsRef<Path>) -> Self {
+            let contents = UserDefinedContents::External(Cow::Owned(path.as_ref().to_owned()));
+            self.0.contents.insert_universal(contents);
+            self
+        }
+
+        pub fn lang_specific_data_bytes(mut self, lang: crate::Lang, data: &[u8]) -> Self {
+            let contents = UserDefinedContents::RcInline(RcInlineData::from_bytes(data));
+            self.0.contents.insert_lang_specific(lang, contents);
+            self
+        }
+
+        pub fn lang_specific_data_file(
+            mut self,
+            lang: crate::Lang,
+            path: impl AsRef<Path>,
+        ) -> Self {
+            let contents = UserDefinedContents::External(Cow::Owned(path.as_ref().to_owned()));
+            self.0.contents.insert_lang_specific(lang, contents);
+            self
+        }
+    }
+}
+
+// What `#[derive(Resource)]` from the resw-derive crate expands to calls
+// into; not meant to be used directly.
+#[doc(hidden)]
+#[allow(clippy::write_with_newline)]
+pub mod derive_support {
+    use crate::{IdOrName, Lang};
+    use std::io;
+    use std::path::Path;
+
+    pub enum ResourceType {
+        // a type the resource compiler knows by keyword, e.g. "TYPELIB"
+        Keyword(&'static str),
+        UserDefined(IdOrName),
+    }
+
+    pub fn write_file_resource(
+        w: &mut dyn io::Write,
+        l: Lang,
+        id_or_name: IdOrName,
+        ty: ResourceType,
+        path: &Path,
+    ) -> io::Result<()> {
+        match ty {
+            ResourceType::Keyword(keyword) => {
+                crate::codegen::write_path_only_resource(w, l, id_or_name, keyword, path)
+            }
+            ResourceType::UserDefined(type_id) => {
+                crate::codegen::write_user_defined_file_resource(w, l, id_or_name, &type_id, path)
+            }
+        }
+    }
+
+    pub fn write_data_resource(
+        w: &mut dyn io::Write,
+        l: Lang,
+        id_or_name: IdOrName,
+        ty: ResourceType,
+        data: &[u8],
+    ) -> io::Result<()> {
+        match ty {
+            ResourceType::Keyword(keyword) => {
+                crate::codegen::write_resource_header(w, l, id_or_name, keyword)?
+            }
+            ResourceType::UserDefined(type_id) => {
+                crate::codegen::write_user_defined_resource_header(w, l, id_or_name, &type_id)?
+            }
+        }
+        write!(w, "\n")?;
+        crate::codegen::write_raw_data_block(w, data)
+    }
+}
+
+#[cfg(feature = "derive")]
+pub use resw_derive::Resource;
+
+pub mod registry {
+    use crate::{CowPath, CowStr};
+
+    #[derive(PartialEq, Debug)]
+    pub(crate) enum RegistryData {
+        Script(CowStr),
+        External(CowPath),
+    }
+
+    impl From<CowPath> for RegistryData {
+        fn from(path: CowPath) -> Self {
+            RegistryData::External(path)
+        }
+    }
+
+    // Only holds script text, so a script from a file can't be added to.
+    #[derive(Default, PartialEq, Debug)]
+    pub struct RegistryBuilder(CowStr);
+
+    impl crate::PrivDefault for RegistryBuilder {
+        fn priv_default() -> Self {
+            RegistryBuilder::default()
+        }
+    }
+
+    impl RegistryBuilder {
+        pub fn build(self) -> crate::resource::Registry {
+            crate::resource::Registry(std::rc::Rc::new(RegistryData::Script(self.0)))
+        }
+    }
+
+    impl crate::ResourceBuilder for RegistryBuilder {
+        type Resource = crate::resource::Registry;
+
+        fn new() -> Self {
+            <Self as crate::PrivDefault>::priv_default()
+        }
+
+        fn build(self) -> crate::resource::Registry {
+            RegistryBuilder::build(self)
+        }
+    }
+
+    impl RegistryBuilder {
+        // The script text, embedded as is in UTF-8, e.g.
+        // `HKCR { NoRemove CLSID { ForceRemove {...} = s 'Widget' } }`.
+        pub fn script(mut self, script: impl Into<CowStr>) -> Self {
+            self.0 = script.into();
+            self
+        }
+
+        // Appends a line to the script, for scripts assembled piece by piece.
+        pub fn line(mut self, line: impl AsRef<str>) -> Self {
+            let script = self.0.to_mut();
+            script.push_str(line.as_ref());
+            script.push_str("\r\n");
+            self
+        }
+    }
+}
+
+pub mod dlg_init {
+    use crate::sys::minwindef::{DWORD, WORD};
+    use crate::{Id, MultiLangText};
+    use std::io;
+
+    // The Win16 message numbers Visual Studio writes, which MFC maps to
+    // LB_ADDSTRING and CB_ADDSTRING.
+    const LIST_BOX_ADD_STRING: WORD = 0x0401;
+    const COMBO_BOX_ADD_STRING: WORD = 0x0403;
+
+    #[derive(PartialEq, Debug)]
+    struct DlgInitEntry {
+        control: Id,
+        message: WORD,
+        strings: Vec<MultiLangText>,
+    }
+
+    #[derive(Default, PartialEq, Debug)]
+    pub(crate) struct DlgInitData(Vec<DlgInitEntry>);
+
+    #[derive(PartialEq, Debug)]
+    pub struct DlgInitBuilder(DlgInitData);
+    builder_implement_priv_default!(DlgInitBuilder);
+    builder_build_method!(DlgInitBuilder, crate::resource::DlgInit);
+
+    impl DlgInitBuilder {
+        fn add_strings(
+            mut self,
+            control: Id,
+            message: WORD,
+            strings: impl IntoIterator<Item = impl Into<MultiLangText>>,
+        ) -> Self {
+            let strings = strings.into_iter().map(Into::into).collect();
+            (self.0).0.push(DlgInitEntry {
+                control,
+                message,
+                strings,
+            });
+            self
+        }
+
+        // Strings added in order; one without text for a language is left out
+        // there.
+        pub fn combo_box(
+            self,
+            control: impl Into<Id>,
+            strings: impl IntoIterator<Item = impl Into<MultiLangText>>,
+        ) -> Self {
+            self.add_strings(control.into(), COMBO_BOX_ADD_STRING, strings)
+        }
+
+        pub fn list_box(
+            self,
+            control: impl Into<Id>,
+            strings: impl IntoIterator<Item = impl Into<MultiLangText>>,
+        ) -> Self {
+            self.add_strings(control.into(), LIST_BOX_ADD_STRING, strings)
+        }
+    }
+
+    impl DlgInitData {
+        fn texts(&self, l: crate::Lang) -> impl Iterator<Item = (&DlgInitEntry, &str)> {
+            self.0.iter().flat_map(move |entry| {
+                entry
+                    .strings
+                    .iter()
+                    .filter_map(move |text| Some((entry, text.0.get(l)?.as_ref())))
+            })
+        }
+
+        pub(crate) fn is_missing_for_lang(&self, l: crate::Lang) -> bool {
+            self.texts(l).next().is_none()
+        }
+
+        // Each string is a control id, the message adding it, the byte length
+        // and the null terminated string, unaligned; a zero WORD ends the list.
+        // MFC passes the strings to the A version of SendDlgItemMessage, so
+        // they are in the script's code page.
+        pub(crate) fn encode(&self, l: crate::Lang) -> Result<Vec<u8>, io::Error> {
+            let code_page = crate::codegen::current_code_page();
+            let mut data = Vec::new();
+            for (entry, text) in self.texts(l) {
+                if !text.is_ascii() && code_page != 65001 {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!(
+                            "DLGINIT text {:?} can't be written in code page {}, only ASCII can",
+                            text, code_page
+                        ),
+                    ));
+                }
+                data.extend_from_slice(&entry.control.0.to_le_bytes());
+                data.extend_from_slice(&entry.message.to_le_bytes());
+                data.extend_from_slice(&((text.len() + 1) as DWORD).to_le_bytes());
+                data.extend_from_slice(text.as_bytes());
+                data.push(0);
+            }
+            data.extend_from_slice(&[0, 0]);
+            Ok(data)
+        }
+
+        pub(crate) fn translatable_texts(&self, l: crate::Lang) -> Vec<(String, String)> {
+            let mut texts = Vec::new();
+            for entry in self.0.iter() {
+                for (idx, text) in entry.strings.iter().enumerate() {
+                    if let Some(text) = text.0.get(l) {
+                        let context = format!("control {} string {}", entry.control, idx);
+                        texts.push((context, text.to_string()));
+                    }
+                }
+            }
+            texts
+        }
+    }
+}
+
+pub mod res_file {
+    use crate::sys::minwindef::{DWORD, WORD};
+    use crate::{Id, IdOrName, Lang};
+    use std::borrow::Cow;
+    use std::io;
+    use std::path::Path;
+
+    #[derive(Clone, PartialEq, Debug)]
+    pub struct ResEntry {
+        pub type_id: IdOrName,
+        pub name: IdOrName,
+        pub lang: Lang,
+        pub memory_flags: WORD,
+        pub data_version: DWORD,
+        pub version: DWORD,
+        pub characteristics: DWORD,
+        pub data: Vec<u8>,
+    }
+
+    fn invalid_data(msg: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, msg.to_owned())
+    }
+
+    struct Reader<'a> {
+        data: &'a [u8],
+        pos: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn at(data: &'a [u8], pos: usize) -> Self {
+            Reader { data, pos }
+        }
+
+        fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+            let end = self
+                .pos
+                .checked_add(len)
+                .filter(|&end| end <= self.data.len())
+                .ok_or_else(|| invalid_data("unexpected end of resource data"))?;
+            let v = &self.data[self.pos..end];
+            self.pos = end;
+            Ok(v)
+        }
+
+        fn word(&mut self) -> io::Result<WORD> {
+            let v = self.bytes(2)?;
+            Ok(WORD::from_le_bytes([v[0], v[1]]))
+        }
+
+        fn dword(&mut self) -> io::Result<DWORD> {
+            let v = self.bytes(4)?;
+            Ok(DWORD::from_le_bytes([v[0], v[1], v[2], v[3]]))
+        }
+
+        fn align_dword(&mut self) {
+            self.pos = (self.pos + 3) & !3;
+        }
+    }
+
+    fn utf16_to_name(units: &[u16]) -> io::Result<IdOrName> {
+        let name = String::from_utf16(units).map_err(|_| invalid_data("invalid resource name"))?;
+        Ok(IdOrName::Name(Cow::Owned(name)))
+    }
+
+    fn read_id_or_name(r: &mut Reader) -> io::Result<IdOrName> {
+        let first = r.word()?;
+        if first == 0xFFFF {
+            return Ok(IdOrName::Id(Id(r.word()?)));
+        }
+        let mut units = Vec::new();
+        let mut unit = first;
+        while unit != 0 {
+            units.push(unit);
+            unit = r.word()?;
+        }
+        utf16_to_name(&units)
+    }
+
+    pub fn parse_res(data: &[u8]) -> io::Result<Vec<ResEntry>> {
+        let mut entries = Vec::new();
+        let mut pos = 0;
+        while pos + 8 <= data.len() {
+            let mut r = Reader::at(data, pos);
+            let data_size = r.dword()? as usize;
+            let header_size = r.dword()? as usize;
+            let type_id = read_id_or_name(&mut r)?;
+            let name = read_id_or_name(&mut r)?;
+            r.align_dword();
+            let data_version = r.dword()?;
+            let memory_flags = r.word()?;
+            let lang_id = r.word()?;
+            let version = r.dword()?;
+            let characteristics = r.dword()?;
+            // the header must cover what was just read, otherwise a zeroed
+            // entry would be parsed over and over again
+            if header_size < r.pos - pos {
+                return Err(invalid_data("resource header size is too small"));
+            }
+            let mut r = Reader::at(data, pos + header_size);
+            let payload = r.bytes(data_size)?;
+            // the leading entry with type 0 only marks the file as a 32-bit .res
+            if type_id != IdOrName::Id(Id(0)) {
+                entries.push(ResEntry {
+                    type_id,
+                    name,
+                    lang: Lang::from_lang_id(lang_id),
+                    memory_flags,
+                    data_version,
+                    version,
+                    characteristics,
+                    data: payload.to_vec(),
+                });
+            }
+            r.align_dword();
+            if r.pos <= pos {
+                return Err(invalid_data("resource entry has no size"));
+            }
+            pos = r.pos;
+        }
+        Ok(entries)
+    }
+
+    pub fn read_res_file(path: impl AsRef<Path>) -> io::Result<Vec<ResEntry>> {
+        let data = std::fs::read(path)?;
+        parse_res(&data)
+    }
+
+    const RESOURCE_DATA_DIRECTORY_INDEX: usize = 2;
+
+    fn read_pe_dir_entry_id(rsrc: &[u8], name: DWORD) -> io::Result<IdOrName> {
+        if name & 0x8000_0000 == 0 {
+            return Ok(IdOrName::Id(Id(name as WORD)));
+        }
+        let mut r = Reader::at(rsrc, (name & 0x7FFF_FFFF) as usize);
+        let len = r.word()? as usize;
+        let units = r
+            .bytes(len * 2)?
+            .chunks(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect::<Vec<_>>();
+        utf16_to_name(&units)
+    }
+
+    fn read_pe_dir(rsrc: &[u8], offset: usize) -> io::Result<Vec<(IdOrName, DWORD)>> {
+        let mut r = Reader::at(rsrc, offset + 12);
+        let count = r.word()? as usize + r.word()? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for _ in 0..count {
+            let name = r.dword()?;
+            let target = r.dword()?;
+            entries.push((read_pe_dir_entry_id(rsrc, name)?, target));
+        }
+        Ok(entries)
+    }
+
+    fn read_pe_subdir(rsrc: &[u8], target: DWORD) -> io::Result<Vec<(IdOrName, DWORD)>> {
+        if target & 0x8000_0000 == 0 {
+            return Err(invalid_data("expected resource subdirectory"));
+        }
+        read_pe_dir(rsrc, (target & 0x7FFF_FFFF) as usize)
+    }
+
+    pub fn parse_pe(data: &[u8]) -> io::Result<Vec<ResEntry>> {
+        let pe_offset = Reader::at(data, 0x3C).dword()? as usize;
+        let mut r = Reader::at(data, pe_offset);
+        if r.bytes(4)? != b"PE\0\0" {
+            return Err(invalid_data("not a PE image"));
+        }
+        let _machine = r.word()?;
+        let section_count = r.word()? as usize;
+        let _ = r.bytes(12)?;
+        let optional_header_size = r.word()? as usize;
+        let _characteristics = r.word()?;
+        let optional_header = r.pos;
+        let data_directories = match Reader::at(data, optional_header).word()? {
+            0x10b => optional_header + 96,
+            0x20b => optional_header + 112,
+            _ => return Err(invalid_data("unknown PE optional header")),
+        };
+        let mut r = Reader::at(data, data_directories + RESOURCE_DATA_DIRECTORY_INDEX * 8);
+        let rsrc_rva = r.dword()?;
+        let rsrc_size = r.dword()?;
+        if rsrc_rva == 0 || rsrc_size == 0 {
+            return Ok(Vec::new());
+        }
+        let mut sections = Vec::with_capacity(section_count);
+        let mut r = Reader::at(data, optional_header + optional_header_size);
+        for _ in 0..section_count {
+            let _ = r.bytes(8)?;
+            let virtual_size = r.dword()?;
+            let virtual_address = r.dword()?;
+            let raw_size = r.dword()?;
+            let raw_pointer = r.dword()?;
+            let _ = r.bytes(16)?;
+            sections.push((virtual_address, virtual_size.max(raw_size), raw_pointer));
+        }
+        let rva_to_offset = |rva: DWORD| -> io::Result<usize> {
+            let (va, _, raw) = sections
+                .iter()
+                .find(|&&(va, size, _)| rva >= va && rva - va < size)
+                .ok_or_else(|| invalid_data("resource address outside of any section"))?;
+            (rva - va)
+                .checked_add(*raw)
+                .map(|offset| offset as usize)
+                .ok_or_else(|| invalid_data("resource address outside of the file"))
+        };
+        let rsrc_offset = rva_to_offset(rsrc_rva)?;
+        let rsrc = Reader::at(data, rsrc_offset).bytes(rsrc_size as usize)?;
+
+        let mut entries = Vec::new();
+        for (type_id, type_target) in read_pe_dir(rsrc, 0)? {
+            for (name, name_target) in read_pe_subdir(rsrc, type_target)? {
+                for (lang, data_entry) in read_pe_subdir(rsrc, name_target)? {
+                    let lang_id = match lang {
+                        IdOrName::Id(Id(v)) => v,
+                        IdOrName::Name(_) => return Err(invalid_data("named resource language")),
+                    };
+                    let mut r = Reader::at(rsrc, data_entry as usize);
+                    let data_rva = r.dword()?;
+                    let data_size = r.dword()?;
+                    let payload =
+                        Reader::at(data, rva_to_offset(data_rva)?).bytes(data_size as usize)?;
+                    entries.push(ResEntry {
+                        type_id: type_id.clone(),
+                        name: name.clone(),
+                        lang: Lang::from_lang_id(lang_id),
+                        memory_flags: 0,
+                        data_version: 0,
+                        version: 0,
+                        characteristics: 0,
+                        data: payload.to_vec(),
+                    });
+                }
+            }
+        }
+        Ok(entries)
+    }
+
+    pub fn read_pe_file(path: impl AsRef<Path>) -> io::Result<Vec<ResEntry>> {
+        let data = std::fs::read(path)?;
+        parse_pe(&data)
+    }
+
+    const RT_STRING: WORD = 6;
+
+    // Decodes the RT_STRING blocks among `entries`. Block n holds the strings
+    // with ids (n - 1) * 16 to n * 16 - 1, each prefixed by its length in
+    // UTF-16 units; empty slots are left out.
+    pub fn extract_strings(entries: &[ResEntry]) -> io::Result<Vec<(Lang, Id, String)>> {
+        let mut strings = Vec::new();
+        for entry in entries {
+            let block = match (&entry.type_id, &entry.name) {
+                (IdOrName::Id(Id(RT_STRING)), IdOrName::Id(Id(block))) if *block > 0 => {
+                    *block as u32
+                }
+                _ => continue,
+            };
+            // ids are 16 bits, so there are no blocks beyond 4096
+            if block > 0x1000 {
+                return Err(invalid_data("string table block out of range"));
+            }
+            let mut r = Reader::at(&entry.data, 0);
+            for index in 0..16u32 {
+                if r.pos >= entry.data.len() {
+                    break;
+                }
+                let len = r.word()? as usize;
+                if len == 0 {
+                    continue;
+                }
+                let units = r
+                    .bytes(len * 2)?
+                    .chunks(2)
+                    .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                    .collect::<Vec<_>>();
+                let text = String::from_utf16(&units)
+                    .map_err(|_| invalid_data("invalid string table text"))?;
+                let id = (block - 1) * 16 + index;
+                strings.push((entry.lang, Id(id as u16), text));
+            }
+        }
+        strings.sort_by(|a, b| (a.0, &a.1).cmp(&(b.0, &b.1)));
+        Ok(strings)
+    }
+
+    pub fn extract_string_table(
+        entries: &[ResEntry],
+    ) -> io::Result<crate::string_table::StringTableBuilder> {
+        let mut builder = crate::resource::StringTable::from_builder();
+        for (lang, id, text) in extract_strings(entries)? {
+            builder = builder.lang_specific_string(lang, id, text);
+        }
+        Ok(builder)
+    }
+}
+
+pub mod message_table {
+    use crate::sys::minwindef::{DWORD, WORD};
+    use crate::{CowPath, MultiLangText};
+    use std::io;
+
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum Severity {
+        Success,
+        Informational,
+        Warning,
+        Error,
+    }
+
+    #[derive(PartialEq, Debug)]
+    pub(crate) struct Message {
+        pub(crate) id: DWORD,
+        pub(crate) text: MultiLangText,
+    }
+
+    #[derive(PartialEq, Debug)]
+    pub(crate) enum MessageTableData {
+        Messages(Vec<Message>),
+        External(CowPath),
+        // the builder was given a message it can't hold, an error once the
+        // table is written
+        Invalid(String),
+    }
+
+    impl From<CowPath> for MessageTableData {
+        fn from(path: CowPath) -> Self {
+            MessageTableData::External(path)
+        }
+    }
+
+    // Only holds messages, or the error that ended adding them, so a table
+    // from a file can't be added to.
+    #[derive(PartialEq, Debug)]
+    pub struct MessageTableBuilder(Result<Vec<Message>, String>);
+
+    impl crate::PrivDefault for MessageTableBuilder {
+        fn priv_default() -> Self {
+            MessageTableBuilder(Ok(Vec::new()))
+        }
+    }
+
+    impl MessageTableBuilder {
+        pub fn build(self) -> crate::resource::MessageTable {
+            let data = match self.0 {
+                Ok(messages) => MessageTableData::Messages(messages),
+                Err(error) => MessageTableData::Invalid(error),
+            };
+            crate::resource::MessageTable(std::rc::Rc::new(data))
+        }
+    }
+
+    impl crate::ResourceBuilder for MessageTableBuilder {
+        type Resource = crate::resource::MessageTable;
+
+        fn new() -> Self {
+            <Self as crate::PrivDefault>::priv_default()
+        }
+
+        fn build(self) -> crate::resource::MessageTable {
+            MessageTableBuilder::build(self)
+        }
+    }
+
+    impl MessageTableBuilder {
+        // The message id FormatMessage takes is made of the severity, the
+        // facility (up to 0xFFF) and the code, as HRESULTs are. Texts are
+        // stored as given; mc.exe ends each line with "\r\n". A facility out
+        // of range or an id given twice fails writing the table.
+        pub fn message(
+            mut self,
+            code: WORD,
+            severity: Severity,
+            facility: WORD,
+            text: impl Into<MultiLangText>,
+        ) -> Self {
+            let id = ((severity as DWORD) << 30) | ((facility as DWORD) << 16) | code as DWORD;
+            let messages = match &mut self.0 {
+                Ok(messages) => messages,
+                Err(_) => return self,
+            };
+            let error = if facility > 0xFFF {
+                Some(format!("facility 0x{:X} is out of range", facility))
+            } else if messages.iter().any(|message| message.id == id) {
+                Some(format!("message 0x{:08X} is declared twice", id))
+            } else {
+                None
+            };
+            if let Some(error) = error {
+                self.0 = Err(error);
+                return self;
+            }
+            messages.push(Message {
+                id,
+                text: text.into(),
+            });
+            self
+        }
+    }
+
+    const MESSAGE_RESOURCE_UNICODE: WORD = 0x0001;
+
+    // The MESSAGE_RESOURCE_DATA of a language: blocks of consecutive ids, then
+    // each block's entries, their UTF-16 text null terminated and padded to a
+    // multiple of four bytes. None if no message has text for the language.
+    pub(crate) fn encode(messages: &[Message], l: crate::Lang) -> io::Result<Option<Vec<u8>>> {
+        let mut texts: Vec<(DWORD, &str)> = messages
+            .iter()
+            .filter_map(|message| Some((message.id, message.text.0.get(l)?.as_ref())))
+            .collect();
+        if texts.is_empty() {
+            return Ok(None);
+        }
+        texts.sort_by_key(|&(id, _)| id);
+
+        let mut blocks: Vec<(DWORD, DWORD, Vec<u8>)> = Vec::new();
+        for (id, text) in texts {
+            let mut entry = Vec::new();
+            let units: Vec<u16> = text.encode_utf16().chain(Some(0)).collect();
+            let len = (4 + units.len() * 2 + 3) & !3;
+            if len > WORD::MAX as usize {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("message 0x{:08X} is too long", id),
+                ));
+            }
+            entry.extend_from_slice(&(len as WORD).to_le_bytes());
+            entry.extend_from_slice(&MESSAGE_RESOURCE_UNICODE.to_le_bytes());
+            for unit in units {
+                entry.extend_from_slice(&unit.to_le_bytes());
+            }
+            entry.resize(len, 0);
+            match blocks.last_mut() {
+                Some((_, high, entries)) if high.checked_add(1) == Some(id) => {
+                    *high = id;
+                    entries.extend(entry);
+                }
+                _ => blocks.push((id, id, entry)),
+            }
+        }
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&(blocks.len() as DWORD).to_le_bytes());
+        let mut offset = 4 + blocks.len() * 12;
+        for (low, high, entries) in blocks.iter() {
+            data.extend_from_slice(&low.to_le_bytes());
+            data.extend_from_slice(&high.to_le_bytes());
+            data.extend_from_slice(&(offset as DWORD).to_le_bytes());
+            offset += entries.len();
+        }
+        for (_, _, entries) in blocks.iter() {
+            data.extend_from_slice(entries);
+        }
+        Ok(Some(data))
+    }
+}
+
+pub mod message_compiler {
+    use crate::sys::minwindef::{DWORD, WORD};
+    use crate::Lang;
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::path::Path;
+
+    #[derive(PartialEq, Debug)]
+    pub struct McMessage {
+        pub code: WORD,
+        pub severity: u8,
+        pub facility: WORD,
+        pub symbolic_name: Option<String>,
+        pub texts: BTreeMap<Lang, String>,
+    }
+
+    impl McMessage {
+        pub fn message_id(&self) -> DWORD {
+            ((self.severity as DWORD) << 30) | ((self.facility as DWORD) << 16) | self.code as DWORD
+        }
+    }
+
+    #[derive(Default, PartialEq, Debug)]
+    pub struct McFile {
+        pub messages: Vec<McMessage>,
+    }
+
+    fn error_at(line: usize, msg: String) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: {}", line, msg),
+        )
+    }
+
+    fn parse_number(v: &str) -> Option<DWORD> {
+        let v = v.trim();
+        if let Some(hex) = v.strip_prefix("0x").or_else(|| v.strip_prefix("0X")) {
+            DWORD::from_str_radix(hex, 16).ok()
+        } else {
+            v.parse().ok()
+        }
+    }
+
+    // Parses `(Name=0x1:SYMBOL Other=0x2:SYMBOL)` into (name, value) pairs.
+    fn parse_name_list(line: usize, list: &str) -> io::Result<Vec<(String, DWORD)>> {
+        let inner = list
+            .trim()
+            .strip_prefix('(')
+            .and_then(|l| l.strip_suffix(')'))
+            .ok_or_else(|| error_at(line, "expected parenthesized name list".to_owned()))?;
+        let mut names = Vec::new();
+        for entry in inner.split_whitespace() {
+            let (name, rest) = entry
+                .split_once('=')
+                .ok_or_else(|| error_at(line, format!("invalid name list entry `{}`", entry)))?;
+            let value = rest.split(':').next().unwrap_or("");
+            let value = parse_number(value)
+                .ok_or_else(|| error_at(line, format!("invalid value in `{}`", entry)))?;
+            names.push((name.to_owned(), value));
+        }
+        Ok(names)
+    }
+
+    fn lookup<'a>(names: &'a [(String, DWORD)], name: &str) -> Option<&'a DWORD> {
+        names
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+
+    pub fn parse_mc(source: &str) -> io::Result<McFile> {
+        let mut severity_names: Vec<(String, DWORD)> = vec![
+            ("Success".to_owned(), 0),
+            ("Informational".to_owned(), 1),
+            ("Warning".to_owned(), 2),
+            ("Error".to_owned(), 3),
+        ];
+        let mut facility_names: Vec<(String, DWORD)> = vec![
+            ("System".to_owned(), 0x0FF),
+            ("Application".to_owned(), 0xFFF),
+        ];
+        let mut language_names: Vec<(String, DWORD)> = vec![("English".to_owned(), 0x409)];
+
+        let mut file = McFile::default();
+        let mut severity = 0u8;
+        let mut facility: WORD = 0;
+        let mut last_code: Option<DWORD> = None;
+        let mut lines = source.lines().enumerate().map(|(idx, l)| (idx + 1, l));
+        while let Some((line_no, line)) = lines.next() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with(';') {
+                continue;
+            }
+            let (key, value) = trimmed
+                .split_once('=')
+                .map(|(k, v)| (k.trim(), v.trim()))
+                .ok_or_else(|| error_at(line_no, format!("unexpected `{}`", trimmed)))?;
+            let mut value = value.to_owned();
+            if value.starts_with('(') {
+                while !value.ends_with(')') {
+                    let (_, next) = lines
+                        .next()
+                        .ok_or_else(|| error_at(line_no, "unterminated name list".to_owned()))?;
+                    value.push(' ');
+                    value.push_str(next.trim());
+                }
+            }
+            let key = key.to_ascii_lowercase();
+            match key.as_str() {
+                "severitynames" => severity_names = parse_name_list(line_no, &value)?,
+                "facilitynames" => facility_names = parse_name_list(line_no, &value)?,
+                "languagenames" => language_names.extend(parse_name_list(line_no, &value)?),
+                "messageidtypedef" | "messageidtypedefmacro" | "outputbase" => {}
+                "messageid" => {
+                    let code = if value.is_empty() {
+                        last_code.map_or(0, |code| code + 1)
+                    } else if let Some(offset) = value.strip_prefix('+') {
+                        // relative to the previous message, or to 0 for the first
+                        let offset = parse_number(offset)
+                            .ok_or_else(|| error_at(line_no, format!("invalid id `{}`", value)))?;
+                        last_code.unwrap_or(0).saturating_add(offset)
+                    } else {
+                        parse_number(&value)
+                            .ok_or_else(|| error_at(line_no, format!("invalid id `{}`", value)))?
+                    };
+                    if code > 0xFFFF {
+                        return Err(error_at(
+                            line_no,
+                            format!("message id {} out of range", code),
+                        ));
+                    }
+                    last_code = Some(code);
+                    file.messages.push(McMessage {
+                        code: code as WORD,
+                        severity,
+                        facility,
+                        symbolic_name: None,
+                        texts: BTreeMap::new(),
+                    });
+                }
+                "severity" | "facility" | "symbolicname" | "language" => {
+                    let message = file.messages.last_mut().ok_or_else(|| {
+                        error_at(line_no, format!("`{}` outside of a message", key))
+                    })?;
+                    match key.as_str() {
+                        "severity" => {
+                            let v = lookup(&severity_names, &value).ok_or_else(|| {
+                                error_at(line_no, format!("unknown severity `{}`", value))
+                            })?;
+                            severity = *v as u8;
+                            message.severity = severity;
+                        }
+                        "facility" => {
+                            let v = lookup(&facility_names, &value).ok_or_else(|| {
+                                error_at(line_no, format!("unknown facility `{}`", value))
+                            })?;
+                            if *v > 0xFFF {
+                                return Err(error_at(
+                                    line_no,
+                                    format!("facility 0x{:X} out of range", v),
+                                ));
+                            }
+                            facility = *v as WORD;
+                            message.facility = facility;
+                        }
+                        "symbolicname" => message.symbolic_name = Some(value),
+                        _ => {
+                            let lang_id = lookup(&language_names, &value).ok_or_else(|| {
+                                error_at(line_no, format!("unknown language `{}`", value))
+                            })?;
+                            let mut text = String::new();
+                            loop {
+                                let (_, text_line) = lines.next().ok_or_else(|| {
+                                    error_at(line_no, "unterminated message text".to_owned())
+                                })?;
+                                if text_line.trim_end() == "." {
+                                    break;
+                                }
+                                text.push_str(text_line);
+                                text.push_str("\r\n");
+                            }
+                            message
+                                .texts
+                                .insert(Lang::from_lang_id(*lang_id as WORD), text);
+                        }
+                    }
+                }
+                _ => return Err(error_at(line_no, format!("unknown keyword `{}`", key))),
+            }
+        }
+        Ok(file)
+    }
+
+    // The Rust type an insert is passed as, after its printf-style format.
+    #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+    pub enum InsertType {
+        Str,
+        Int,
+        UInt,
+        Char,
+        Pointer,
+    }
+
+    impl InsertType {
+        fn from_format(format: &str) -> io::Result<InsertType> {
+            let conversion = format.trim_start_matches(|c: char| {
+                c.is_ascii_digit() || matches!(c, '-' | '+' | '#' | ' ' | '.' | '*')
+            });
+            let spec = conversion.trim_start_matches(['l', 'h', 'w']);
+            let size = &conversion[..conversion.len() - spec.len()];
+            let ty = match spec {
+                "s" | "S" | "c" | "C" => {
+                    // FormatMessageW reads these as ANSI, the generated wrappers
+                    // only pass UTF-16
+                    if size.contains('h') || (size.is_empty() && matches!(spec, "S" | "C")) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            format!("insert format `{}` takes ANSI text", format),
+                        ));
+                    }
+                    if spec.eq_ignore_ascii_case("s") {
+                        InsertType::Str
+                    } else {
+                        InsertType::Char
+                    }
+                }
+                "d" | "i" => InsertType::Int,
+                "u" | "x" | "X" | "o" => InsertType::UInt,
+                "p" => InsertType::Pointer,
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unsupported insert format `{}`", format),
+                    ))
+                }
+            };
+            Ok(ty)
+        }
+    }
+
+    // Inserts `%1` to `%99` used by a message text, with their types; an insert
+    // without `!format!` is a string.
+    pub fn parse_inserts(text: &str) -> io::Result<BTreeMap<u8, InsertType>> {
+        let mut inserts = BTreeMap::new();
+        let mut rest = text;
+        while let Some(pos) = rest.find('%') {
+            rest = &rest[pos + 1..];
+            let digits = rest.chars().take_while(|c| c.is_ascii_digit()).count();
+            if digits == 0 {
+                // escapes like %% or %n
+                let mut chars = rest.chars();
+                chars.next();
+                rest = chars.as_str();
+                continue;
+            }
+            let index: u8 = rest[..digits.min(2)].parse().unwrap_or(0);
+            rest = &rest[digits.min(2)..];
+            if index == 0 {
+                continue;
+            }
+            let ty = match rest.strip_prefix('!').and_then(|r| r.split_once('!')) {
+                Some((format, after)) => {
+                    rest = after;
+                    InsertType::from_format(format)?
+                }
+                None => InsertType::Str,
+            };
+            if let Some(existing) = inserts.insert(index, ty) {
+                if existing != ty {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("insert %{} is used with different formats", index),
+                    ));
+                }
+            }
+        }
+        Ok(inserts)
+    }
+
+    impl McFile {
+        // Writes a Rust module with one `format_<symbolic name>` function per
+        // message, taking its inserts as typed parameters and calling FormatMessageW
+        // on the message table linked into the current module.
+        pub fn generate_format_module(&self, path: impl AsRef<Path>) -> io::Result<()> {
+            let mut messages = Vec::new();
+            for message in self.messages.iter() {
+                let mut inserts = BTreeMap::new();
+                for text in message.texts.values() {
+                    for (index, ty) in parse_inserts(text)? {
+                        if *inserts.entry(index).or_insert(ty) != ty {
+                            return Err(io::Error::new(
+                                io::ErrorKind::InvalidData,
+                                format!(
+                                    "insert %{} of message 0x{:08X} differs between languages",
+                                    index,
+                                    message.message_id()
+                                ),
+                            ));
+                        }
+                    }
+                }
+                messages.push((message, inserts));
+            }
+            crate::codegen::write_file_atomically(path.as_ref(), |w| {
+                crate::codegen::write_format_message_module(w, &messages)
+            })
+        }
+    }
+
+    pub fn read_mc_file(path: impl AsRef<Path>) -> io::Result<McFile> {
+        let source = std::fs::read_to_string(path)?;
+        parse_mc(&source)
+    }
+}
+
+#[cfg(feature = "spec")]
+#[allow(clippy::write_with_newline)]
+pub mod spec {
+    use crate::resource::VersionInfo;
+    use crate::resource::{Bitmap, Cursor, Font, Icon, Menu, MessageTable, StringTable, HTML};
+    use crate::version_info::{FileType, Version};
+    use crate::{Build, Id, IdOrName, Lang, Lint, MultiLangText, Severity};
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    pub struct BuildSpec {
+        pub languages: Vec<String>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub string_tables: Vec<StringTableSpec>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub menus: Vec<MenuSpec>,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        pub files: Vec<FileSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub version_info: Option<VersionInfoSpec>,
+    }
+
+    // Either one text for all languages, or a table keyed by language tag.
+    // The key "default" in the table supplies the fallback text.
+    #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(untagged)]
+    pub enum TextSpec {
+        Universal(String),
+        LangSpecific(BTreeMap<String, String>),
+    }
+
+    #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    pub struct StringSpec {
+        pub id: Id,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub symbol: Option<String>,
+        pub text: TextSpec,
+    }
+
+    #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    pub struct StringTableSpec {
+        pub strings: Vec<StringSpec>,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    pub struct MenuItemSpec {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub id: Option<Id>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub symbol: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub text: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        pub separator: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub items: Option<Vec<MenuItemSpec>>,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    pub struct MenuSpec {
+        pub name: IdOrName,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub symbol: Option<String>,
+        pub items: Vec<MenuItemSpec>,
+    }
+
+    #[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(rename_all = "snake_case")]
+    pub enum FileKind {
+        Bitmap,
+        Cursor,
+        Font,
+        Html,
+        Icon,
+        MessageTable,
+    }
+
+    #[derive(Clone, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    pub struct FileSpec {
+        pub kind: FileKind,
+        pub name: IdOrName,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub symbol: Option<String>,
+        pub path: PathBuf,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub lang: Option<String>,
+    }
+
+    #[derive(Clone, Copy, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(rename_all = "snake_case")]
+    pub enum FileTypeSpec {
+        Unknown,
+        App,
+        Dll,
+        Drv,
+        Font,
+        Vxd,
+        StaticLib,
+    }
+
+    // The fixed versions are written as "major.minor.patch.build". The string
+    // values are the StringFileInfo ones, `custom_strings` holds keys without
+    // a setter of their own.
+    #[derive(Clone, Default, Serialize, Deserialize, PartialEq, Debug)]
+    #[serde(deny_unknown_fields)]
+    pub struct VersionInfoSpec {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub fixed_file_version: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub fixed_product_version: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub fixed_file_type: Option<FileTypeSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub translations: Option<Vec<String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub product_name: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub product_version: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub file_description: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub file_version: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub internal_name: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub original_filename: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub company_name: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub legal_copyright: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub legal_trademarks: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub private_build: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub special_build: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        pub comments: Option<TextSpec>,
+        #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+        pub custom_strings: BTreeMap<String, TextSpec>,
+    }
+
+    fn invalid_data(msg: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, msg)
+    }
+
+    fn invalid_input(msg: String) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, msg)
+    }
+
+    fn parse_lang(tag: &str) -> io::Result<Lang> {
+        crate::lang::from_tag(tag)
+            .ok_or_else(|| invalid_data(format!("unknown language `{}`", tag)))
+    }
+
+    fn multi_lang_text(text: &TextSpec) -> io::Result<MultiLangText> {
+        match text {
+            TextSpec::Universal(text) => Ok(MultiLangText::from(text.clone())),
+            TextSpec::LangSpecific(texts) => {
+                let mut r = MultiLangText::empty();
+                for (tag, text) in texts {
+                    if tag == "default" {
+                        r.0.insert_universal(text.clone().into());
+                    } else {
+                        r = r.lang(parse_lang(tag)?, text.clone());
+                    }
+                }
+                Ok(r)
+            }
+        }
+    }
+
+    #[derive(PartialEq, Debug)]
+    enum MenuEntry {
+        Item(Id, MultiLangText),
+        Separator,
+        Popup(MultiLangText, Vec<MenuEntry>),
+    }
+
+    fn menu_entries(items: &[MenuItemSpec]) -> io::Result<Vec<MenuEntry>> {
+        let mut entries = Vec::with_capacity(items.len());
+        for item in items {
+            if item.separator {
+                entries.push(MenuEntry::Separator);
+                continue;
+            }
+            let text = item
+                .text
+                .as_ref()
+                .ok_or_else(|| invalid_data("menu item without text".to_owned()))?;
+            let text = multi_lang_text(text)?;
+            match (&item.items, &item.id) {
+                (Some(items), _) => entries.push(MenuEntry::Popup(text, menu_entries(items)?)),
+                (None, Some(id)) => entries.push(MenuEntry::Item(id.clone(), text)),
+                (None, None) => return Err(invalid_data("menu item without id".to_owned())),
+            }
+        }
+        Ok(entries)
+    }
+
+    macro_rules! apply_menu_entries {
+        ($fn_name:ident, $builder_ty:path) => {
+            fn $fn_name(mut builder: $builder_ty, entries: Vec<MenuEntry>) -> $builder_ty {
+                for entry in entries {
+                    builder = match entry {
+                        MenuEntry::Item(id, text) => builder.item(id, text),
+                        MenuEntry::Separator => builder.separator(),
+                        MenuEntry::Popup(text, entries) => {
+                            builder.popup(text, |popup| apply_popup_entries(popup, entries))
+                        }
+                    };
+                }
+                builder
+            }
+        };
+    }
+
+    apply_menu_entries!(apply_menu_entries, crate::menu::MenuBuilder);
+    apply_menu_entries!(apply_popup_entries, crate::menu::PopupBuilder);
+
+    impl VersionInfoSpec {
+        fn to_resource(&self) -> io::Result<VersionInfo> {
+            let mut builder = VersionInfo::from_builder();
+            if let Some(version) = &self.fixed_file_version {
+                builder = builder.fixed_file_version(Version::parse(version)?);
+            }
+            if let Some(version) = &self.fixed_product_version {
+                builder = builder.fixed_product_version(Version::parse(version)?);
+            }
+            if let Some(file_type) = self.fixed_file_type {
+                builder = builder.fixed_file_type(match file_type {
+                    FileTypeSpec::Unknown => FileType::UNKNOWN,
+                    FileTypeSpec::App => FileType::APP,
+                    FileTypeSpec::Dll => FileType::DLL,
+                    FileTypeSpec::Drv => FileType::DRV,
+                    FileTypeSpec::Font => FileType::FONT,
+                    FileTypeSpec::Vxd => FileType::VXD,
+                    FileTypeSpec::StaticLib => FileType::STATIC_LIB,
+                });
+            }
+            if let Some(translations) = &self.translations {
+                let langs = translations
+                    .iter()
+                    .map(|tag| parse_lang(tag))
+                    .collect::<io::Result<Vec<_>>>()?;
+                builder = builder.translations(langs);
+            }
+            macro_rules! set_text {
+                ($field:ident, $setter:ident, $lang_specific_setter:ident) => {
+                    match &self.$field {
+                        None => {}
+                        Some(TextSpec::Universal(text)) => builder = builder.$setter(text.clone()),
+                        Some(TextSpec::LangSpecific(texts)) => {
+                            for (tag, text) in texts {
+                                if tag == "default" {
+                                    builder = builder.$setter(text.clone());
+                                } else {
+                                    let lang = parse_lang(tag)?;
+                                    builder = builder.$lang_specific_setter(lang, text.clone());
+                                }
+                            }
+                        }
+                    }
+                };
+            }
+            set_text!(product_name, product_name, lang_specific_product_name);
+            set_text!(
+                product_version,
+                product_version,
+                lang_specific_product_version
+            );
+            set_text!(
+                file_description,
+                file_description,
+                lang_specific_file_description
+            );
+            set_text!(file_version, file_version, lang_specific_file_version);
+            set_text!(internal_name, internal_name, lang_specific_internal_name);
+            set_text!(
+                original_filename,
+                original_filename,
+                lang_specific_original_filename
+            );
+            set_text!(company_name, company_name, lang_specific_company_name);
+            set_text!(
+                legal_copyright,
+                legal_copyright,
+                lang_specific_legal_copyright
+            );
+            set_text!(
+                legal_trademarks,
+                legal_trademarks,
+                lang_specific_legal_trademarks
+            );
+            set_text!(private_build, private_build, lang_specific_private_build);
+            set_text!(special_build, special_build, lang_specific_special_build);
+            set_text!(comments, comments, lang_specific_comments);
+            for (key, text) in self.custom_strings.iter() {
+                match text {
+                    TextSpec::Universal(text) => {
+                        builder = builder.custom_string(key.clone(), text.clone());
+                    }
+                    TextSpec::LangSpecific(texts) => {
+                        for (tag, text) in texts {
+                            if tag == "default" {
+                                builder = builder.custom_string(key.clone(), text.clone());
+                            } else {
+                                let lang = parse_lang(tag)?;
+                                builder = builder.lang_specific_custom_string(
+                                    lang,
+                                    key.clone(),
+                                    text.clone(),
+                                );
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(builder.build())
+        }
+    }
+
+    impl BuildSpec {
+        pub fn from_toml_str(s: &str) -> io::Result<Self> {
+            toml::from_str(s).map_err(|e| invalid_data(e.to_string()))
+        }
+
+        pub fn from_json_str(s: &str) -> io::Result<Self> {
+            serde_json::from_str(s).map_err(|e| invalid_data(e.to_string()))
+        }
+
+        pub fn to_toml_string(&self) -> io::Result<String> {
+            toml::to_string_pretty(self).map_err(|e| invalid_data(e.to_string()))
+        }
+
+        pub fn to_json_string(&self) -> io::Result<String> {
+            serde_json::to_string_pretty(self).map_err(|e| invalid_data(e.to_string()))
+        }
+
+        // Files ending in `.json` are read as JSON, everything else as TOML.
+        pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+            let path = path.as_ref();
+            let content = std::fs::read_to_string(path)?;
+            let is_json = path
+                .extension()
+                .map(|ext| ext.eq_ignore_ascii_case("json"))
+                .unwrap_or(false);
+            if is_json {
+                Self::from_json_str(&content)
+            } else {
+                Self::from_toml_str(&content)
+            }
+        }
+
+        // Relative file paths are resolved against `base_dir`. A string id or
+        // resource given more than once is an error.
+        pub fn to_build(&self, base_dir: &Path) -> io::Result<Build> {
+            let languages = self
+                .languages
+                .iter()
+                .map(|tag| parse_lang(tag))
+                .collect::<io::Result<Vec<_>>>()?;
+            let mut build = Build::new(&languages);
+            let mut string_ids = std::collections::BTreeSet::new();
+            for string_table in self.string_tables.iter() {
+                let mut builder = StringTable::from_builder();
+                for string in string_table.strings.iter() {
+                    if !string_ids.insert(string.id.clone()) {
+                        return Err(invalid_input(format!(
+                            "string id {} is given more than once",
+                            string.id
+                        )));
+                    }
+                    match &string.text {
+                        TextSpec::Universal(text) => {
+                            builder = builder.string(string.id.clone(), text);
+                        }
+                        TextSpec::LangSpecific(texts) => {
+                            for (tag, text) in texts {
+                                if tag == "default" {
+                                    builder = builder.string(string.id.clone(), text);
+                                } else {
+                                    let lang = parse_lang(tag)?;
+                                    builder =
+                                        builder.lang_specific_string(lang, string.id.clone(), text);
+                                }
+                            }
+                        }
+                    }
+                }
+                build = build.resource(IdOrName::Id(Id(0)), builder.build());
+            }
+            for menu in self.menus.iter() {
+                let entries = menu_entries(&menu.items)?;
+                let menu_resource = apply_menu_entries(Menu::from_builder(), entries).build();
+                build = build.resource(menu.name.clone(), menu_resource);
+            }
+            for file in self.files.iter() {
+                let path = base_dir.join(&file.path);
+                let lang = file.lang.as_ref().map(|tag| parse_lang(tag)).transpose()?;
+                macro_rules! add_file {
+                    ($type_name:ident) => {{
+                        let resource = $type_name::from_file(&path);
+                        match lang {
+                            Some(lang) => {
+                                build.lang_specific_resource(lang, file.name.clone(), resource)
+                            }
+                            None => build.resource(file.name.clone(), resource),
+                        }
+                    }};
+                }
+                build = match file.kind {
+                    FileKind::Bitmap => add_file!(Bitmap),
+                    FileKind::Cursor => add_file!(Cursor),
+                    FileKind::Font => add_file!(Font),
+                    FileKind::Html => add_file!(HTML),
+                    FileKind::Icon => add_file!(Icon),
+                    FileKind::MessageTable => add_file!(MessageTable),
+                };
+            }
+            if let Some(version_info) = &self.version_info {
+                build = build.resource(IdOrName::Id(Id(1)), version_info.to_resource()?);
+            }
+            build.check_with(|lint| match lint {
+                Lint::DuplicateId => Severity::Deny,
+                _ => Severity::Allow,
+            })?;
+            Ok(build)
+        }
+    }
+
+    fn lang_tag(lang: Lang) -> String {
+        match crate::lang::tag(lang) {
+            Some(tag) => tag.to_owned(),
+            None => format!("0x{:04X}", lang.lang_id()),
+        }
+    }
+
+    impl StringTableSpec {
+        // A string with the same text in every extracted language becomes universal.
+        pub fn from_strings(strings: &[(Lang, Id, String)]) -> Self {
+            let mut langs = strings.iter().map(|s| s.0).collect::<Vec<_>>();
+            langs.sort();
+            langs.dedup();
+            let mut by_id: BTreeMap<Id, BTreeMap<Lang, &str>> = BTreeMap::new();
+            for (lang, id, text) in strings {
+                by_id.entry(id.clone()).or_default().insert(*lang, text);
+            }
+            let strings = by_id
+                .into_iter()
+                .map(|(id, texts)| {
+                    let first = texts.values().next().cloned().unwrap_or_default();
+                    let text = if texts.len() == langs.len() && texts.values().all(|t| *t == first)
+                    {
+                        TextSpec::Universal(first.to_owned())
+                    } else {
+                        TextSpec::LangSpecific(
+                            texts
+                                .into_iter()
+                                .map(|(lang, text)| (lang_tag(lang), text.to_owned()))
+                                .collect(),
+                        )
+                    };
+                    StringSpec {
+                        id,
+                        symbol: None,
+                        text,
+                    }
+                })
+                .collect();
+            StringTableSpec { strings }
+        }
+    }
+
+    impl BuildSpec {
+        // Migration helper: turns the string tables of an existing executable,
+        // DLL or .res file into a spec.
+        pub fn extract_string_tables(path: impl AsRef<Path>) -> io::Result<Self> {
+            let path = path.as_ref();
+            let is_res = path
+                .extension()
+                .map(|ext| ext.eq_ignore_ascii_case("res"))
+                .unwrap_or(false);
+            let entries = if is_res {
+                crate::res_file::read_res_file(path)?
+            } else {
+                crate::res_file::read_pe_file(path)?
+            };
+            let strings = crate::res_file::extract_strings(&entries)?;
+            let mut langs = strings.iter().map(|s| s.0).collect::<Vec<_>>();
+            langs.sort();
+            langs.dedup();
+            Ok(BuildSpec {
+                languages: langs.into_iter().map(lang_tag).collect(),
+                string_tables: vec![StringTableSpec::from_strings(&strings)],
+                menus: Vec::new(),
+                files: Vec::new(),
+                version_info: None,
+            })
+        }
+
+        // Symbols declared in the spec, paired with their numeric ids.
+        pub fn symbols(&self) -> Vec<(&str, Id)> {
+            fn collect_menu_items<'a>(items: &'a [MenuItemSpec], symbols: &mut Vec<(&'a str, Id)>) {
+                for item in items {
+                    if let (Some(symbol), Some(id)) = (&item.symbol, &item.id) {
+                        symbols.push((symbol, id.clone()));
+                    }
+                    if let Some(items) = &item.items {
+                        collect_menu_items(items, symbols);
+                    }
+                }
+            }
+            let mut symbols = Vec::new();
+            for string_table in self.string_tables.iter() {
+                for string in string_table.strings.iter() {
+                    if let Some(symbol) = &string.symbol {
+                        symbols.push((symbol.as_str(), string.id.clone()));
+                    }
+                }
+            }
+            for menu in self.menus.iter() {
+                if let (Some(symbol), IdOrName::Id(id)) = (&menu.symbol, &menu.name) {
+                    symbols.push((symbol.as_str(), id.clone()));
+                }
+                collect_menu_items(&menu.items, &mut symbols);
+            }
+            for file in self.files.iter() {
+                if let (Some(symbol), IdOrName::Id(id)) = (&file.symbol, &file.name) {
+                    symbols.push((symbol.as_str(), id.clone()));
+                }
+            }
+            symbols
+        }
+
+        pub fn write_c_header(&self, w: &mut dyn io::Write) -> io::Result<()> {
+            write!(
+                w,
+                "// Resource header automatically generated by RESW-RS.\n"
+            )?;
+            write!(w, "// Do not edit this file manually.\n")?;
+            write!(w, "\n")?;
+            write!(w, "#pragma once\n")?;
+            write!(w, "\n")?;
+            for (symbol, id) in self.symbols() {
+                let valid = symbol.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+                    && symbol
+                        .chars()
+                        .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+                if !valid {
+                    return Err(invalid_input(format!(
+                        "symbol `{}` is not a C identifier",
+                        symbol
+                    )));
+                }
+                write!(w, "#define {} {}\n", symbol, id)?;
+            }
+            Ok(())
+        }
+
+        // Writes the header of write_c_header to `path`, replacing the file
+        // only once the header is complete.
+        pub fn generate_c_header(&self, path: impl AsRef<Path>) -> io::Result<()> {
+            crate::codegen::write_file_atomically(path.as_ref(), |w| self.write_c_header(w))
+        }
+
+        // The typed string keys of StringTable::generate_key_module, for the
+        // strings of all string tables of the spec, named by their symbols.
+        pub fn generate_string_keys(&self, name: &str, path: impl AsRef<Path>) -> io::Result<()> {
+            let mut keys: BTreeMap<Id, Option<&str>> = BTreeMap::new();
+            for string_table in self.string_tables.iter() {
+                for string in string_table.strings.iter() {
+                    let symbol = keys.entry(string.id.clone()).or_default();
+                    if string.symbol.is_some() {
+                        *symbol = string.symbol.as_deref();
+                    }
+                }
+            }
+            let keys: Vec<(Id, Option<&str>)> = keys.into_iter().collect();
+            crate::codegen::write_file_atomically(path.as_ref(), |w| {
+                crate::codegen::write_string_keys_module(w, name, &keys)
+            })
+        }
+    }
+
+    impl Build {
+        pub fn from_spec(path: impl AsRef<Path>) -> io::Result<Build> {
+            let path = path.as_ref();
+            let spec = BuildSpec::load(path)?;
+            let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+            spec.to_build(base_dir)
+        }
+    }
+}
+
+// Translation templates listing every translatable text of a Build, taken from
+// the `source` language, with the resource id and item as context.
+#[allow(clippy::write_with_newline)]
+pub mod localization {
+    use crate::{Build, Lang};
+    use std::io::{self, Write};
+
+    #[derive(PartialEq, Debug)]
+    pub struct TranslationUnit {
+        pub context: String,
+        pub source: String,
+        pub target: Option<String>,
+    }
+
+    fn resource_texts(build: &Build, lang: Lang) -> Vec<(String, String)> {
+        let mut texts = Vec::new();
+        for (_, id_or_name, resource) in build.entries().filter(|e| e.0 == lang) {
+            for (item, text) in resource.translatable_texts(lang) {
+                let context = format!("{}/{}", id_or_name, item);
+                texts.push((context, text));
+            }
+        }
+        texts
+    }
+
+    pub fn translation_units(
+        build: &Build,
+        source: Lang,
+        target: Option<Lang>,
+    ) -> Vec<TranslationUnit> {
+        let targets = target.map(|target| resource_texts(build, target));
+        resource_texts(build, source)
+            .into_iter()
+            .map(|(context, source)| {
+                let target = targets.as_ref().and_then(|targets| {
+                    targets
+                        .iter()
+                        .find(|(target_context, _)| *target_context == context)
+                        .map(|(_, text)| text.clone())
+                });
+                TranslationUnit {
+                    context,
+                    source,
+                    target,
+                }
+            })
+            .collect()
+    }
+
+    fn write_po_str(w: &mut dyn Write, s: &str) -> io::Result<()> {
+        write!(w, "\"")?;
+        for ch in s.chars() {
+            match ch {
+                '\\' => write!(w, "\\\\")?,
+                '"' => write!(w, "\\\"")?,
+                '\n' => write!(w, "\\n")?,
+                '\r' => write!(w, "\\r")?,
+                '\t' => write!(w, "\\t")?,
+                _ => write!(w, "{}", ch)?,
+            }
+        }
+        write!(w, "\"")
+    }
+
+    pub fn write_pot(build: &Build, source: Lang, w: &mut dyn Write) -> io::Result<()> {
+        write!(w, "msgid \"\"\n")?;
+        write!(w, "msgstr \"\"\n")?;
+        write!(w, "\"Content-Type: text/plain; charset=UTF-8\\n\"\n")?;
+        for unit in translation_units(build, source, None) {
+            write!(w, "\nmsgctxt ")?;
+            write_po_str(w, &unit.context)?;
+            write!(w, "\nmsgid ")?;
+            write_po_str(w, &unit.source)?;
+            write!(w, "\nmsgstr \"\"\n")?;
+        }
+        Ok(())
+    }
+
+    fn write_xml_str(w: &mut dyn Write, s: &str) -> io::Result<()> {
+        for ch in s.chars() {
+            match ch {
+                '&' => write!(w, "&amp;")?,
+                '<' => write!(w, "&lt;")?,
+                '>' => write!(w, "&gt;")?,
+                '"' => write!(w, "&quot;")?,
+                _ => write!(w, "{}", ch)?,
+            }
+        }
+        Ok(())
+    }
+
+    fn xliff_lang(lang: Lang) -> String {
+        match crate::lang::tag(lang) {
+            Some(tag) => tag.to_owned(),
+            None => format!("x-lcid-{:04x}", lang.lang_id()),
+        }
+    }
+
+    // XLIFF 1.2; targets are filled in from `target` where the Build already has them.
+    pub fn write_xliff(
+        build: &Build,
+        source: Lang,
+        target: Lang,
+        w: &mut dyn Write,
+    ) -> io::Result<()> {
+        write!(w, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n")?;
+        write!(
+            w,
+            "<xliff version=\"1.2\" xmlns=\"urn:oasis:names:tc:xliff:document:1.2\">\n"
+        )?;
+        write!(
+            w,
+            "  <file original=\"resources\" datatype=\"winres\" source-language=\"{}\" target-language=\"{}\">\n",
+            xliff_lang(source),
+            xliff_lang(target)
+        )?;
+        write!(w, "    <body>\n")?;
+        for unit in translation_units(build, source, Some(target)) {
+            write!(w, "      <trans-unit id=\"")?;
+            write_xml_str(w, &unit.context)?;
+            write!(w, "\">\n        <source>")?;
+            write_xml_str(w, &unit.source)?;
+            write!(w, "</source>\n")?;
+            if let Some(target) = &unit.target {
+                write!(w, "        <target>")?;
+                write_xml_str(w, target)?;
+                write!(w, "</target>\n")?;
+            }
+            write!(w, "      </trans-unit>\n")?;
+        }
+        write!(w, "    </body>\n")?;
+        write!(w, "  </file>\n")?;
+        write!(w, "</xliff>\n")?;
+        Ok(())
+    }
+}
+
+// Reads the VERSIONINFO resource back at runtime, e.g. for about boxes.
+#[cfg(all(feature = "runtime", windows))]
+pub mod runtime {
+    use std::ffi::{OsStr, OsString};
+    use std::io;
+    use std::os::windows::ffi::{OsStrExt, OsStringExt};
+    use std::path::Path;
+    use winapi::shared::minwindef::{DWORD, HMODULE, LPVOID, MAX_PATH, UINT, WORD};
+    use winapi::um::libloaderapi::{
+        GetModuleFileNameW, GetModuleHandleExW, GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS,
+        GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+    };
+    use winapi::um::winver::{GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW};
+
+    #[derive(Clone, Debug, Default)]
+    pub struct ModuleVersionInfo {
+        pub fixed_file_version: Option<[WORD; 4]>,
+        pub fixed_product_version: Option<[WORD; 4]>,
+        pub fixed_file_flags: Option<DWORD>,
+        pub fixed_file_os: Option<DWORD>,
+        pub fixed_file_type: Option<DWORD>,
+        pub product_name: Option<String>,
+        pub product_version: Option<String>,
+        pub file_description: Option<String>,
+        pub file_version: Option<String>,
+        pub internal_name: Option<String>,
+        pub original_filename: Option<String>,
+        pub company_name: Option<String>,
+        pub legal_copyright: Option<String>,
+        pub legal_trademarks: Option<String>,
+        pub private_build: Option<String>,
+        pub special_build: Option<String>,
+        pub comments: Option<String>,
+    }
+
+    const VS_FFI_SIGNATURE: DWORD = 0xFEEF_04BD;
+
+    fn wide(s: &OsStr) -> Vec<u16> {
+        s.encode_wide().chain(Some(0)).collect()
+    }
+
+    fn query(block: &[u8], sub_block: &str) -> Option<(*const u8, usize)> {
+        let sub_block = wide(OsStr::new(sub_block));
+        let mut buffer: LPVOID = std::ptr::null_mut();
+        let mut len: UINT = 0;
+        let ok = unsafe {
+            VerQueryValueW(
+                block.as_ptr() as _,
+                sub_block.as_ptr(),
+                &mut buffer,
+                &mut len,
+            )
+        };
+        if ok == 0 || buffer.is_null() {
+            None
+        } else {
+            Some((buffer as *const u8, len as usize))
+        }
+    }
+
+    fn query_string(block: &[u8], translation: &str, name: &str) -> Option<String> {
+        let (ptr, len) = query(
+            block,
+            &format!("\\StringFileInfo\\{}\\{}", translation, name),
+        )?;
+        // the length is in characters and includes the terminating null
+        let units = unsafe { std::slice::from_raw_parts(ptr as *const u16, len) };
+        let units = units.split(|&unit| unit == 0).next().unwrap_or(&[]);
+        Some(String::from_utf16_lossy(units))
+    }
+
+    fn version_words(ms: DWORD, ls: DWORD) -> [WORD; 4] {
+        [
+            (ms >> 16) as WORD,
+            ms as WORD,
+            (ls >> 16) as WORD,
+            ls as WORD,
+        ]
+    }
+
+    impl ModuleVersionInfo {
+        // The version info of the module (executable or DLL) this crate is linked into.
+        pub fn current() -> io::Result<Self> {
+            let mut module: HMODULE = std::ptr::null_mut();
+            let ok = unsafe {
+                GetModuleHandleExW(
+                    GET_MODULE_HANDLE_EX_FLAG_FROM_ADDRESS
+                        | GET_MODULE_HANDLE_EX_FLAG_UNCHANGED_REFCOUNT,
+                    ModuleVersionInfo::current as *const () as _,
+                    &mut module,
+                )
+            };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut path = vec![0u16; MAX_PATH];
+            loop {
+                let len =
+                    unsafe { GetModuleFileNameW(module, path.as_mut_ptr(), path.len() as DWORD) }
+                        as usize;
+                if len == 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if len < path.len() {
+                    path.truncate(len);
+                    break;
+                }
+                let new_len = path.len() * 2;
+                path.resize(new_len, 0);
+            }
+            Self::from_file(OsString::from_wide(&path))
+        }
+
+        pub fn from_file(path: impl AsRef<Path>) -> io::Result<Self> {
+            let path = wide(path.as_ref().as_os_str());
+            let mut handle: DWORD = 0;
+            let size = unsafe { GetFileVersionInfoSizeW(path.as_ptr(), &mut handle) };
+            if size == 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut block = vec![0u8; size as usize];
+            let ok =
+                unsafe { GetFileVersionInfoW(path.as_ptr(), 0, size, block.as_mut_ptr() as _) };
+            if ok == 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut info = ModuleVersionInfo::default();
+            if let Some((ptr, len)) = query(&block, "\\") {
+                if len >= 13 * 4 {
+                    let fixed = unsafe { std::slice::from_raw_parts(ptr as *const DWORD, 13) };
+                    if fixed[0] == VS_FFI_SIGNATURE {
+                        info.fixed_file_version = Some(version_words(fixed[2], fixed[3]));
+                        info.fixed_product_version = Some(version_words(fixed[4], fixed[5]));
+                        info.fixed_file_flags = Some(fixed[7] & fixed[6]);
+                        info.fixed_file_os = Some(fixed[8]);
+                        info.fixed_file_type = Some(fixed[9]);
+                    }
+                }
+            }
+            let translation = match query(&block, "\\VarFileInfo\\Translation") {
+                Some((ptr, len)) if len >= 4 => {
+                    let pair = unsafe { std::slice::from_raw_parts(ptr as *const WORD, 2) };
+                    format!("{:04x}{:04x}", pair[0], pair[1])
+                }
+                // Unicode US English, what most resource scripts declare
+                _ => "040904b0".to_owned(),
+            };
+            let string = |name| query_string(&block, &translation, name);
+            info.product_name = string("ProductName");
+            info.product_version = string("ProductVersion");
+            info.file_description = string("FileDescription");
+            info.file_version = string("FileVersion");
+            info.internal_name = string("InternalName");
+            info.original_filename = string("OriginalFilename");
+            info.company_name = string("CompanyName");
+            info.legal_copyright = string("LegalCopyright");
+            info.legal_trademarks = string("LegalTrademarks");
+            info.private_build = string("PrivateBuild");
+            info.special_build = string("SpecialBuild");
+            info.comments = string("Comments");
+            Ok(info)
+        }
+    }
+}
+
+#[cfg(feature = "testing")]
+pub mod testing {
+    use crate::res_file::{self, ResEntry};
+    use crate::{Build, IdOrName, Lang};
+    use std::collections::BTreeMap;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    type EntryKey = (IdOrName, IdOrName, Lang);
+
+    fn describe(key: &EntryKey) -> String {
+        format!("type {:?}, name {:?}, language {:?}", key.0, key.1, key.2)
+    }
+
+    fn compile_source(work_dir: &Path, stem: &str, source: &str) -> io::Result<Vec<ResEntry>> {
+        let rc_path = work_dir.join(stem).with_extension("rc");
+        let res_path = work_dir.join(stem).with_extension("res");
+        std::fs::write(&rc_path, source)?;
+        Build::compile_rc_file_to_res(&rc_path, &res_path)?;
+        res_file::read_res_file(&res_path)
+    }
+
+    fn index(entries: Vec<ResEntry>) -> BTreeMap<EntryKey, ResEntry> {
+        entries
+            .into_iter()
+            .map(|entry| {
+                (
+                    (entry.type_id.clone(), entry.name.clone(), entry.lang),
+                    entry,
+                )
+            })
+            .collect()
+    }
+
+    // Compiles `build` and the hand-written `reference_rc` with the platform
+    // resource compiler and lists every difference between the two results.
+    // Entry order is ignored.
+    pub fn compare_with_reference(
+        build: Build,
+        reference_rc: &str,
+        work_dir: &Path,
+    ) -> io::Result<Vec<String>> {
+        std::fs::create_dir_all(work_dir)?;
+        let generated = compile_build(build, work_dir)?;
+        let reference = compile_source(work_dir, "reference", reference_rc)?;
+        Ok(compare_entries(reference, generated))
+    }
+
+    // Like compare_with_reference, against an already compiled .res file,
+    // e.g. one checked in from before a change to the generated scripts.
+    pub fn compare_with_res(
+        build: Build,
+        reference_res: &Path,
+        work_dir: &Path,
+    ) -> io::Result<Vec<String>> {
+        std::fs::create_dir_all(work_dir)?;
+        let generated = compile_build(build, work_dir)?;
+        let reference = res_file::read_res_file(reference_res)?;
+        Ok(compare_entries(reference, generated))
+    }
+
+    // Compiles two resource scripts and lists where their results differ.
+    pub fn compare_scripts(
+        expected_rc: &str,
+        actual_rc: &str,
+        work_dir: &Path,
+    ) -> io::Result<Vec<String>> {
+        std::fs::create_dir_all(work_dir)?;
+        let expected = compile_source(work_dir, "expected", expected_rc)?;
+        let actual = compile_source(work_dir, "actual", actual_rc)?;
+        Ok(compare_entries(expected, actual))
+    }
+
+    // Lists where two compiled .res files differ, in any order of entries.
+    pub fn compare_res_files(expected: &Path, actual: &Path) -> io::Result<Vec<String>> {
+        Ok(compare_entries(
+            res_file::read_res_file(expected)?,
+            res_file::read_res_file(actual)?,
+        ))
+    }
+
+    fn compile_build(build: Build, work_dir: &Path) -> io::Result<Vec<ResEntry>> {
+        let generated_rc_path = work_dir.join("generated.rc");
+        build.generate_rc_file(&generated_rc_path)?;
+        let generated_source = std::fs::read_to_string(&generated_rc_path)?;
+        compile_source(work_dir, "generated", &generated_source)
+    }
+
+    fn compare_entries(reference: Vec<ResEntry>, generated: Vec<ResEntry>) -> Vec<String> {
+        let reference = index(reference);
+        let generated = index(generated);
+        let mut differences = Vec::new();
+        for (key, expected) in reference.iter() {
+            match generated.get(key) {
+                None => differences.push(format!("missing from generated: {}", describe(key))),
+                Some(actual) => {
+                    if actual.data != expected.data {
+                        let offset = actual
+                            .data
+                            .iter()
+                            .zip(expected.data.iter())
+                            .take_while(|(a, e)| a == e)
+                            .count();
+                        differences.push(format!(
+                            "data differs for {} from byte {}: expected {} bytes, generated {} bytes",
+                            describe(key),
+                            offset,
+                            expected.data.len(),
+                            actual.data.len()
+                        ));
+                    }
+                    if (actual.characteristics, actual.version)
+                        != (expected.characteristics, expected.version)
+                    {
+                        differences.push(format!(
+                            "characteristics/version differ for {}",
+                            describe(key)
+                        ));
+                    }
+                }
+            }
+        }
+        for key in generated.keys() {
+            if !reference.contains_key(key) {
+                differences.push(format!("unexpected in generated: {}", describe(key)));
+            }
+        }
+        differences
+    }
+
+    fn work_dir() -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "resw-roundtrip-{}-{:?}",
+            std::process::id(),
+            std::thread::current().id()
+        ))
+    }
+
+    pub fn assert_equivalent_to_rc(build: Build, reference_rc: &str) {
+        let work_dir = work_dir();
+        let differences = compare_with_reference(build, reference_rc, &work_dir);
+        assert_no_differences(differences, &work_dir);
+    }
+
+    pub fn assert_equivalent_to_res(build: Build, reference_res: &Path) {
+        let work_dir = work_dir();
+        let differences = compare_with_res(build, reference_res, &work_dir);
+        assert_no_differences(differences, &work_dir);
+    }
+
+    fn assert_no_differences(differences: io::Result<Vec<String>>, work_dir: &Path) {
+        let differences =
+            differences.unwrap_or_else(|e| panic!("failed to compile resource scripts: {}", e));
+        if !differences.is_empty() {
+            panic!(
+                "generated resources differ from the reference (scripts kept in {}):\n{}",
+                work_dir.display(),
+                differences.join("\n")
+            );
+        }
+        let _ = std::fs::remove_dir_all(work_dir);
+    }
+
+    // How `path` appears inside a narrow string literal of the script.
+    fn escaped_in_script(path: &Path) -> Option<String> {
+        let mut escaped = Vec::new();
+        crate::codegen::write_path(&mut escaped, path).ok()?;
+        let escaped = String::from_utf8(escaped).ok()?;
+        Some(escaped.trim_start_matches('L').trim_matches('"').to_owned())
+    }
+
+    // Drops the header comment and replaces the base directory (the package
+    // directory under cargo), which resolved file paths start with, by `$CWD`,
+    // so the script reads the same on every machine.
+    pub fn normalize_rc(script: &str) -> String {
+        let script = script.replace("\r\n", "\n");
+        let mut lines: Vec<&str> = script.lines().collect();
+        let header_len = lines
+            .iter()
+            .take_while(|line| line.starts_with("//"))
+            .count();
+        lines.drain(..header_len);
+        let mut normalized = lines.join("\n");
+        normalized.push('\n');
+        if let Ok(base_dir) = crate::codegen::base_dir() {
+            let mut prefixes = vec![base_dir.clone()];
+            if let Ok(canonical) = base_dir.canonicalize() {
+                prefixes.push(crate::codegen::strip_verbatim_prefix(canonical));
+            }
+            for prefix in prefixes {
+                if let Some(escaped) = escaped_in_script(&prefix) {
+                    normalized = normalized.replace(&escaped, "$CWD");
+                }
+            }
+        }
+        normalized
+    }
+
+    // The lines of `expected` and `actual` that differ, as `-` and `+` lines
+    // with their line numbers, from a longest common subsequence of the two.
+    fn diff_lines(expected: &str, actual: &str) -> String {
+        let expected: Vec<&str> = expected.lines().collect();
+        let actual: Vec<&str> = actual.lines().collect();
+        let mut common = vec![vec![0usize; actual.len() + 1]; expected.len() + 1];
+        for i in (0..expected.len()).rev() {
+            for j in (0..actual.len()).rev() {
+                common[i][j] = if expected[i] == actual[j] {
+                    common[i + 1][j + 1] + 1
+                } else {
+                    common[i + 1][j].max(common[i][j + 1])
+                };
+            }
+        }
+        let mut diff = String::new();
+        let (mut i, mut j) = (0, 0);
+        while i < expected.len() || j < actual.len() {
+            if i < expected.len() && j < actual.len() && expected[i] == actual[j] {
+                i += 1;
+                j += 1;
+            } else if j == actual.len()
+                || (i < expected.len() && common[i + 1][j] >= common[i][j + 1])
+            {
+                diff.push_str(&format!("{:>5} - {}\n", i + 1, expected[i]));
+                i += 1;
+            } else {
+                diff.push_str(&format!("{:>5} + {}\n", j + 1, actual[j]));
+                j += 1;
+            }
+        }
+        diff
+    }
+
+    // Compares the normalized script of `build` with the snapshot at `path`.
+    // A missing or differing snapshot fails the assertion; set
+    // RESW_UPDATE_SNAPSHOTS=1 to write it instead.
+    pub fn assert_rc_snapshot(build: Build, path: impl AsRef<Path>) {
+        let path = path.as_ref();
+        let mut script = Vec::new();
+        build
+            .write_rc(&mut script)
+            .unwrap_or_else(|e| panic!("failed to generate resource script: {}", e));
+        let actual = normalize_rc(&String::from_utf8_lossy(&script));
+        let update = std::env::var_os("RESW_UPDATE_SNAPSHOTS").is_some();
+        let expected = match std::fs::read_to_string(path) {
+            Ok(expected) => expected.replace("\r\n", "\n"),
+            Err(e) if e.kind() == io::ErrorKind::NotFound && update => {
+                write_snapshot(path, &actual);
+                return;
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => panic!(
+                "snapshot {} is missing (set RESW_UPDATE_SNAPSHOTS=1 to write it)",
+                path.display()
+            ),
+            Err(e) => panic!("failed to read snapshot {}: {}", path.display(), e),
+        };
+        if expected == actual {
+            return;
+        }
+        if update {
+            write_snapshot(path, &actual);
+            return;
+        }
+        panic!(
+            "resource script differs from snapshot {} (- snapshot, + generated; \
+             set RESW_UPDATE_SNAPSHOTS=1 to accept):\n{}",
+            path.display(),
+            diff_lines(&expected, &actual)
+        );
+    }
+
+    fn write_snapshot(path: &Path, contents: &str) {
+        if let Some(dir) = path.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        std::fs::write(path, contents)
+            .unwrap_or_else(|e| panic!("failed to write snapshot {}: {}", path.display(), e));
+    }
+}
+
+impl Build {
+    pub fn precompiled_resources(
+        mut self,
+        entries: impl IntoIterator<Item = res_file::ResEntry>,
+    ) -> Self {
+        for entry in entries {
+            let lang = entry.lang;
+            let name = entry.name.clone();
+            self =
+                self.lang_specific_resource(lang, name, resource::Precompiled::from_entry(entry));
+        }
+        self
+    }
+
+    pub fn generate_rc_file(self, path: &std::path::Path) -> Result<(), io::Error> {
+        self.check()?;
+        self.write_script(path, false)
+    }
+
+    // Writes a fragment (usually named `.rc2`) to be `#include`d from a resource
+    // script maintained elsewhere, e.g. in Visual Studio. It has no header of its
+    // own and expects the including script to be in effect with code page 65001.
+    pub fn generate_rc_include_file(self, path: &std::path::Path) -> Result<(), io::Error> {
+        self.check()?;
+        self.write_script(path, true)
+    }
+
+    // Writes the resource script to `w`, checked the same way as by
+    // `generate_rc_file`.
+    pub fn write_rc(self, w: &mut dyn io::Write) -> Result<(), io::Error> {
+        self.check()?;
+        let (script, files) = self.checked_script(false, &self.entries().collect::<Vec<_>>())?;
+        codegen::write_out_dir_files(&files)?;
+        w.write_all(&script)
+    }
+
+    fn write_script(self, path: &std::path::Path, fragment: bool) -> Result<(), io::Error> {
+        self.write_script_entries(path, fragment, &self.entries().collect::<Vec<_>>())
+    }
+
+    fn write_script_entries(
+        &self,
+        path: &std::path::Path,
+        fragment: bool,
+        entries: &[(Lang, &IdOrName, &dyn Resource)],
+    ) -> Result<(), io::Error> {
+        log!(info, "writing resource script {}", path.display());
+        let (script, files) = self.checked_script(fragment, entries)?;
+        codegen::write_out_dir_files(&files)?;
+        codegen::write_file_atomically(path, |w| w.write_all(&script))
+    }
+
+    // The script text, re-read by the syntax checker before it is written out,
+    // and the generated files in OUT_DIR it refers to, to be written with it.
+    fn checked_script(
+        &self,
+        fragment: bool,
+        entries: &[(Lang, &IdOrName, &dyn Resource)],
+    ) -> Result<(Vec<u8>, codegen::OutDirFiles), io::Error> {
+        let mut script = Vec::new();
+        let (result, files) =
+            codegen::with_out_dir_files(|| self.write_script_to(&mut script, fragment, entries));
+        result?;
+        rc_syntax::check(&script)?;
+        Ok((script, files))
+    }
+
+    fn write_script_to(
+        &self,
+        w: &mut dyn io::Write,
+        fragment: bool,
+        entries: &[(Lang, &IdOrName, &dyn Resource)],
+    ) -> Result<(), io::Error> {
+        let w = &mut codegen::LineEndingWriter::new(w, self.line_ending);
+        let mut strings = HashMap::new();
+        for (lang, _, resource) in self.entries() {
+            for (id, text) in resource.defined_strings(lang) {
+                strings.insert((lang, id), text);
+            }
+        }
+        codegen::with_diagnostics(self.diagnostics.clone(), || {
+            codegen::with_table_strings(strings, || {
+                codegen::with_build_languages(self.ordered_languages(), || {
+                    codegen::with_path_resolution(|| {
+                        self.write_script_contents(w, fragment, entries)
+                    })
+                })
+            })
+        })?;
+        w.finish()
+    }
+
+    fn write_script_contents(
+        &self,
+        w: &mut dyn io::Write,
+        fragment: bool,
+        entries: &[(Lang, &IdOrName, &dyn Resource)],
+    ) -> Result<(), io::Error> {
+        codegen::with_dialect(self.dialect, || {
+            codegen::with_wide_text(self.wide_text, || {
+                if fragment {
+                    codegen::write_fragment_header(w)?;
+                } else {
+                    codegen::write_header(w)?;
+                }
+                for (header, system) in self.headers.iter() {
+                    codegen::write_header_include(w, header, *system)?;
+                }
+                let designer = self.designer_compatible && !fragment;
+                if designer {
+                    codegen::write_text_includes(w, &self.headers)?;
+                }
+
+                let mut dialogs: BTreeMap<Lang, Vec<&IdOrName>> = BTreeMap::new();
+                for &(lang, id_or_name, resource) in entries {
+                    if designer && resource.kind() == ResourceKind::Dialog {
+                        dialogs.entry(lang).or_default().push(id_or_name);
+                    }
+                    let code_page = self
+                        .code_pages
+                        .get(&lang)
+                        .cloned()
+                        .unwrap_or(codegen::DEFAULT_CODE_PAGE);
+                    if code_page != codegen::current_code_page() {
+                        codegen::write_code_page_pragma(w, code_page)?;
+                    }
+                    log!(
+                        debug,
+                        "writing {} {} for {}",
+                        describe_type(resource),
+                        id_or_name,
+                        describe_langs(&[lang])
+                    );
+                    resource
+                        .write_script_segment(w, lang, id_or_name.clone())
+                        .map_err(|e| {
+                            if e.kind() != io::ErrorKind::InvalidInput {
+                                return e;
+                            }
+                            io::Error::new(
+                                e.kind(),
+                                format!(
+                                    "{} {} ({}): {}",
+                                    describe_type(resource),
+                                    id_or_name,
+                                    describe_langs(&[lang]),
+                                    e
+                                ),
+                            )
+                        })?;
+                }
+                for (lang, ids) in dialogs {
+                    codegen::write_design_info(w, lang, &ids)?;
+                }
+                for (path, inline) in self.rc_fragments.iter() {
+                    if codegen::current_code_page() != codegen::DEFAULT_CODE_PAGE {
+                        codegen::write_code_page_pragma(w, codegen::DEFAULT_CODE_PAGE)?;
+                    }
+                    codegen::write_rc_fragment(w, path, *inline)?;
+                }
+                // hand the including script back the code page it expects
+                if fragment && codegen::current_code_page() != codegen::DEFAULT_CODE_PAGE {
+                    codegen::write_code_page_pragma(w, codegen::DEFAULT_CODE_PAGE)?;
+                }
+                Ok(())
+            })
+        })
+    }
+
+    // Splits the build for the MUI deployment model: `neutral.rc` holds the
+    // universal resources without translatable text, once, as LANG_NEUTRAL, for
+    // the main binary; `<language tag>.rc` holds one language's localizable and
+    // language-specific resources for its satellite (.mui) DLL. VERSIONINFO goes
+    // into every script, as MUI files are required to carry one.
+    pub fn generate_mui_rc_files(self, dir: &std::path::Path) -> Result<MuiScripts, io::Error> {
+        self.check()?;
+        std::fs::create_dir_all(dir)?;
+        let neutral_lang = Lang(0, 0);
+        let mut neutral = Vec::new();
+        let mut seen = Vec::new();
+        let mut localized: BTreeMap<Lang, Vec<(Lang, &IdOrName, &dyn Resource)>> = BTreeMap::new();
+        for (lang, id_or_name, resource) in self.entries() {
+            let is_version = resource.kind() == ResourceKind::VersionInfo;
+            let applies_to = self.applies_to(resource);
+            let has_text = !resource.translatable_texts(lang).is_empty();
+            if is_version || has_text || applies_to.is_some() {
+                localized
+                    .entry(lang)
+                    .or_default()
+                    .push((lang, id_or_name, resource));
+            }
+            if is_version || (!has_text && applies_to.is_none()) {
+                let key = (
+                    applies_to,
+                    resource.condition(),
+                    resource.type_keyword(),
+                    resource.user_defined_type(),
+                    id_or_name,
+                );
+                if !seen.contains(&key) {
+                    seen.push(key);
+                    neutral.push((neutral_lang, id_or_name, resource));
+                }
+            }
+        }
+        let neutral_path = dir.join("neutral.rc");
+        let mut content_hashes = BTreeMap::new();
+        let hash = self.update_script(&neutral_path, &neutral)?;
+        content_hashes.insert(neutral_path.clone(), hash);
+        let mut languages = Vec::new();
+        for (lang, entries) in localized {
+            let tag = match crate::lang::tag(lang) {
+                Some(tag) => tag.to_owned(),
+                None => format!("{:04x}", lang.lang_id()),
+            };
+            let path = dir.join(tag).with_extension("rc");
+            let hash = self.update_script(&path, &entries)?;
+            content_hashes.insert(path.clone(), hash);
+            languages.push((lang, path));
+        }
+        Ok(MuiScripts {
+            neutral: neutral_path,
+            languages,
+            content_hashes,
+        })
+    }
+
+    // Rewrites the script at `path` only if its text changed, leaving untouched
+    // sections alone, and returns a hash of the text and every file it refers
+    // to, for deciding whether the compiled section is still current.
+    fn update_script(
+        &self,
+        path: &std::path::Path,
+        entries: &[(Lang, &IdOrName, &dyn Resource)],
+    ) -> Result<u64, io::Error> {
+        use std::hash::{Hash, Hasher};
+        let (script, files) = self.checked_script(false, entries)?;
+        codegen::write_out_dir_files(&files)?;
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        script.hash(&mut hasher);
+        for (_, _, resource) in entries {
+            for file in resource.referenced_files() {
+                std::fs::read(file).ok().hash(&mut hasher);
+            }
+        }
+        if std::fs::read(path).ok().as_ref() != Some(&script) {
+            codegen::write_file_atomically(path, |w| w.write_all(&script))?;
+        }
+        Ok(hasher.finish())
+    }
+
+    // Writes a Rust module with typed ids and `load_*` wrappers for the icons,
+    // cursors, bitmaps, menus and accelerator tables registered so far, meant to
+    // be `include!`d by the application. The wrappers load from the module the
+    // code is linked into and return null on failure, like the underlying calls.
+    // Conditional resources get no loaders, their condition may be false.
+    pub fn generate_loader_module(&self, path: &std::path::Path) -> Result<(), io::Error> {
+        self.write_loader_module(path, false)
+    }
+
+    // Like generate_loader_module, adding `load_data_json` to deserialize
+    // embedded data; the including crate needs serde and serde_json.
+    pub fn generate_loader_module_with_serde(
+        &self,
+        path: &std::path::Path,
+    ) -> Result<(), io::Error> {
+        self.write_loader_module(path, true)
+    }
+
+    fn write_loader_module(&self, path: &std::path::Path, serde: bool) -> Result<(), io::Error> {
+        let mut entries = Vec::new();
+        let mut data_entries = Vec::new();
+        for (_, id_or_name, resource) in self.resources.iter() {
+            // a conditional resource may not be compiled in at all
+            if resource.condition().is_some() {
+                continue;
+            }
+            if let Some(type_id) = resource.user_defined_type() {
+                data_entries.push((type_id, id_or_name.clone()));
+            } else if resource.kind() == ResourceKind::RcData {
+                data_entries.push((IdOrName::Id(Id(codegen::RT_RCDATA)), id_or_name.clone()));
+            } else if let Some(keyword) = resource.type_keyword() {
+                entries.push((keyword, id_or_name.clone()));
+            }
+        }
+        entries.sort();
+        entries.dedup();
+        data_entries.sort();
+        data_entries.dedup();
+        codegen::write_file_with_line_ending(path, self.line_ending, |w| {
+            codegen::write_loader_module(w, &entries, &data_entries, serde)
+        })
+    }
+
+    // Writes a Rust file with `verify_embedded_resources()`, which looks up every
+    // registered resource with FindResourceExW in each language it is generated
+    // for, and a `#[cfg(test)]` test calling it. `include!` it from a crate the
+    // compiled resources are linked into to catch linking mistakes in CI.
+    // String tables are left out, their blocks don't map to the registered id,
+    // and so are Conditional resources, which aren't in every configuration.
+    pub fn generate_resource_checks(&self, path: &std::path::Path) -> Result<(), io::Error> {
+        let mut entries = Vec::new();
+        // the segments are only written to see whether they are empty, any
+        // warnings are reported when the script itself is written
+        codegen::with_diagnostics(Diagnostics::Ignore, || {
+            codegen::with_dialect(self.dialect, || -> Result<(), io::Error> {
+                for (lang, id_or_name, resource) in self.entries() {
+                    if resource.condition().is_some() {
+                        continue;
+                    }
+                    let type_id = match resource
+                        .type_keyword()
+                        .and_then(codegen::predefined_type_id)
+                    {
+                        Some(type_id) => type_id,
+                        None => continue,
+                    };
+                    let mut segment = Vec::new();
+                    resource.write_script_segment(&mut segment, lang, id_or_name.clone())?;
+                    if segment.is_empty() {
+                        continue;
+                    }
+                    let id_or_name = if type_id == codegen::RT_VERSION {
+                        IdOrName::Id(Id(1))
+                    } else {
+                        id_or_name.clone()
+                    };
+                    entries.push((lang, type_id, id_or_name));
+                }
+                Ok(())
+            })
+        })?;
+        codegen::write_file_with_line_ending(path, self.line_ending, |w| {
+            codegen::write_resource_checks(w, &entries)
+        })
+    }
+
+    // Every external file referenced by the registered resources, resolved the
+    // same way the generated script refers to them, sorted and deduplicated.
+    pub fn referenced_files(&self) -> Result<Vec<std::path::PathBuf>, io::Error> {
+        let mut resolver = codegen::PathResolver::new()?;
+        let mut files = std::collections::BTreeSet::new();
+        for (_, _, resource) in self.resources.iter() {
+            for path in resource.referenced_files() {
+                files.insert(resolver.resolve(path));
+            }
+        }
+        for (path, _) in self.rc_fragments.iter() {
+            files.insert(resolver.resolve(path));
+        }
+        Ok(files.into_iter().collect())
+    }
+
+    // Generates the script only, plus a makefile-style depfile listing every
+    // referenced asset, for build systems that run the resource compiler themselves.
+    pub fn generate_rc_file_with_depfile(
+        self,
+        path: &std::path::Path,
+        depfile_path: &std::path::Path,
+    ) -> Result<(), io::Error> {
+        let dependencies = self.referenced_files()?;
+        let line_ending = self.line_ending;
+        self.generate_rc_file(path)?;
+        codegen::write_file_with_line_ending(depfile_path, line_ending, |w| {
+            codegen::write_depfile(w, path, &dependencies)
+        })
+    }
+
+    // Runs the resource compiler directly, outside of cargo's linking machinery,
+    // with the compiler ResourceCompiler::detect finds.
+    pub fn compile_rc_file_to_res(
+        path: &std::path::Path,
+        res_path: &std::path::Path,
+    ) -> Result<(), io::Error> {
+        Self::compile_rc_file_to_res_with(&ResourceCompiler::detect()?, path, res_path)
+    }
+
+    pub fn compile_rc_file_to_res_with(
+        compiler: &ResourceCompiler,
+        path: &std::path::Path,
+        res_path: &std::path::Path,
+    ) -> Result<(), io::Error> {
+        Self::run_resource_compiler(compiler, path, res_path, &CompileOptions::default())
+    }
+
+    fn run_resource_compiler(
+        compiler: &ResourceCompiler,
+        path: &std::path::Path,
+        res_path: &std::path::Path,
+        options: &CompileOptions,
+    ) -> Result<(), io::Error> {
+        use std::process::Command;
+        let mut command = Command::new(compiler.path());
+        let (define, include_dir) = match compiler {
+            ResourceCompiler::Windres(_) => ("-D", "-I"),
+            ResourceCompiler::Rc(_) | ResourceCompiler::LlvmRc(_) => ("/d", "/i"),
+        };
+        for macro_definition in options.macros.iter() {
+            command.arg(define).arg(macro_definition);
+        }
+        for dir in options.include_dirs.iter() {
+            command.arg(include_dir).arg(dir);
+        }
+        if options.has_preprocessor_options() {
+            if !matches!(compiler, ResourceCompiler::Windres(_)) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} doesn't take preprocessor options, only windres does",
+                        compiler.path().display()
+                    ),
+                ));
+            }
+            if let Some(preprocessor) = &options.preprocessor {
+                let mut arg = std::ffi::OsString::from("--preprocessor=");
+                arg.push(preprocessor);
+                command.arg(arg);
+            }
+            for preprocessor_arg in options.preprocessor_args.iter() {
+                let mut arg = std::ffi::OsString::from("--preprocessor-arg=");
+                arg.push(preprocessor_arg);
+                command.arg(arg);
+            }
+        }
+        match compiler {
+            ResourceCompiler::Windres(_) => {
+                // windres writes a .res file when asked for one, otherwise a
+                // COFF object for the linker, which has to be for the machine
+                // being linked; .res files are machine independent.
+                let res_output = res_path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("res"));
+                let bfd_target = match std::env::var("CARGO_CFG_TARGET_ARCH").as_deref() {
+                    _ if res_output => None,
+                    Ok("x86_64") => Some("pe-x86-64"),
+                    Ok("x86") => Some("pe-i386"),
+                    Ok("aarch64") => Some("pe-aarch64-little"),
+                    _ => None,
+                };
+                if let Some(bfd_target) = bfd_target {
+                    command.arg("-F").arg(bfd_target);
+                }
+                command
+                    .arg("-O")
+                    .arg(if res_output { "res" } else { "coff" })
+                    .arg("-i")
+                    .arg(path)
+                    .arg("-o")
+                    .arg(res_path);
+            }
+            ResourceCompiler::Rc(_) | ResourceCompiler::LlvmRc(_) => {
+                command.arg("/nologo").arg("/fo").arg(res_path).arg(path);
+            }
+        }
+        log!(info, "running {:?}", command);
+        let output = command.output()?;
+        let mut messages = String::from_utf8_lossy(&output.stdout).into_owned();
+        messages += &String::from_utf8_lossy(&output.stderr);
+        if output.status.success() {
+            // warnings, which go where the build's other warnings go
+            for message in messages.lines().filter(|line| !line.trim().is_empty()) {
+                codegen::report(message.to_owned())?;
+            }
+            return Ok(());
+        }
+        // point each message about the script at the resource it is about
+        let source = std::fs::read_to_string(path).unwrap_or_default();
+        let mut error = format!(
+            "{} exited with {}",
+            compiler.path().display(),
+            output.status
+        );
+        for message in messages.lines() {
+            error += "\n";
+            error += message;
+            let statement = Self::message_line(message, path)
+                .and_then(|line| Self::describe_script_line(&source, line));
+            if let Some(statement) = statement {
+                error += &format!(" [in {}]", statement);
+            }
+        }
+        Err(io::Error::other(error))
+    }
+
+    // The line of `script` a compiler message is about, from rc.exe's
+    // `resource.rc(12) : error ...` or windres' and clang's `resource.rc:12: ...`.
+    fn message_line(message: &str, script: &std::path::Path) -> Option<usize> {
+        let file_name = script.file_name()?.to_str()?;
+        message.match_indices(file_name).find_map(|(pos, _)| {
+            let rest = &message[pos + file_name.len()..];
+            let digits = rest.strip_prefix('(').or_else(|| rest.strip_prefix(':'))?;
+            let end = digits
+                .find(|ch: char| !ch.is_ascii_digit())
+                .unwrap_or(digits.len());
+            digits[..end].parse().ok()
+        })
+    }
+
+    // The resource statement of a generated script that line `line` belongs
+    // to, e.g. "MENUEX 1 (en-US)". Every statement is written right after a
+    // LANGUAGE line; copied in scripts start with a `// path` comment and
+    // aren't described.
+    fn describe_script_line(source: &str, line: usize) -> Option<String> {
+        let mut lines = source.lines();
+        if !lines
+            .next()?
+            .ends_with("automatically generated by RESW-RS.")
+        {
+            return None;
+        }
+        let mut lang = None;
+        let mut statement = None;
+        for text in lines.take(line.checked_sub(1)?) {
+            if let Some(rest) = text.strip_prefix("LANGUAGE ") {
+                let mut ids = rest
+                    .split(',')
+                    .map(|id| WORD::from_str_radix(id.trim().trim_start_matches("0x"), 16).ok());
+                lang = match (ids.next().flatten(), ids.next().flatten()) {
+                    (Some(primary), Some(sub)) => Some(Lang(primary, sub)),
+                    _ => None,
+                };
+                statement = None;
+            } else if text.starts_with("// ") {
+                lang = None;
+            } else if lang.is_some() && statement.is_none() && !text.starts_with('#') {
+                let mut words = text.split_whitespace();
+                statement = match (words.next(), words.next()) {
+                    (Some(id), Some(keyword)) if id != "STRINGTABLE" => {
+                        Some(format!("{} {}", keyword, id))
+                    }
+                    (Some(keyword), _) => Some(keyword.to_owned()),
+                    (None, _) => None,
+                };
+            }
+        }
+        Some(format!("{} ({})", statement?, describe_langs(&[lang?])))
+    }
+
+    // Compiles and links the script through embed-resource, or with the
+    // detected compiler when the options ask for it.
+    pub fn compile_rc_file(path: &std::path::Path) -> Result<(), io::Error> {
+        Self::compile_rc_file_with(path, &CompileOptions::default())
+    }
+
+    pub fn compile_rc_file_with(
+        path: &std::path::Path,
+        options: &CompileOptions,
+    ) -> Result<(), io::Error> {
+        Self::compile_rc_file_to_artifact(path, options).map(|_| ())
+    }
+
+    // Returns the compiled file handed to the linker, or None when nothing was
+    // compiled because the target isn't Windows or no compiler was found.
+    fn compile_rc_file_to_artifact(
+        path: &std::path::Path,
+        options: &CompileOptions,
+    ) -> Result<Option<std::path::PathBuf>, io::Error> {
+        if std::env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os != "windows") {
+            log!(
+                info,
+                "not compiling {}, the target isn't Windows",
+                path.display()
+            );
+            return Ok(None);
+        }
+        if options.link_args {
+            return Self::compile_rc_file_for_link_args(path, options);
+        }
+        if !options.detected_compiler {
+            return Self::compile_rc_file_with_embed_resource(path, options);
+        }
+        match ResourceCompiler::detect() {
+            Ok(compiler) => Self::compile_rc_file_to_library(&compiler, path, options).map(Some),
+            Err(e) => {
+                // embed-resource has some guesses of its own left
+                log!(warn, "{}", e);
+                Self::compile_rc_file_with_embed_resource(path, options)
+            }
+        }
+    }
+
+    // Compiles into the file embed-resource would have made and links it the
+    // same way: a .res named `<stem>.lib` for MSVC targets, a COFF object named
+    // `lib<stem>.a` for GNU ones. Linkers go by the contents, not the name.
+    fn compile_rc_file_to_library(
+        compiler: &ResourceCompiler,
+        path: &std::path::Path,
+        options: &CompileOptions,
+    ) -> Result<std::path::PathBuf, io::Error> {
+        let out_dir = codegen::out_dir()?;
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let file_name = if std::env::var("CARGO_CFG_TARGET_ENV").is_ok_and(|env| env == "msvc") {
+            format!("{}.lib", stem)
+        } else {
+            format!("lib{}.a", stem)
+        };
+        let library = out_dir.join(file_name);
+        Self::run_resource_compiler(compiler, path, &library, options)?;
+        Self::link_compiled(&library, &options.link_target, Some(&stem))?;
+        Ok(library)
+    }
+
+    fn compile_rc_file_with_embed_resource(
+        path: &std::path::Path,
+        options: &CompileOptions,
+    ) -> Result<Option<std::path::PathBuf>, io::Error> {
+        use embed_resource::{CompilationResult, ParamsMacrosAndIncludeDirs};
+        if options.has_preprocessor_options() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "preprocessor options need a detected resource compiler, \
+                 see CompileOptions::use_detected_compiler",
+            ));
+        }
+        log!(
+            info,
+            "compiling {} with embed-resource, macros {:?}, include dirs {:?}",
+            path.display(),
+            options.macros,
+            options.include_dirs
+        );
+        if path.to_str().is_none() {
+            // embed-resource would panic on it
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{}: script paths have to be valid UTF-8", path.display()),
+            ));
+        }
+        let params = ParamsMacrosAndIncludeDirs(&options.macros, &options.include_dirs);
+        let result = match &options.link_target {
+            LinkTarget::Default => embed_resource::compile(path, params),
+            LinkTarget::Bins(bins) => embed_resource::compile_for(path, bins, params),
+            LinkTarget::Tests => embed_resource::compile_for_tests(path, params),
+            LinkTarget::Benchmarks => embed_resource::compile_for_benchmarks(path, params),
+            LinkTarget::Examples => embed_resource::compile_for_examples(path, params),
+            LinkTarget::Cdylib => embed_resource::compile_for_cdylib(path, params),
+            LinkTarget::Everything => embed_resource::compile_for_everything(path, params),
+        };
+        if let CompilationResult::Failed(failure) = &result {
+            return Err(io::Error::other(failure.to_string()));
+        }
+        let compiled = matches!(result, CompilationResult::Ok);
+        let result = if options.manifest_required {
+            result.manifest_required()
+        } else {
+            result.manifest_optional()
+        };
+        result.map_err(|e| io::Error::other(e.to_string()))?;
+        if !compiled {
+            return Ok(None);
+        }
+        // embed-resource names its output after the script, as a static
+        // library in OUT_DIR whose naming depends on the host toolchain
+        let out_dir = codegen::out_dir()?;
+        let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+        let file_name = if cfg!(all(windows, not(target_env = "msvc"))) {
+            format!("lib{}.a", stem)
+        } else {
+            format!("{}.lib", stem)
+        };
+        Ok(Some(out_dir.join(file_name)))
+    }
+
+    // Compiles into OUT_DIR and passes the result to the linker of the
+    // targeted artifacts as it is: a .res file, or a COFF object from windres.
+    fn compile_rc_file_for_link_args(
+        path: &std::path::Path,
+        options: &CompileOptions,
+    ) -> Result<Option<std::path::PathBuf>, io::Error> {
+        let compiler = match ResourceCompiler::detect() {
+            Ok(compiler) => compiler,
+            Err(e) if !options.manifest_required => {
+                println!("cargo:warning={}", e);
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let out_dir = codegen::out_dir()?;
+        let extension = match compiler {
+            ResourceCompiler::Windres(_) => "o",
+            ResourceCompiler::Rc(_) | ResourceCompiler::LlvmRc(_) => "res",
+        };
+        let stem = path.file_stem().unwrap_or_default();
+        let out_path = out_dir.join(stem).with_extension(extension);
+        Self::run_resource_compiler(&compiler, path, &out_path, options)?;
+        Self::link_compiled(&out_path, &options.link_target, None)?;
+        Ok(Some(out_path))
+    }
+
+    // Hands `compiled` to the linker of the targeted artifacts. A crate
+    // without binaries links it into its library: as a library named
+    // `library` if it is one, into cdylibs otherwise.
+    fn link_compiled(
+        compiled: &std::path::Path,
+        link_target: &LinkTarget,
+        library: Option<&str>,
+    ) -> Result<(), io::Error> {
+        let out_path = compiled.display();
+        match link_target {
+            LinkTarget::Default => match library {
+                _ if Self::package_has_bins(&codegen::base_dir()?) => {
+                    println!("cargo:rustc-link-arg-bins={}", out_path)
+                }
+                Some(name) => {
+                    let dir = compiled.parent().unwrap_or(Path::new("."));
+                    println!("cargo:rustc-link-search=native={}", dir.display());
+                    println!("cargo:rustc-link-lib=dylib={}", name);
+                }
+                None => println!("cargo:rustc-link-arg-cdylib={}", out_path),
+            },
+            LinkTarget::Bins(bins) => {
+                for bin in bins {
+                    println!("cargo:rustc-link-arg-bin={}={}", bin, out_path);
+                }
+            }
+            LinkTarget::Tests => println!("cargo:rustc-link-arg-tests={}", out_path),
+            LinkTarget::Benchmarks => println!("cargo:rustc-link-arg-benches={}", out_path),
+            LinkTarget::Examples => println!("cargo:rustc-link-arg-examples={}", out_path),
+            LinkTarget::Cdylib => println!("cargo:rustc-link-arg-cdylib={}", out_path),
+            LinkTarget::Everything => println!("cargo:rustc-link-arg={}", out_path),
+        }
+        Ok(())
+    }
+
+    // Whether the package has binaries: src/main.rs, src/bin, or `[[bin]]`
+    // targets in the manifest. The manifest isn't parsed, as with the spec
+    // feature off there is no TOML parser around.
+    fn package_has_bins(manifest_dir: &std::path::Path) -> bool {
+        let src = manifest_dir.join("src");
+        let declared = || match std::fs::read_to_string(manifest_dir.join("Cargo.toml")) {
+            Ok(manifest) => manifest.lines().any(|line| line.trim() == "[[bin]]"),
+            Err(e) => {
+                log!(warn, "can't read Cargo.toml: {}", e);
+                false
+            }
+        };
+        src.join("main.rs").is_file() || src.join("bin").is_dir() || declared()
+    }
+
+    pub fn compile(self) -> Result<(), io::Error> {
+        self.compile_with(&CompileOptions::default())
+    }
+
+    pub fn compile_with(self, options: &CompileOptions) -> Result<(), io::Error> {
+        self.compile_to_with(options).map(|_| ())
+    }
+
+    // Like compile, but tells where the generated script and the compiled
+    // file ended up, for build scripts that copy, sign or link them elsewhere.
+    pub fn compile_to(self) -> Result<CompileArtifacts, io::Error> {
+        self.compile_to_with(&CompileOptions::default())
+    }
+
+    pub fn compile_to_with(self, options: &CompileOptions) -> Result<CompileArtifacts, io::Error> {
+        let rc_file_path = codegen::out_dir()?.join("resource.rc");
+        let diagnostics = self.diagnostics.clone();
+        self.generate_rc_file(&rc_file_path)?;
+        let compiled = codegen::with_diagnostics(diagnostics, || {
+            Self::compile_rc_file_to_artifact(&rc_file_path, options)
+        })?;
+
+        Ok(CompileArtifacts {
+            rc_file: rc_file_path,
+            compiled,
+        })
+    }
+}
+
+// The files Build::compile_to produced. `compiled` is the .res, .o or static
+// library passed to the linker, and None when nothing was compiled because the
+// target isn't Windows or no resource compiler was available.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CompileArtifacts {
+    pub rc_file: std::path::PathBuf,
+    pub compiled: Option<std::path::PathBuf>,
+}
+
+// The resource compiler the generated script is written for. It decides how
+// strings are escaped, whether 32-bit numbers carry an `L` suffix and which
+// code page pragmas may be emitted.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Dialect {
+    #[default]
+    MsvcRc,
+    GnuWindres,
+    LlvmRc,
+}
+
+// The line ending of generated files. Line endings inside text copied into a
+// script, such as inlined scripts, are converted as well.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+// A resource as ResourceOrder::Custom compares it.
+pub type ResourceEntry<'a> = (&'a IdOrName, &'a dyn Resource);
+
+// How the resources of a language are ordered in generated scripts, and so in
+// the compiled file. Sorting is stable: resources comparing equal keep their
+// registration order.
+#[derive(Clone, Default)]
+pub enum ResourceOrder {
+    #[default]
+    Registration,
+    // by numeric resource type, RT_CURSOR first, user defined types last
+    ByType,
+    // names first, case-insensitively, then ids, as the compiled file's
+    // resource directory lists them
+    ById,
+    Custom(Rc<dyn Fn(ResourceEntry<'_>, ResourceEntry<'_>) -> std::cmp::Ordering>),
+}
+
+impl ResourceOrder {
+    pub fn custom(
+        f: impl Fn(ResourceEntry<'_>, ResourceEntry<'_>) -> std::cmp::Ordering + 'static,
+    ) -> Self {
+        ResourceOrder::Custom(Rc::new(f))
+    }
+
+    fn sort(&self, resources: &mut [ResourceEntry<'_>]) {
+        match self {
+            ResourceOrder::Registration => {}
+            ResourceOrder::ByType => {
+                resources.sort_by_cached_key(|&(_, resource)| Self::type_key(resource))
+            }
+            ResourceOrder::ById => {
+                resources.sort_by_cached_key(|&(id_or_name, _)| match id_or_name {
+                    IdOrName::Name(name) => (false, 0, name.to_uppercase()),
+                    IdOrName::Id(id) => (true, id.0, String::new()),
+                })
+            }
+            ResourceOrder::Custom(f) => resources.sort_by(|&a, &b| f(a, b)),
+        }
+    }
+
+    fn type_key(resource: &dyn Resource) -> (u32, Option<IdOrName>) {
+        match resource.user_defined_type() {
+            Some(IdOrName::Id(type_id)) => (u32::from(type_id.0), None),
+            Some(name) => (u32::MAX, Some(name)),
+            None => {
+                let type_id = match resource.type_keyword() {
+                    Some(resource::StringTable::TYPE_KEYWORD) => Some(6), // RT_STRING
+                    keyword => keyword.and_then(codegen::predefined_type_id),
+                };
+                (type_id.map_or(u32::MAX, u32::from), None)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ResourceOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceOrder::Registration => f.write_str("Registration"),
+            ResourceOrder::ByType => f.write_str("ByType"),
+            ResourceOrder::ById => f.write_str("ById"),
+            ResourceOrder::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+// Where warnings found while writing scripts, and those the resource compiler
+// prints while compiling them, go. `Collect` appends them to a shared list
+// the caller keeps a clone of, `Callback` hands each one to a closure, and
+// `Deny` turns the first one into an error.
+#[derive(Clone, Default)]
+pub enum Diagnostics {
+    #[default]
+    Stderr,
+    Ignore,
+    Collect(Rc<RefCell<Vec<String>>>),
+    Callback(Rc<dyn Fn(&str)>),
+    Deny,
+}
+
+impl Diagnostics {
+    pub fn collect() -> (Self, Rc<RefCell<Vec<String>>>) {
+        let list = Rc::new(RefCell::new(Vec::new()));
+        (Diagnostics::Collect(list.clone()), list)
+    }
+
+    pub fn callback(f: impl Fn(&str) + 'static) -> Self {
+        Diagnostics::Callback(Rc::new(f))
+    }
+
+    pub(crate) fn report(&self, message: String) -> Result<(), io::Error> {
+        log!(warn, "{}", message);
+        match self {
+            Diagnostics::Stderr => eprintln!("Warning: {}", message),
+            Diagnostics::Ignore => {}
+            Diagnostics::Collect(list) => list.borrow_mut().push(message),
+            Diagnostics::Callback(f) => f(&message),
+            Diagnostics::Deny => return Err(io::Error::new(io::ErrorKind::InvalidData, message)),
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostics::Stderr => f.write_str("Stderr"),
+            Diagnostics::Ignore => f.write_str("Ignore"),
+            Diagnostics::Collect(list) => f.debug_tuple("Collect").field(list).finish(),
+            Diagnostics::Callback(_) => f.write_str("Callback(..)"),
+            Diagnostics::Deny => f.write_str("Deny"),
+        }
+    }
+}
+
+// The scripts written by Build::generate_mui_rc_files.
+#[derive(PartialEq, Debug)]
+pub struct MuiScripts {
+    pub neutral: std::path::PathBuf,
+    pub languages: Vec<(Lang, std::path::PathBuf)>,
+    content_hashes: BTreeMap<std::path::PathBuf, u64>,
+}
+
+impl MuiScripts {
+    // Compiles every script into a .res file next to it, ready to be linked
+    // into the main binary and the resource-only satellite DLLs. A script is
+    // skipped when its .res was compiled from the same content, as recorded in
+    // a `.res.hash` file beside it, so changing one language's translations
+    // only recompiles that language.
+    pub fn compile_to_res(&self) -> Result<(), io::Error> {
+        let scripts = std::iter::once(&self.neutral).chain(self.languages.iter().map(|(_, p)| p));
+        for script in scripts {
+            let res_path = script.with_extension("res");
+            let hash_path = script.with_extension("res.hash");
+            let hash = self
+                .content_hashes
+                .get(script)
+                .map(|hash| format!("{:016x}", hash));
+            if let Some(hash) = &hash {
+                let recorded = std::fs::read_to_string(&hash_path).ok();
+                if res_path.exists() && recorded.as_deref() == Some(hash.as_str()) {
+                    log!(debug, "{} is up to date", res_path.display());
+                    continue;
+                }
+            }
+            Build::compile_rc_file_to_res(script, &res_path)?;
+            if let Some(hash) = hash {
+                std::fs::write(&hash_path, hash)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// A resource compiler, and the command line conventions it follows.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ResourceCompiler {
+    Rc(std::path::PathBuf),
+    Windres(std::path::PathBuf),
+    LlvmRc(std::path::PathBuf),
+}
+
+impl ResourceCompiler {
+    // Tells the kind apart by the file name, e.g. `x86_64-w64-mingw32-windres`
+    // or `llvm-rc.exe`; anything else is taken to be rc.exe.
+    pub fn from_path(path: impl Into<std::path::PathBuf>) -> Self {
+        let path = path.into();
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        if stem.ends_with("windres") {
+            ResourceCompiler::Windres(path)
+        } else if stem.starts_with("llvm-rc") {
+            ResourceCompiler::LlvmRc(path)
+        } else {
+            ResourceCompiler::Rc(path)
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        match self {
+            ResourceCompiler::Rc(path)
+            | ResourceCompiler::Windres(path)
+            | ResourceCompiler::LlvmRc(path) => path,
+        }
+    }
+
+    // The dialect to write scripts in for this compiler.
+    pub fn dialect(&self) -> Dialect {
+        match self {
+            ResourceCompiler::Rc(_) => Dialect::MsvcRc,
+            ResourceCompiler::Windres(_) => Dialect::GnuWindres,
+            ResourceCompiler::LlvmRc(_) => Dialect::LlvmRc,
+        }
+    }
+
+    // Finds the compiler for the target cargo is building for. `RC_$TARGET`,
+    // `RC_${TARGET//-/_}` and `RC` are honored first, as embed-resource does.
+    // Then MSVC targets look for rc.exe on PATH in a developer prompt
+    // (VCINSTALLDIR set), in WindowsSdkVerBinPath and in the installed Windows
+    // SDKs, and for llvm-rc when cross compiling; GNU targets look for the
+    // target-prefixed windres, then plain windres; gnullvm targets for
+    // llvm-rc. Outside of a build script the host is taken as the target.
+    pub fn detect() -> Result<Self, io::Error> {
+        let target = std::env::var("TARGET").ok();
+        if let Some(target) = &target {
+            for var in [
+                format!("RC_{}", target),
+                format!("RC_{}", target.replace('-', "_")),
+            ] {
+                if let Some(path) = std::env::var_os(&var) {
+                    log!(info, "using resource compiler {:?} from {}", path, var);
+                    return Ok(Self::from_path(path));
+                }
+            }
+        }
+        if let Some(path) = std::env::var_os("RC") {
+            log!(info, "using resource compiler {:?} from RC", path);
+            return Ok(Self::from_path(path));
+        }
+        let target_name = target.as_deref().unwrap_or("the host");
+        let is_msvc = match &target {
+            Some(target) => target.ends_with("-msvc"),
+            None => cfg!(windows),
+        };
+        let is_gnullvm = target
+            .as_deref()
+            .is_some_and(|target| target.ends_with("-gnullvm"));
+        let mut tried = Vec::new();
+        let mut on_path = |name: &str| {
+            let found = find_in_path(name);
+            tried.push(name.to_owned());
+            found
+        };
+        let found = if is_msvc {
+            let arch = match std::env::var("CARGO_CFG_TARGET_ARCH").as_deref() {
+                Ok("x86") => "x86",
+                Ok("aarch64") => "arm64",
+                _ => "x64",
+            };
+            std::env::var_os("VCINSTALLDIR")
+                .and_then(|_| on_path("rc.exe"))
+                .or_else(|| {
+                    let dir = std::env::var_os("WindowsSdkVerBinPath")?;
+                    let path = Path::new(&dir).join(arch).join("rc.exe");
+                    path.is_file().then_some(path)
+                })
+                .or_else(|| {
+                    if cfg!(windows) {
+                        embed_resource::find_windows_sdk_tool("rc.exe")
+                    } else {
+                        None
+                    }
+                })
+                .or_else(|| on_path("llvm-rc"))
+        } else if is_gnullvm {
+            on_path("llvm-rc")
+        } else {
+            target
+                .as_deref()
+                .and_then(|target| {
+                    let arch = target.split('-').next()?;
+                    on_path(&format!("{}-w64-mingw32-windres", arch))
+                })
+                .or_else(|| on_path("windres"))
+        };
+        match found {
+            Some(path) => {
+                log!(
+                    info,
+                    "using resource compiler {} for {}",
+                    path.display(),
+                    target_name
+                );
+                Ok(Self::from_path(path))
+            }
+            None => {
+                if is_msvc {
+                    tried.insert(0, "rc.exe from the Windows SDK".to_owned());
+                }
+                Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!(
+                        "no resource compiler found for {} (looked for {}); set RC to the one to use",
+                        target_name,
+                        tried.join(", ")
+                    ),
+                ))
+            }
+        }
+    }
+}
+
+// The file `name` (or `name.exe` on Windows) in one of the PATH directories.
+fn find_in_path(name: &str) -> Option<std::path::PathBuf> {
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths).find_map(|dir| {
+        let path = dir.join(name);
+        if path.is_file() {
+            return Some(path);
+        }
+        let path = dir.join(format!("{}.exe", name));
+        (cfg!(windows) && path.is_file()).then_some(path)
+    })
+}
+
+// Which cargo artifacts the compiled resource gets linked into.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub enum LinkTarget {
+    // binaries if the crate has any, the library otherwise
+    #[default]
+    Default,
+    Bins(Vec<String>),
+    Tests,
+    Benchmarks,
+    Examples,
+    Cdylib,
+    Everything,
+}
+
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct CompileOptions {
+    link_target: LinkTarget,
+    macros: Vec<std::ffi::OsString>,
+    include_dirs: Vec<std::path::PathBuf>,
+    manifest_required: bool,
+    link_args: bool,
+    detected_compiler: bool,
+    preprocessor: Option<std::ffi::OsString>,
+    preprocessor_args: Vec<std::ffi::OsString>,
+}
+
+impl CompileOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn link_target(mut self, link_target: LinkTarget) -> Self {
+        self.link_target = link_target;
+        self
+    }
+
+    // `NAME` or `NAME=VALUE`
+    pub fn define(mut self, macro_definition: impl Into<std::ffi::OsString>) -> Self {
+        self.macros.push(macro_definition.into());
+        self
+    }
+
+    pub fn include_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.include_dirs.push(dir.as_ref().to_owned());
+        self
+    }
+
+    // Fail when no resource compiler is available for a Windows target,
+    // instead of silently skipping the resource.
+    pub fn manifest_required(mut self) -> Self {
+        self.manifest_required = true;
+        self
+    }
+
+    pub fn manifest_optional(mut self) -> Self {
+        self.manifest_required = false;
+        self
+    }
+
+    // Hands the compiled file to the linker with `cargo:rustc-link-arg`
+    // directives only, instead of as a static library where a crate without
+    // binaries is concerned. There are no library name clashes then, and
+    // cdylib targets get the resources too.
+    pub fn link_args(mut self, link_args: bool) -> Self {
+        self.link_args = link_args;
+        self
+    }
+
+    // Compiles with the compiler ResourceCompiler::detect finds (rc, windres
+    // or llvm-rc) instead of through embed-resource, which stays the default
+    // and is still used when nothing is detected.
+    pub fn use_detected_compiler(mut self, detected_compiler: bool) -> Self {
+        self.detected_compiler = detected_compiler;
+        self
+    }
+
+    // windres' `--preprocessor`, the command it runs the script through
+    // instead of `gcc -E -xc -DRC_INVOKED`. Only windres takes it, and only
+    // with use_detected_compiler or link_args: embed-resource doesn't pass
+    // it on.
+    pub fn preprocessor(mut self, preprocessor: impl Into<std::ffi::OsString>) -> Self {
+        self.preprocessor = Some(preprocessor.into());
+        self
+    }
+
+    // windres' `--preprocessor-arg`, one argument added to the preprocessor's
+    // command line; same restrictions as `preprocessor`.
+    pub fn preprocessor_arg(mut self, arg: impl Into<std::ffi::OsString>) -> Self {
+        self.preprocessor_args.push(arg.into());
+        self
+    }
+
+    fn has_preprocessor_options(&self) -> bool {
+        self.preprocessor.is_some() || !self.preprocessor_args.is_empty()
+    }
+}
+
+// scripts are written one statement per `write!(w, "...\n")`, keep it that way
+#[allow(clippy::write_with_newline)]
+mod codegen {
+    use crate::resource;
+    use crate::sys::minwindef::{UINT, WORD};
+    use crate::CowStr;
+    use crate::{Dialect, Id, IdOrName};
+    use std::cell::{Cell, RefCell};
+    use std::collections::HashMap;
+    use std::io::{Error as IOError, Write};
+    use std::path::{Path, PathBuf};
+
+    pub(crate) const DEFAULT_CODE_PAGE: UINT = 65001;
+
+    // Resource::write_script_segment only receives the writer, so the dialect
+    // and the code page currently in effect live here while a script is written.
+    thread_local! {
+        static DIALECT: Cell<Dialect> = const { Cell::new(Dialect::MsvcRc) };
+        static CODE_PAGE: Cell<UINT> = const { Cell::new(DEFAULT_CODE_PAGE) };
+        static WIDE_TEXT: Cell<bool> = const { Cell::new(false) };
+    }
+
+    pub(crate) fn with_wide_text<R>(wide_text: bool, f: impl FnOnce() -> R) -> R {
+        let previous = WIDE_TEXT.with(|t| t.replace(wide_text));
+        let r = f();
+        WIDE_TEXT.with(|t| t.set(previous));
+        r
+    }
+
+    // Generated files for OUT_DIR, by path, waiting to be written.
+    pub(crate) type OutDirFiles = Vec<(PathBuf, Vec<u8>)>;
+
+    thread_local! {
+        static OUT_DIR_FILES: RefCell<Option<OutDirFiles>> = const { RefCell::new(None) };
+    }
+
+    // Names generated content for OUT_DIR after its hash, for resources that
+    // can only refer to a file. The content is only recorded here; scripts
+    // written under `with_out_dir_files` hand it to `write_out_dir_files`.
+    pub(crate) fn out_dir_file(
+        prefix: &str,
+        extension: &str,
+        content: &[u8],
+    ) -> Result<PathBuf, IOError> {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        content.hash(&mut hasher);
+        let file_name = format!("{}-{:016x}.{}", prefix, hasher.finish(), extension);
+        let path = out_dir()?.join(file_name);
+        OUT_DIR_FILES.with(|files| {
+            if let Some(files) = files.borrow_mut().as_mut() {
+                files.push((path.clone(), content.to_vec()));
+            }
+        });
+        Ok(path)
+    }
+
+    // Runs `f`, returning the OUT_DIR files it named along with its result.
+    pub(crate) fn with_out_dir_files<R>(f: impl FnOnce() -> R) -> (R, OutDirFiles) {
+        let previous = OUT_DIR_FILES.with(|files| files.replace(Some(Vec::new())));
+        let r = f();
+        let files = OUT_DIR_FILES.with(|files| files.replace(previous));
+        (r, files.unwrap_or_default())
+    }
+
+    // An unchanged file isn't rewritten, so the resources aren't recompiled
+    // for nothing.
+    pub(crate) fn write_out_dir_files(files: &OutDirFiles) -> Result<(), IOError> {
+        for (path, content) in files {
+            if std::fs::read(path).ok().as_ref() != Some(content) {
+                write_file_atomically(path, |w| w.write_all(content))?;
+            }
+        }
+        Ok(())
+    }
+
+    // Writes `path` through a buffered temporary file next to it, renamed over
+    // `path` only once `f` succeeds, so a failed run never leaves a truncated
+    // file behind for the next incremental build to pick up.
+    pub(crate) fn write_file_atomically(
+        path: &Path,
+        f: impl FnOnce(&mut dyn Write) -> Result<(), IOError>,
+    ) -> Result<(), IOError> {
+        log!(debug, "writing {}", path.display());
+        let mut temp_name = path.file_name().unwrap_or_default().to_owned();
+        temp_name.push(format!(".{}.tmp", std::process::id()));
+        let temp_path = path.with_file_name(temp_name);
+        let result = std::fs::File::create(&temp_path).and_then(|file| {
+            let mut file = std::io::BufWriter::new(file);
+            f(&mut file)?;
+            file.into_inner().map_err(|e| e.into_error())?.sync_all()
+        });
+        match result.and_then(|_| std::fs::rename(&temp_path, path)) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                let _ = std::fs::remove_file(&temp_path);
+                Err(e)
+            }
+        }
+    }
+
+    pub(crate) fn write_file_with_line_ending(
+        path: &Path,
+        line_ending: crate::LineEnding,
+        f: impl FnOnce(&mut dyn Write) -> Result<(), IOError>,
+    ) -> Result<(), IOError> {
+        write_file_atomically(path, |w| {
+            let mut w = LineEndingWriter::new(w, line_ending);
+            f(&mut w)?;
+            w.finish()
+        })
+    }
+
+    // Turns the `\n` line endings codegen writes, and the `\r\n` ones of text
+    // passed through, into `line_ending`. `finish` writes out a trailing `\r`
+    // held back in case a `\n` follows in the next write.
+    pub(crate) struct LineEndingWriter<'a> {
+        inner: &'a mut dyn Write,
+        line_ending: crate::LineEnding,
+        pending_cr: bool,
+    }
+
+    impl<'a> LineEndingWriter<'a> {
+        pub(crate) fn new(inner: &'a mut dyn Write, line_ending: crate::LineEnding) -> Self {
+            LineEndingWriter {
+                inner,
+                line_ending,
+                pending_cr: false,
+            }
+        }
+
+        pub(crate) fn finish(&mut self) -> Result<(), IOError> {
+            if std::mem::take(&mut self.pending_cr) {
+                self.inner.write_all(b"\r")?;
+            }
+            self.inner.flush()
+        }
+    }
+
+    impl Write for LineEndingWriter<'_> {
+        fn write(&mut self, buf: &[u8]) -> Result<usize, IOError> {
+            let newline: &[u8] = match self.line_ending {
+                crate::LineEnding::Lf => b"\n",
+                crate::LineEnding::CrLf => b"\r\n",
+            };
+            let mut converted = Vec::with_capacity(buf.len() + buf.len() / 16);
+            for &byte in buf {
+                if std::mem::take(&mut self.pending_cr) && byte != b'\n' {
+                    converted.push(b'\r');
+                }
+                match byte {
+                    b'\r' => self.pending_cr = true,
+                    b'\n' => converted.extend_from_slice(newline),
+                    _ => converted.push(byte),
+                }
+            }
+            self.inner.write_all(&converted)?;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<(), IOError> {
+            self.inner.flush()
+        }
+    }
+
+    // Relative resource paths are taken from the package being built, so they
+    // mean the same whatever directory a build script or tool runs in; outside
+    // of cargo, from the current directory.
+    pub(crate) fn base_dir() -> Result<PathBuf, IOError> {
+        match std::env::var_os("CARGO_MANIFEST_DIR") {
+            Some(dir) => Ok(PathBuf::from(dir)),
+            None => std::env::current_dir(),
+        }
+    }
+
+    // Where cargo wants a build script's output; the compile methods need it.
+    pub(crate) fn out_dir() -> Result<PathBuf, IOError> {
+        std::env::var_os("OUT_DIR")
+            .map(PathBuf::from)
+            .ok_or_else(|| {
+                IOError::new(
+                    std::io::ErrorKind::NotFound,
+                    "OUT_DIR isn't set, not run by cargo?",
+                )
+            })
+    }
+
+    // Resolves resource file paths against base_dir, remembering each
+    // result. Files that exist are canonicalized, others are only made
+    // absolute.
+    pub(crate) struct PathResolver {
+        base_dir: PathBuf,
+        resolved: HashMap<PathBuf, PathBuf>,
+    }
+
+    impl PathResolver {
+        pub(crate) fn new() -> Result<Self, IOError> {
+            Ok(PathResolver {
+                base_dir: base_dir()?,
+                resolved: HashMap::new(),
+            })
+        }
+
+        pub(crate) fn resolve(&mut self, path: &Path) -> PathBuf {
+            if let Some(resolved) = self.resolved.get(path) {
+                return resolved.clone();
+            }
+            let absolute = self.base_dir.join(path);
+            let resolved = match absolute.canonicalize() {
+                Ok(canonical) => strip_verbatim_prefix(canonical),
+                Err(_) => absolute,
+            };
+            self.resolved.insert(path.to_owned(), resolved.clone());
+            resolved
+        }
+    }
+
+    // `canonicalize` yields `\\?\C:\...` on Windows, which resource compilers
+    // don't accept; turn such paths back into ordinary ones.
+    pub(crate) fn strip_verbatim_prefix(path: PathBuf) -> PathBuf {
+        let string = match path.to_str() {
+            Some(string) => string,
+            None => return path,
+        };
+        if let Some(rest) = string.strip_prefix(r"\\?\UNC\") {
+            PathBuf::from(format!(r"\\{}", rest))
+        } else if let Some(rest) = string.strip_prefix(r"\\?\") {
+            if rest.as_bytes().get(1) == Some(&b':') {
+                PathBuf::from(rest)
+            } else {
+                path
+            }
+        } else {
+            path
+        }
+    }
+
+    thread_local! {
+        static PATH_RESOLVER: RefCell<Option<PathResolver>> = const { RefCell::new(None) };
+        static DIAGNOSTICS: RefCell<crate::Diagnostics> = RefCell::new(crate::Diagnostics::default());
+    }
+
+    // Routes warnings raised by `f` to the build's diagnostics.
+    pub(crate) fn with_diagnostics<R>(diagnostics: crate::Diagnostics, f: impl FnOnce() -> R) -> R {
+        let previous = DIAGNOSTICS.with(|d| d.replace(diagnostics));
+        let r = f();
+        DIAGNOSTICS.with(|d| *d.borrow_mut() = previous);
+        r
+    }
+
+    pub(crate) fn report(message: String) -> Result<(), IOError> {
+        let diagnostics = DIAGNOSTICS.with(|d| d.borrow().clone());
+        diagnostics.report(message)
+    }
+
+    thread_local! {
+        static TABLE_STRINGS: RefCell<HashMap<(crate::Lang, Id), String>> = RefCell::new(HashMap::new());
+    }
+
+    // Makes the build's STRINGTABLE entries available to texts given by
+    // string id while `f` runs.
+    pub(crate) fn with_table_strings<R>(
+        strings: HashMap<(crate::Lang, Id), String>,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let previous = TABLE_STRINGS.with(|s| s.replace(strings));
+        let r = f();
+        TABLE_STRINGS.with(|s| *s.borrow_mut() = previous);
+        r
+    }
+
+    pub(crate) fn table_string(lang: crate::Lang, id: &Id) -> Result<String, IOError> {
+        TABLE_STRINGS
+            .with(|s| s.borrow().get(&(lang, id.clone())).cloned())
+            .ok_or_else(|| {
+                IOError::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("string {} isn't in the build's string tables", id),
+                )
+            })
+    }
+
+    thread_local! {
+        static BUILD_LANGUAGES: RefCell<Vec<crate::Lang>> = const { RefCell::new(Vec::new()) };
+    }
+
+    // Makes the languages the build writes known while `f` runs.
+    pub(crate) fn with_build_languages<R>(langs: Vec<crate::Lang>, f: impl FnOnce() -> R) -> R {
+        let previous = BUILD_LANGUAGES.with(|l| l.replace(langs));
+        let r = f();
+        BUILD_LANGUAGES.with(|l| *l.borrow_mut() = previous);
+        r
+    }
+
+    // The build's languages in script order, or just `lang` when the resource
+    // isn't written as part of a build.
+    pub(crate) fn build_languages_or(lang: crate::Lang) -> Vec<crate::Lang> {
+        let langs = BUILD_LANGUAGES.with(|l| l.borrow().clone());
+        if langs.is_empty() {
+            vec![lang]
+        } else {
+            langs
+        }
+    }
+
+    // Runs one generation with a single resolver, so the base directory is
+    // looked up once and every file is resolved at most once.
+    pub(crate) fn with_path_resolution<R>(
+        f: impl FnOnce() -> Result<R, IOError>,
+    ) -> Result<R, IOError> {
+        let previous = PATH_RESOLVER.with(|r| r.replace(None));
+        let resolver = PathResolver::new()?;
+        PATH_RESOLVER.with(|r| *r.borrow_mut() = Some(resolver));
+        let result = f();
+        PATH_RESOLVER.with(|r| *r.borrow_mut() = previous);
+        result
+    }
+
+    fn resolve_path(path: &Path) -> Result<PathBuf, IOError> {
+        PATH_RESOLVER.with(|r| match r.borrow_mut().as_mut() {
+            Some(resolver) => Ok(resolver.resolve(path)),
+            None => Ok(PathResolver::new()?.resolve(path)),
+        })
+    }
+
+    pub(crate) fn with_dialect<R>(dialect: Dialect, f: impl FnOnce() -> R) -> R {
+        let previous_dialect = DIALECT.with(|d| d.replace(dialect));
+        let previous_code_page = CODE_PAGE.with(|c| c.replace(DEFAULT_CODE_PAGE));
+        let r = f();
+        DIALECT.with(|d| d.set(previous_dialect));
+        CODE_PAGE.with(|c| c.set(previous_code_page));
+        r
+    }
+
+    pub(crate) fn dialect() -> Dialect {
+        DIALECT.with(|d| d.get())
+    }
+
+    pub(crate) fn current_code_page() -> UINT {
+        CODE_PAGE.with(|c| c.get())
+    }
+
+    // windres evaluates every integer as 32-bit, the Microsoft compiler and
+    // llvm-rc need the suffix to keep values from being truncated to 16 bits.
+    fn long_suffix() -> &'static str {
+        match dialect() {
+            Dialect::MsvcRc | Dialect::LlvmRc => "L",
+            Dialect::GnuWindres => "",
+        }
+    }
+
+    pub(crate) fn write_header(w: &mut dyn Write) -> Result<(), IOError> {
+        write!(
+            w,
+            "// Resource script automatically generated by RESW-RS.\n"
+        )?;
+        write!(w, "// Do not edit this file manually.\n")?;
+        write!(w, "\n")?;
+        write_code_page_pragma(w, DEFAULT_CODE_PAGE)?;
+        Ok(())
+    }
+
+    pub(crate) fn write_header_include(
+        w: &mut dyn Write,
+        header: &str,
+        system: bool,
+    ) -> Result<(), IOError> {
+        if header.contains(['"', '<', '>', '\n']) {
+            return Err(IOError::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("can't #include {:?}", header),
+            ));
+        }
+        if system {
+            write!(w, "#include <{}>\n", header)
+        } else {
+            write!(w, "#include \"{}\"\n", header)
+        }
+    }
+
+    // The sections Visual Studio keeps in every script it edits, only read by
+    // the resource editor (APSTUDIO_INVOKED is never defined otherwise): the
+    // symbol header, which is the first header included with quotes, and the
+    // other includes.
+    pub(crate) fn write_text_includes(
+        w: &mut dyn Write,
+        headers: &[(String, bool)],
+    ) -> Result<(), IOError> {
+        let symbol_header = headers.iter().position(|(_, system)| !system);
+        write!(w, "#ifdef APSTUDIO_INVOKED\n")?;
+        write!(w, "1 TEXTINCLUDE\nBEGIN\n")?;
+        if let Some(pos) = symbol_header {
+            write!(w, "\t")?;
+            write_narrow_str(w, &headers[pos].0)?;
+            write!(w, "\n")?;
+        }
+        write!(w, "\t\"\\0\"\nEND\n")?;
+        write!(w, "2 TEXTINCLUDE\nBEGIN\n")?;
+        for (pos, (header, system)) in headers.iter().enumerate() {
+            if Some(pos) == symbol_header {
+                continue;
+            }
+            let mut line = Vec::new();
+            write_header_include(&mut line, header, *system)?;
+            // the form the editor writes itself, quotes doubled
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end().replace('"', "\"\"");
+            write!(w, "\t\"{}\\r\\n\"\n", line)?;
+        }
+        write!(w, "\t\"\\r\\n\"\n\t\"\\0\"\nEND\n")?;
+        write!(w, "3 TEXTINCLUDE\nBEGIN\n\t\"\\r\\n\"\n\t\"\\0\"\nEND\n")?;
+        write!(w, "#endif\n")?;
+        Ok(())
+    }
+
+    // An empty guideline block for each dialog of `lang`, which the dialog
+    // editor wants before it opens a dialog.
+    pub(crate) fn write_design_info(
+        w: &mut dyn Write,
+        lang: crate::Lang,
+        dialogs: &[&IdOrName],
+    ) -> Result<(), IOError> {
+        write!(w, "#ifdef APSTUDIO_INVOKED\n")?;
+        write!(w, "LANGUAGE 0x{:x}, 0x{:x}\n", lang.0, lang.1)?;
+        write!(w, "GUIDELINES DESIGNINFO\nBEGIN\n")?;
+        for id_or_name in dialogs {
+            write!(w, "\t")?;
+            write_id_or_name(w, id_or_name)?;
+            write!(w, ", DIALOG\n\tBEGIN\n\tEND\n")?;
+        }
+        write!(w, "END\n")?;
+        write!(w, "#endif\n")?;
+        Ok(())
+    }
+
+    pub(crate) fn write_fragment_header(w: &mut dyn Write) -> Result<(), IOError> {
+        write!(
+            w,
+            "// Resource script fragment automatically generated by RESW-RS.\n"
+        )?;
+        write!(
+            w,
+            "// Include it from a resource script using code page 65001.\n"
+        )?;
+        write!(w, "\n")?;
+        Ok(())
+    }
+
+    pub(crate) fn write_code_page_pragma(
+        w: &mut dyn Write,
+        code_page: UINT,
+    ) -> Result<(), IOError> {
+        if dialect() == Dialect::LlvmRc && code_page != 65001 && code_page != 1252 {
+            return Err(IOError::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("llvm-rc does not support code page {}", code_page),
+            ));
+        }
+        CODE_PAGE.with(|c| c.set(code_page));
+        write!(w, "#pragma code_page({})\n", code_page)
+    }
+
+    pub(crate) fn write_c_numeric(
+        w: &mut dyn Write,
+        c_numeric: impl std::fmt::Display,
+    ) -> Result<(), IOError> {
+        if std::mem::size_of_val(&c_numeric) > 2 {
+            write!(w, "{}{}", c_numeric, long_suffix())
+        } else {
+            write!(w, "{}", c_numeric)
+        }
+    }
+
+    pub(crate) fn write_c_uchar(
+        w: &mut dyn Write,
+        c_uchar: crate::sys::ctypes::c_uchar,
+    ) -> Result<(), IOError> {
+        write_c_numeric(w, c_uchar)
+    }
+
+    pub(crate) fn write_c_int(
+        w: &mut dyn Write,
+        c_int: crate::sys::ctypes::c_int,
+    ) -> Result<(), IOError> {
+        write_c_numeric(w, c_int)
+    }
+
+    pub(crate) fn write_c_long(
+        w: &mut dyn Write,
+        c_long: crate::sys::ctypes::c_long,
+    ) -> Result<(), IOError> {
+        write_c_numeric(w, c_long)
+    }
+
+    pub(crate) fn write_dword(
+        w: &mut dyn Write,
+        dword: crate::sys::minwindef::DWORD,
+    ) -> Result<(), IOError> {
+        write!(w, "{}{}", dword, long_suffix())
+    }
+
+    pub(crate) fn write_mandatory_dword(
+        w: &mut dyn Write,
+        dword: Option<&crate::sys::minwindef::DWORD>,
+    ) -> Result<(), IOError> {
+        write_dword(w, dword.cloned().unwrap())
+    }
+
+    pub(crate) fn write_rect(w: &mut dyn Write, rect: &crate::Rect) -> Result<(), IOError> {
+        write_c_int(w, rect.x)?;
+        write!(w, ", ")?;
+        write_c_int(w, rect.y)?;
+        write!(w, ", ")?;
+        write_c_int(w, rect.width)?;
+        write!(w, ", ")?;
+        write_c_int(w, rect.height)?;
+        Ok(())
+    }
+
+    pub(crate) fn write_mandatory_rect(
+        w: &mut dyn Write,
+        rect: Option<&crate::Rect>,
+    ) -> Result<(), IOError> {
+        if let Some(rect) = rect {
+            write_rect(w, rect)
+        } else {
+            write_rect(w, &crate::Rect::default())
+        }
+    }
+
+    pub(crate) fn write_font(w: &mut dyn Write, font: &crate::Font) -> Result<(), IOError> {
+        write_c_int(w, font.size.0)?;
+        write!(w, ", ")?;
+        ensure_narrow_str_encodable(&font.typeface, "font typeface")?;
+        write_narrow_str(w, &font.typeface)?;
+        write!(w, ", ")?;
+        write_c_long(w, font.weight.0)?;
+        write!(w, ", ")?;
+        write_c_int(w, font.italic.0)?;
+        write!(w, ", ")?;
+        write_c_uchar(w, font.charset.0)?;
+        Ok(())
+    }
+
+    pub(crate) fn need_escape_narrow_byte(v: &u8) -> bool {
+        matches!(v, 0..=31u8 | b'\\' | b'\"' | 127u8)
+    }
+
+    pub(crate) fn need_escape_wide_u16(v: &u16) -> bool {
+        match v {
+            0..=31u16 => true,
+            92u16 /*b'\\'*/ => true,
+            34u16 /*b'\"'*/ => true,
+            127u16 => true,
+            32u16..=126u16 => false,
+            _ => true,
+        }
+    }
+
+    pub(crate) fn write_mandatory_text(
+        w: &mut dyn Write,
+        string: Option<&CowStr>,
+    ) -> Result<(), IOError> {
+        write_text(w, string.map_or("", |string| string))
+    }
+
+    // User visible text. Narrow strings only keep non-ASCII text intact when
+    // the compiler honors the code page pragma, which windres and older rc
+    // don't, so such text is written as a wide string instead, as is all text
+    // when the build asks for wide strings.
+    pub(crate) fn write_text(w: &mut dyn Write, string: &str) -> Result<(), IOError> {
+        if string.is_ascii() && !WIDE_TEXT.with(|t| t.get()) {
+            write_narrow_str(w, string)
+        } else {
+            write_wide_os_str(w, std::ffi::OsStr::new(string))
+        }
+    }
+
+    pub(crate) fn write_narrow_str(w: &mut dyn Write, string: &str) -> Result<(), IOError> {
+        write!(w, "\"")?;
+        let bytes = string.as_bytes();
+        let mut run_start = 0;
+        for (idx, byte) in bytes.iter().enumerate() {
+            if need_escape_narrow_byte(byte) {
+                w.write_all(&bytes[run_start..idx])?;
+                write!(w, "\\{:03o}", byte)?;
+                run_start = idx + 1;
+            }
+        }
+        w.write_all(&bytes[run_start..])?;
+        write!(w, "\"")?;
+        Ok(())
+    }
+
+    // Raw bytes, not text: every byte outside printable ASCII is escaped, so
+    // the script's code page doesn't change them.
+    pub(crate) fn write_narrow_bytes(w: &mut dyn Write, bytes: &[u8]) -> Result<(), IOError> {
+        write!(w, "\"")?;
+        for byte in bytes {
+            if need_escape_narrow_byte(byte) || !byte.is_ascii() {
+                write!(w, "\\{:03o}", byte)?;
+            } else {
+                w.write_all(&[*byte])?;
+            }
+        }
+        write!(w, "\"")?;
+        Ok(())
+    }
+
+    // Raw UTF-16 code units, unpaired surrogates included.
+    pub(crate) fn write_wide_units(w: &mut dyn Write, units: &[u16]) -> Result<(), IOError> {
+        write!(w, "L\"")?;
+        for unit in units {
+            if need_escape_wide_u16(unit) {
+                write!(w, "\\x{:04x}", unit)?;
+            } else {
+                w.write_all(&[*unit as u8])?;
+            }
+        }
+        write!(w, "\"")?;
+        Ok(())
+    }
+
+    #[cfg(windows)]
+    fn os_str_units(name: &std::ffi::OsStr) -> Vec<u16> {
+        use std::os::windows::ffi::OsStrExt;
+        name.encode_wide().collect()
+    }
+
+    #[cfg(not(windows))]
+    fn os_str_units(name: &std::ffi::OsStr) -> Vec<u16> {
+        name.to_string_lossy().encode_utf16().collect()
+    }
+
+    fn write_wide_os_str(w: &mut dyn Write, name: &std::ffi::OsStr) -> Result<(), IOError> {
+        // windres doesn't seem supporting 4 byte escaped character at all,
+        // so non-ascii text is written as is when the script is utf-8.
+        let raw_non_ascii =
+            dialect() == Dialect::GnuWindres && current_code_page() == DEFAULT_CODE_PAGE;
+        write!(w, "L\"")?;
+        let mut buf = [0u16; 2];
+        for ch in std::char::decode_utf16(os_str_units(name)) {
+            let units: &[u16] = match ch {
+                Ok(ch) if raw_non_ascii && !ch.is_ascii() => {
+                    write!(w, "{}", ch)?;
+                    continue;
+                }
+                Ok(ch) => ch.encode_utf16(&mut buf),
+                Err(e) => {
+                    buf[0] = e.unpaired_surrogate();
+                    &buf[..1]
+                }
+            };
+            for &ch in units {
+                if ch == b'\\' as u16 {
+                    write!(w, "\\\\")?;
+                } else if !need_escape_wide_u16(&ch) {
+                    debug_assert!(ch <= u8::MAX as _);
+                    let ch: [u8; 1] = [ch as u8];
+                    w.write_all(&ch)?;
+                } else {
+                    write!(w, "\\x{:04x}", ch)?;
+                }
+            }
+        }
+        write!(w, "\"")?;
+        Ok(())
+    }
+
+    pub(crate) fn write_id(w: &mut dyn Write, id: &Id) -> Result<(), IOError> {
+        write!(w, "{}", id.0)
+    }
+
+    pub(crate) fn write_id_or_name(
+        w: &mut dyn Write,
+        id_or_name: &IdOrName,
+    ) -> Result<(), IOError> {
+        match id_or_name {
+            IdOrName::Id(id) => write!(w, "{}", id),
+            IdOrName::Name(name) => {
+                ensure_narrow_str_encodable(name, "resource name")?;
+                write_narrow_str(w, name)
+            }
+        }
+    }
+
+    // Narrow strings are written as UTF-8, which rc only decodes correctly
+    // under the default code page pragma; elsewhere they have to be ASCII.
+    fn ensure_narrow_str_encodable(string: &str, what: &str) -> Result<(), IOError> {
+        let code_page = current_code_page();
+        if code_page != DEFAULT_CODE_PAGE && !string.is_ascii() {
+            return Err(IOError::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "{} {:?} is not ASCII, which code page {} can't express",
+                    what, string, code_page
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    // File names only use the escapes every resource compiler reads back the
+    // same way there: `\\` for a backslash and `""` for a quote. `%`, `#` and
+    // the like mean nothing inside a string literal and are written as is.
+    // Control characters have no such form and are rejected.
+    pub(crate) fn write_path(w: &mut dyn Write, path: &std::path::Path) -> Result<(), IOError> {
+        let units = os_str_units(path.as_os_str());
+        if let Some(&unit) = units.iter().find(|&&unit| unit < 0x20 || unit == 0x7f) {
+            return Err(IOError::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "file name {:?} contains control character 0x{:02x}, \
+                     which a resource script can't express",
+                    path, unit
+                ),
+            ));
+        }
+        // same as write_wide_os_str, windres only takes non-ascii text as is
+        let raw_non_ascii =
+            dialect() == Dialect::GnuWindres && current_code_page() == DEFAULT_CODE_PAGE;
+        if units.iter().any(|&unit| unit >= 0x80) {
+            write!(w, "L")?;
+        }
+        write!(w, "\"")?;
+        let mut buf = [0u16; 2];
+        for ch in std::char::decode_utf16(units.iter().cloned()) {
+            match ch {
+                Ok('\\') => write!(w, "\\\\")?,
+                Ok('"') => write!(w, "\"\"")?,
+                Ok(ch) if ch.is_ascii() || raw_non_ascii => write!(w, "{}", ch)?,
+                Ok(ch) => {
+                    for unit in ch.encode_utf16(&mut buf) {
+                        write!(w, "\\x{:04x}", unit)?;
+                    }
+                }
+                Err(e) => write!(w, "\\x{:04x}", e.unpaired_surrogate())?,
+            }
+        }
+        write!(w, "\"")?;
+        Ok(())
+    }
+
+    fn ensure_id_or_name_ignorable(id_or_name: &IdOrName) -> Result<(), IOError> {
+        match id_or_name {
+            &IdOrName::Id(Id(v)) => {
+                if v == 0 || v == (-1i16 as WORD) {
+                    return Ok(());
+                }
+            }
+            IdOrName::Name(s) => {
+                if s.is_empty() || s == " " || s == "_" {
+                    return Ok(());
+                }
+            }
+        }
+        report(format!(
+            "Expected ignorable id or name, found {:?}. Ignored.",
+            id_or_name
+        ))
+    }
+
+    pub(crate) fn write_extra_info(
+        w: &mut dyn Write,
+        extra_info: Option<&crate::ExtraInfo>,
+    ) -> Result<(), IOError> {
+        if let Some(extra_info) = extra_info {
+            if let Some(characteristics) = &extra_info.characteristics {
+                write!(w, " ")?;
+                write_dword(w, *characteristics)?;
+            }
+            if let Some(version) = &extra_info.version {
+                write!(w, " ")?;
+                write_dword(w, *version)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_style_and_exstyle_statements(
+        w: &mut dyn Write,
+        style: crate::dialog::WindowStyle,
+    ) -> Result<(), IOError> {
+        match (style.0.as_ref(), style.1.as_ref()) {
+            (None, None) => {}
+            (Some(basic), None) => {
+                write!(w, "\nSTYLE ")?;
+                write_dword(w, *basic)?;
+            }
+            (None, Some(extended)) => {
+                write!(w, "\nEXSTYLE ")?;
+                write_dword(w, *extended)?;
+            }
+            (Some(basic), Some(extended)) => {
+                write!(w, "\nSTYLE ")?;
+                write_dword(w, *basic)?;
+                write!(w, " EXSTYLE ")?;
+                write_dword(w, *extended)?;
+            }
+        };
+        Ok(())
+    }
+
+    pub(crate) fn write_rc_fragment(
+        w: &mut dyn Write,
+        path: &std::path::Path,
+        inline: bool,
+    ) -> Result<(), IOError> {
+        let path = resolve_path(path)?;
+        if inline {
+            let text = std::fs::read(&path)
+                .map_err(|e| IOError::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+            let text = script_text_as_utf8(&path, text)?;
+            write!(w, "// {}\n", path.display())?;
+            w.write_all(&text)?;
+            if !text.ends_with(b"\n") {
+                write!(w, "\n")?;
+            }
+        } else {
+            write!(w, "#include ")?;
+            write_include_path(w, &path)?;
+            write!(w, "\n")?;
+        }
+        Ok(())
+    }
+
+    // Scripts saved by Visual Studio are often UTF-16 with a byte order mark;
+    // the generated script is UTF-8, so those are converted before being
+    // copied in. A UTF-8 byte order mark is dropped, anything else is copied
+    // as is.
+    fn script_text_as_utf8(path: &std::path::Path, text: Vec<u8>) -> Result<Vec<u8>, IOError> {
+        let from_units: fn([u8; 2]) -> u16 = match text.get(..2) {
+            Some(b"\xff\xfe") => u16::from_le_bytes,
+            Some(b"\xfe\xff") => u16::from_be_bytes,
+            _ => {
+                return Ok(match text.strip_prefix(b"\xef\xbb\xbf") {
+                    Some(rest) => rest.to_vec(),
+                    None => text,
+                })
+            }
+        };
+        let units = text[2..]
+            .chunks(2)
+            .map(|pair| match *pair {
+                [a, b] => Ok(from_units([a, b])),
+                _ => Err(()),
+            })
+            .collect::<Result<Vec<_>, ()>>();
+        units
+            .ok()
+            .and_then(|units| String::from_utf16(&units).ok())
+            .map(String::into_bytes)
+            .ok_or_else(|| {
+                IOError::new(
+                    std::io::ErrorKind::InvalidData,
+                    format!("{}: malformed UTF-16 text", path.display()),
+                )
+            })
+    }
+
+    // The preprocessor takes `#include` file names without string escapes
+    // other than `\\`, and never as wide strings. The name is written as
+    // UTF-8, which fragments are always included under.
+    fn write_include_path(w: &mut dyn Write, path: &std::path::Path) -> Result<(), IOError> {
+        let name = path
+            .to_str()
+            .filter(|name| !name.contains(|ch: char| ch == '"' || ch.is_control()))
+            .ok_or_else(|| {
+                IOError::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("can't #include {:?}", path),
+                )
+            })?;
+        write!(w, "\"{}\"", name.replace('\\', "\\\\"))
+    }
+
+    pub(crate) fn write_user_defined_file_resource(
+        w: &mut dyn Write,
+        lang: crate::Lang,
+        id_or_name: crate::IdOrName,
+        type_id: &crate::IdOrName,
+        path: &std::path::Path,
+    ) -> Result<(), IOError> {
+        write_user_defined_resource_header(w, lang, id_or_name, type_id)?;
+        write!(w, " ")?;
+        write_path(w, &resolve_path(path)?)?;
+        write!(w, "\n")?;
+        Ok(())
+    }
+
+    pub(crate) fn write_path_only_resource(
+        w: &mut dyn Write,
+        lang: crate::Lang,
+        id_or_name: crate::IdOrName,
+        res_type_keyword: &'static str,
+        path: &std::path::Path,
+    ) -> Result<(), IOError> {
+        write_resource_header(w, lang, id_or_name, res_type_keyword)?;
+        write!(w, " ")?;
+        write_path(w, &resolve_path(path)?)?;
+        write!(w, "\n")?;
+        Ok(())
+    }
+
+    pub(crate) fn write_user_defined_resource_header(
+        w: &mut dyn Write,
+        lang: crate::Lang,
+        id_or_name: crate::IdOrName,
+        type_id: &crate::IdOrName,
+    ) -> Result<(), IOError> {
+        write!(w, "LANGUAGE 0x{:x}, 0x{:x}\n", lang.0, lang.1)?;
+        write_id_or_name(w, &id_or_name)?;
+        write!(w, " ")?;
+        write_id_or_name(w, type_id)?;
+        Ok(())
+    }
+
+    fn write_depfile_path(w: &mut dyn Write, path: &std::path::Path) -> Result<(), IOError> {
+        for ch in path.to_string_lossy().chars() {
+            match ch {
+                ' ' | '#' => write!(w, "\\{}", ch)?,
+                '$' => write!(w, "$$")?,
+                _ => write!(w, "{}", ch)?,
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_depfile(
+        w: &mut dyn Write,
+        target: &std::path::Path,
+        dependencies: &[std::path::PathBuf],
+    ) -> Result<(), IOError> {
+        write_depfile_path(w, target)?;
+        write!(w, ":")?;
+        for dependency in dependencies {
+            write!(w, " \\\n  ")?;
+            write_depfile_path(w, dependency)?;
+        }
+        write!(w, "\n")?;
+        Ok(())
+    }
+
+    // One line of a data block, and the code page it has to be read in, if
+    // it matters.
+    pub(crate) struct DataLiteral {
+        text: Vec<u8>,
+        code_page: Option<UINT>,
+    }
+
+    impl DataLiteral {
+        pub(crate) fn new(text: Vec<u8>) -> Self {
+            DataLiteral {
+                text,
+                code_page: None,
+            }
+        }
+    }
+
+    const DATA_WORDS_PER_LINE: usize = 8;
+
+    // Raw bytes as little endian WORDs, as narrow strings would have llvm-rc
+    // reject bytes above 0x7f. An odd last byte can only be a string: above
+    // 0x7f it goes in one of its own, read in code page 1252, the only one
+    // llvm-rc takes single high bytes in.
+    pub(crate) fn push_data_literals(
+        literals: &mut Vec<DataLiteral>,
+        data: &[u8],
+    ) -> Result<(), IOError> {
+        let (words, last) = data.split_at(data.len() & !1);
+        for line in words.chunks(DATA_WORDS_PER_LINE * 2) {
+            let mut text = Vec::new();
+            for (idx, pair) in line.chunks(2).enumerate() {
+                if idx > 0 {
+                    write!(text, ", ")?;
+                }
+                write!(text, "0x{:04x}", u16::from_le_bytes([pair[0], pair[1]]))?;
+            }
+            literals.push(DataLiteral::new(text));
+        }
+        let byte = match last {
+            [byte] => *byte,
+            _ => return Ok(()),
+        };
+        let mut text = Vec::new();
+        write_narrow_bytes(&mut text, &[byte])?;
+        match literals.last_mut() {
+            Some(line) if byte.is_ascii() && line.code_page.is_none() && !words.is_empty() => {
+                write!(line.text, ", ")?;
+                line.text.extend(text);
+            }
+            _ => literals.push(DataLiteral {
+                text,
+                code_page: Some(1252).filter(|_| !byte.is_ascii()),
+            }),
+        }
+        Ok(())
+    }
+
+    // Writes `literals` one per line, separated by commas, switching the
+    // code page around those that need one.
+    pub(crate) fn write_data_literals(
+        w: &mut dyn Write,
+        literals: &[DataLiteral],
+    ) -> Result<(), IOError> {
+        for (idx, literal) in literals.iter().enumerate() {
+            let restore = current_code_page();
+            let code_page = literal.code_page.filter(|&c| c != restore);
+            if let Some(code_page) = code_page {
+                write_code_page_pragma(w, code_page)?;
+            }
+            write!(w, "\t")?;
+            w.write_all(&literal.text)?;
+            if idx + 1 < literals.len() {
+                write!(w, ",")?;
+            }
+            write!(w, "\n")?;
+            if code_page.is_some() {
+                write_code_page_pragma(w, restore)?;
+            }
+        }
+        Ok(())
+    }
 
-    pub(crate) fn write_dword(
-        w: &mut dyn Write,
-        dword: winapi::shared::minwindef::DWORD,
-    ) -> Result<(), IOError> {
-        write!(w, "{}L", dword)
+    pub(crate) fn write_raw_data_block(w: &mut dyn Write, data: &[u8]) -> Result<(), IOError> {
+        let mut literals = Vec::new();
+        push_data_literals(&mut literals, data)?;
+        write!(w, "{{\n")?;
+        write_data_literals(w, &literals)?;
+        write!(w, "}}\n")?;
+        Ok(())
     }
 
-    pub(crate) fn write_mandatory_dword(
+    fn control_variant_name(id: &Id, kind: &str) -> String {
+        let predefined = match id.0 as i32 {
+            crate::sys::winuser::IDOK => "Ok",
+            crate::sys::winuser::IDCANCEL => "Cancel",
+            crate::sys::winuser::IDABORT => "Abort",
+            crate::sys::winuser::IDRETRY => "Retry",
+            crate::sys::winuser::IDIGNORE => "Ignore",
+            crate::sys::winuser::IDYES => "Yes",
+            crate::sys::winuser::IDNO => "No",
+            crate::sys::winuser::IDCLOSE => "Close",
+            crate::sys::winuser::IDHELP => "Help",
+            crate::sys::winuser::IDTRYAGAIN => "TryAgain",
+            crate::sys::winuser::IDCONTINUE => "Continue",
+            _ => "",
+        };
+        if !predefined.is_empty() {
+            return predefined.to_owned();
+        }
+        let kind = match kind {
+            "AUTO3STATE" => "Auto3State",
+            "AUTOCHECKBOX" => "AutoCheckBox",
+            "AUTORADIOBUTTON" => "AutoRadioButton",
+            "CHECKBOX" => "CheckBox",
+            "COMBOBOX" => "ComboBox",
+            "CTEXT" => "CText",
+            "DEFPUSHBUTTON" => "DefPushButton",
+            "EDITTEXT" => "EditText",
+            "GROUPBOX" => "GroupBox",
+            "ICON" => "Icon",
+            "LISTBOX" => "ListBox",
+            "LTEXT" => "LText",
+            "PUSHBOX" => "PushBox",
+            "PUSHBUTTON" => "PushButton",
+            "RADIOBUTTON" => "RadioButton",
+            "RTEXT" => "RText",
+            "SCROLLBAR" => "ScrollBar",
+            "STATE3" => "State3",
+            _ => "Control",
+        };
+        format!("{}{}", kind, id)
+    }
+
+    fn snake_case(name: &str) -> String {
+        let mut r = String::new();
+        for (idx, ch) in name.chars().enumerate() {
+            if ch.is_ascii_uppercase() {
+                if idx != 0 {
+                    r.push('_');
+                }
+                r.push(ch.to_ascii_lowercase());
+            } else {
+                r.push(ch);
+            }
+        }
+        r
+    }
+
+    pub(crate) fn write_dialog_control_module(
         w: &mut dyn Write,
-        dword: Option<&winapi::shared::minwindef::DWORD>,
+        name: &str,
+        controls: &[(Id, &'static str)],
+        dialog_proc: bool,
     ) -> Result<(), IOError> {
-        write_dword(w, dword.cloned().unwrap())
+        write!(
+            w,
+            "// Dialog controls automatically generated by RESW-RS.\n"
+        )?;
+        write!(w, "// Do not edit this file manually.\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "#[derive(Clone, Copy, Debug, PartialEq, Eq)]\n")?;
+        // a zero-variant enum can't have a primitive representation
+        if !controls.is_empty() {
+            write!(w, "#[repr(u16)]\n")?;
+        }
+        write!(w, "pub enum {}Control {{\n", name)?;
+        for (id, kind) in controls {
+            write!(w, "    {} = {},\n", control_variant_name(id, kind), id)?;
+        }
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "impl {}Control {{\n", name)?;
+        write!(w, "    pub fn from_id(id: u16) -> Option<Self> {{\n")?;
+        write!(w, "        match id {{\n")?;
+        for (id, kind) in controls {
+            write!(
+                w,
+                "            {} => Some({}Control::{}),\n",
+                id,
+                name,
+                control_variant_name(id, kind)
+            )?;
+        }
+        write!(w, "            _ => None,\n")?;
+        write!(w, "        }}\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(w, "    pub fn id(self) -> u16 {{\n")?;
+        if controls.is_empty() {
+            write!(w, "        match self {{}}\n")?;
+        } else {
+            write!(w, "        self as u16\n")?;
+        }
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        if !dialog_proc {
+            return Ok(());
+        }
+        let proc_name = snake_case(name);
+        write!(w, "\n")?;
+        write!(
+            w,
+            "// Return true from a handler when the message was processed.\n"
+        )?;
+        write!(w, "#[allow(unused_variables)]\n")?;
+        write!(w, "pub trait {}Handler {{\n", name)?;
+        write!(
+            w,
+            "    fn init_dialog(hwnd: *mut core::ffi::c_void, param: isize) -> bool {{\n"
+        )?;
+        write!(w, "        true\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(w, "    fn command(hwnd: *mut core::ffi::c_void, control: {}Control, notify_code: u16) -> bool {{\n", name)?;
+        write!(w, "        false\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(w, "    fn notify(hwnd: *mut core::ffi::c_void, control: {}Control, code: u32, header: *const core::ffi::c_void) -> bool {{\n", name)?;
+        write!(w, "        false\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "// Pass `Some({}_proc::<Handler>)` to CreateDialogParamW or DialogBoxParamW.\n",
+            proc_name
+        )?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(
+            w,
+            "pub extern \"system\" fn {}_proc<H: {}Handler>(\n",
+            proc_name, name
+        )?;
+        write!(w, "    hwnd: *mut core::ffi::c_void,\n")?;
+        write!(w, "    msg: u32,\n")?;
+        write!(w, "    wparam: usize,\n")?;
+        write!(w, "    lparam: isize,\n")?;
+        write!(w, ") -> isize {{\n")?;
+        write!(w, "    #[allow(dead_code)]\n")?;
+        write!(w, "    #[repr(C)]\n")?;
+        write!(w, "    struct NMHDR {{\n")?;
+        write!(w, "        hwnd_from: *mut core::ffi::c_void,\n")?;
+        write!(w, "        id_from: usize,\n")?;
+        write!(w, "        code: u32,\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "    const WM_INITDIALOG: u32 = 0x0110;\n")?;
+        write!(w, "    const WM_COMMAND: u32 = 0x0111;\n")?;
+        write!(w, "    const WM_NOTIFY: u32 = 0x004E;\n")?;
+        write!(w, "    let handled = match msg {{\n")?;
+        write!(
+            w,
+            "        WM_INITDIALOG => H::init_dialog(hwnd, lparam),\n"
+        )?;
+        write!(
+            w,
+            "        WM_COMMAND => match {}Control::from_id(wparam as u16) {{\n",
+            name
+        )?;
+        write!(
+            w,
+            "            Some(control) => H::command(hwnd, control, (wparam >> 16) as u16),\n"
+        )?;
+        write!(w, "            None => false,\n")?;
+        write!(w, "        }},\n")?;
+        write!(w, "        WM_NOTIFY => {{\n")?;
+        write!(
+            w,
+            "            let header = unsafe {{ &*(lparam as *const NMHDR) }};\n"
+        )?;
+        write!(
+            w,
+            "            match {}Control::from_id(header.id_from as u16) {{\n",
+            name
+        )?;
+        write!(w, "                Some(control) => H::notify(hwnd, control, header.code, lparam as *const core::ffi::c_void),\n")?;
+        write!(w, "                None => false,\n")?;
+        write!(w, "            }}\n")?;
+        write!(w, "        }}\n")?;
+        write!(w, "        _ => false,\n")?;
+        write!(w, "    }};\n")?;
+        write!(w, "    handled as isize\n")?;
+        write!(w, "}}\n")?;
+        Ok(())
     }
 
-    pub(crate) fn write_rect(w: &mut dyn Write, rect: &crate::Rect) -> Result<(), IOError> {
-        write_c_int(w, rect.x)?;
-        write!(w, ", ")?;
-        write_c_int(w, rect.y)?;
-        write!(w, ", ")?;
-        write_c_int(w, rect.width)?;
-        write!(w, ", ")?;
-        write_c_int(w, rect.height)?;
-        Ok(())
+    // `IDS_FILE_NOT_FOUND` and `FILE_NOT_FOUND` become `FileNotFound`; a
+    // symbol with lowercase letters and no underscore is taken as is.
+    fn string_key_variant(symbol: &str) -> Result<String, IOError> {
+        let stripped = symbol.strip_prefix("IDS_").unwrap_or(symbol);
+        let variant =
+            if stripped.contains('_') || !stripped.chars().any(|ch| ch.is_ascii_lowercase()) {
+                let mut r = String::new();
+                for word in stripped.split('_').filter(|word| !word.is_empty()) {
+                    let mut chars = word.chars();
+                    r.extend(chars.next().map(|ch| ch.to_ascii_uppercase()));
+                    r.extend(chars.map(|ch| ch.to_ascii_lowercase()));
+                }
+                r
+            } else {
+                stripped.to_owned()
+            };
+        let valid = variant.starts_with(|ch: char| ch.is_ascii_alphabetic())
+            && variant
+                .chars()
+                .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+        if !valid {
+            return Err(IOError::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "string symbol `{}` can't be turned into a Rust name",
+                    symbol
+                ),
+            ));
+        }
+        Ok(variant)
     }
 
-    pub(crate) fn write_mandatory_rect(
+    pub(crate) fn write_string_keys_module(
         w: &mut dyn Write,
-        rect: Option<&crate::Rect>,
+        name: &str,
+        keys: &[(Id, Option<&str>)],
     ) -> Result<(), IOError> {
-        if let Some(rect) = rect {
-            write_rect(w, rect)
+        let mut variants = Vec::with_capacity(keys.len());
+        for (id, symbol) in keys {
+            let variant = match symbol {
+                Some(symbol) => string_key_variant(symbol)?,
+                None => format!("String{}", id),
+            };
+            if let Some((other, _, _)) = variants.iter().find(|(_, v, _)| *v == variant) {
+                return Err(IOError::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "strings {} and {} would both be named {}::{}",
+                        other, id, name, variant
+                    ),
+                ));
+            }
+            variants.push((id, variant, symbol.map(str::to_owned)));
+        }
+        write!(w, "// String keys automatically generated by RESW-RS.\n")?;
+        write!(w, "// Do not edit this file manually.\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]\n")?;
+        if !variants.is_empty() {
+            write!(w, "#[repr(u16)]\n")?;
+        }
+        write!(w, "pub enum {} {{\n", name)?;
+        for (id, variant, _) in variants.iter() {
+            write!(w, "    {} = {},\n", variant, id)?;
+        }
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "impl {} {{\n", name)?;
+        write!(w, "    pub const ALL: &'static [{}] = &[\n", name)?;
+        for (_, variant, _) in variants.iter() {
+            write!(w, "        {}::{},\n", name, variant)?;
+        }
+        write!(w, "    ];\n")?;
+        write!(w, "\n")?;
+        write!(w, "    // The id to pass to LoadStringW.\n")?;
+        write!(w, "    pub fn id(self) -> u16 {{\n")?;
+        if variants.is_empty() {
+            write!(w, "        match self {{}}\n")?;
         } else {
-            write_rect(w, &crate::Rect::default())
+            write!(w, "        self as u16\n")?;
+        }
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "    // The symbol the string was declared with, or its id.\n"
+        )?;
+        write!(w, "    pub fn symbol(self) -> &'static str {{\n")?;
+        write!(w, "        match self {{\n")?;
+        for (id, variant, symbol) in variants.iter() {
+            let symbol = symbol.clone().unwrap_or_else(|| id.to_string());
+            write!(w, "            {}::{} => {:?},\n", name, variant, symbol)?;
         }
+        write!(w, "        }}\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "impl From<{}> for u16 {{\n", name)?;
+        write!(w, "    fn from(key: {}) -> u16 {{\n", name)?;
+        write!(w, "        key.id()\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "impl From<{}> for u32 {{\n", name)?;
+        write!(w, "    fn from(key: {}) -> u32 {{\n", name)?;
+        write!(w, "        key.id() as u32\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "impl core::fmt::Display for {} {{\n", name)?;
+        write!(
+            w,
+            "    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {{\n"
+        )?;
+        write!(w, "        f.write_str(self.symbol())\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        Ok(())
     }
 
-    pub(crate) fn write_font(w: &mut dyn Write, font: &crate::Font) -> Result<(), IOError> {
-        write_c_int(w, font.size.0)?;
-        write!(w, ", ")?;
-        write_narrow_str(w, &font.typeface)?;
-        write!(w, ", ")?;
-        write_c_long(w, font.weight.0)?;
-        write!(w, ", ")?;
-        write_c_int(w, font.italic.0)?;
-        write!(w, ", ")?;
-        write_c_uchar(w, font.charset.0)?;
+    // The form offset CLDR's cardinal rules pick for a whole number, by
+    // primary language.
+    const PLURAL_RULES: &[&str] = &[
+        "    pub fn form_offset(lang_id: u16, n: u64) -> u16 {",
+        "        let (n10, n100) = (n % 10, n % 100);",
+        "        match lang_id & 0x3ff {",
+        "            // Chinese, Indonesian, Japanese, Korean, Malay, Thai, Vietnamese",
+        "            0x04 | 0x21 | 0x11 | 0x12 | 0x3e | 0x1e | 0x2a => 5,",
+        "            // French, Portuguese outside of Portugal",
+        "            0x0c => if n <= 1 { 1 } else { 5 },",
+        "            0x16 if lang_id != 0x0816 => if n <= 1 { 1 } else { 5 },",
+        "            // Russian, Ukrainian, Belarusian",
+        "            0x19 | 0x22 | 0x23 => {",
+        "                if n10 == 1 && n100 != 11 {",
+        "                    1",
+        "                } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {",
+        "                    3",
+        "                } else {",
+        "                    4",
+        "                }",
+        "            }",
+        "            // Croatian, Serbian, Bosnian",
+        "            0x1a => {",
+        "                if n10 == 1 && n100 != 11 {",
+        "                    1",
+        "                } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {",
+        "                    3",
+        "                } else {",
+        "                    5",
+        "                }",
+        "            }",
+        "            // Polish",
+        "            0x15 => {",
+        "                if n == 1 {",
+        "                    1",
+        "                } else if (2..=4).contains(&n10) && !(12..=14).contains(&n100) {",
+        "                    3",
+        "                } else {",
+        "                    4",
+        "                }",
+        "            }",
+        "            // Czech, Slovak",
+        "            0x05 | 0x1b => match n {",
+        "                1 => 1,",
+        "                2..=4 => 3,",
+        "                _ => 5,",
+        "            },",
+        "            // Lithuanian",
+        "            0x27 => {",
+        "                if (11..=19).contains(&n100) {",
+        "                    5",
+        "                } else if n10 == 1 {",
+        "                    1",
+        "                } else if n10 >= 2 {",
+        "                    3",
+        "                } else {",
+        "                    5",
+        "                }",
+        "            }",
+        "            // Latvian",
+        "            0x26 => {",
+        "                if n10 == 0 || (11..=19).contains(&n100) {",
+        "                    0",
+        "                } else if n10 == 1 {",
+        "                    1",
+        "                } else {",
+        "                    5",
+        "                }",
+        "            }",
+        "            // Romanian",
+        "            0x18 => {",
+        "                if n == 1 {",
+        "                    1",
+        "                } else if n == 0 || (1..=19).contains(&n100) {",
+        "                    3",
+        "                } else {",
+        "                    5",
+        "                }",
+        "            }",
+        "            // Slovenian",
+        "            0x24 => match n100 {",
+        "                1 => 1,",
+        "                2 => 2,",
+        "                3 | 4 => 3,",
+        "                _ => 5,",
+        "            },",
+        "            // Irish",
+        "            0x3c => match n {",
+        "                1 => 1,",
+        "                2 => 2,",
+        "                3..=6 => 3,",
+        "                7..=10 => 4,",
+        "                _ => 5,",
+        "            },",
+        "            // Arabic",
+        "            0x01 => match (n, n100) {",
+        "                (0, _) => 0,",
+        "                (1, _) => 1,",
+        "                (2, _) => 2,",
+        "                (_, 3..=10) => 3,",
+        "                (_, 11..=99) => 4,",
+        "                _ => 5,",
+        "            },",
+        "            // Hebrew",
+        "            0x0d => match n {",
+        "                1 => 1,",
+        "                2 => 2,",
+        "                _ => 5,",
+        "            },",
+        "            // Welsh",
+        "            0x52 => match n {",
+        "                0 => 0,",
+        "                1 => 1,",
+        "                2 => 2,",
+        "                3 => 3,",
+        "                6 => 4,",
+        "                _ => 5,",
+        "            },",
+        "            // Icelandic, Macedonian",
+        "            0x0f | 0x2f => {",
+        "                if n10 == 1 && n100 != 11 {",
+        "                    1",
+        "                } else {",
+        "                    5",
+        "                }",
+        "            }",
+        "            _ => if n == 1 { 1 } else { 5 },",
+        "        }",
+        "    }",
+    ];
+
+    // The StringFileInfo block of a VERSIONINFO, with one block of values per
+    // language, keyed by its LANGID and the Unicode code page.
+    pub(crate) fn write_string_file_info(
+        w: &mut dyn Write,
+        blocks: &[(crate::Lang, Vec<(&str, &str)>)],
+    ) -> Result<(), IOError> {
+        write!(w, "\tBLOCK \"StringFileInfo\"\n")?;
+        write!(w, "\t{{\n")?;
+        for (lang, values) in blocks {
+            write!(w, "\t\tBLOCK \"{:04x}04b0\"\n", lang.lang_id())?;
+            write!(w, "\t\t{{\n")?;
+            for (key, value) in values {
+                write!(w, "\t\t\tVALUE \"{}\", ", key)?;
+                write_text(w, value)?;
+                write!(w, "\n")?;
+            }
+            write!(w, "\t\t}}\n")?;
+        }
+        write!(w, "\t}}\n")?;
         Ok(())
     }
 
-    pub(crate) fn need_escape_narrow_byte(v: &u8) -> bool {
-        match v {
-            0..=31u8 => true,
-            b'\\' => true,
-            b'\"' => true,
-            127u8 => true,
-            _ => false,
+    // Tells which languages the StringFileInfo blocks are for, each in the
+    // Unicode code page.
+    pub(crate) fn write_var_file_info(
+        w: &mut dyn Write,
+        langs: &[crate::Lang],
+    ) -> Result<(), IOError> {
+        write!(w, "\tBLOCK \"VarFileInfo\"\n")?;
+        write!(w, "\t{{\n")?;
+        write!(w, "\t\tVALUE \"Translation\"")?;
+        for lang in langs {
+            write!(w, ", 0x{:x}, 1200", lang.lang_id())?;
         }
+        write!(w, "\n")?;
+        write!(w, "\t}}\n")?;
+        Ok(())
     }
 
-    pub(crate) fn need_escape_wide_u16(v: &u16) -> bool {
-        match v {
-            0..=31u16 => true,
-            92u16 /*b'\\'*/ => true,
-            34u16 /*b'\"'*/ => true,
-            127u16 => true,
-            32u16..=126u16 => false,
-            _ => true,
+    pub(crate) fn write_plural_module(
+        w: &mut dyn Write,
+        name: &str,
+        base_ids: &[Id],
+    ) -> Result<(), IOError> {
+        write!(w, "// Plural strings automatically generated by RESW-RS.\n")?;
+        write!(w, "// Do not edit this file manually.\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]\n")?;
+        if !base_ids.is_empty() {
+            write!(w, "#[repr(u16)]\n")?;
+        }
+        write!(w, "pub enum {} {{\n", name)?;
+        for id in base_ids {
+            write!(w, "    String{} = {},\n", id, id)?;
+        }
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "impl {} {{\n", name)?;
+        write!(w, "    pub const ALL: &'static [{}] = &[\n", name)?;
+        for id in base_ids {
+            write!(w, "        {}::String{},\n", name, id)?;
+        }
+        write!(w, "    ];\n")?;
+        write!(w, "\n")?;
+        write!(w, "    pub fn base_id(self) -> u16 {{\n")?;
+        if base_ids.is_empty() {
+            write!(w, "        match self {{}}\n")?;
+        } else {
+            write!(w, "        self as u16\n")?;
+        }
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "    // The id of the form for `n` under the plural rules of `lang_id`.\n"
+        )?;
+        write!(
+            w,
+            "    pub fn id_for(self, lang_id: u16, n: u64) -> u16 {{\n"
+        )?;
+        write!(
+            w,
+            "        self.base_id() + Self::form_offset(lang_id, n)\n"
+        )?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "    // The form for `n` in the thread's UI language, or the Other form\n"
+        )?;
+        write!(w, "    // if the language lacks the one its rules pick.\n")?;
+        write!(w, "    pub fn load(self, n: u64) -> String {{\n")?;
+        write!(w, "        #[link(name = \"kernel32\")]\n")?;
+        write!(w, "        extern \"system\" {{\n")?;
+        write!(w, "            fn GetThreadUILanguage() -> u16;\n")?;
+        write!(w, "        }}\n")?;
+        write!(
+            w,
+            "        let lang_id = unsafe {{ GetThreadUILanguage() }};\n"
+        )?;
+        write!(w, "        Self::load_string(self.id_for(lang_id, n))\n")?;
+        write!(
+            w,
+            "            .or_else(|| Self::load_string(self.base_id() + 5))\n"
+        )?;
+        write!(w, "            .unwrap_or_default()\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(w, "    fn load_string(id: u16) -> Option<String> {{\n")?;
+        write!(w, "        #[link(name = \"user32\")]\n")?;
+        write!(w, "        extern \"system\" {{\n")?;
+        write!(w, "            fn LoadStringW(instance: *mut core::ffi::c_void, id: u32, buffer: *mut u16, len: i32) -> i32;\n")?;
+        write!(w, "        }}\n")?;
+        write!(w, "        extern \"C\" {{\n")?;
+        write!(w, "            static __ImageBase: u8;\n")?;
+        write!(w, "        }}\n")?;
+        write!(w, "        let mut text: *const u16 = core::ptr::null();\n")?;
+        write!(
+            w,
+            "        // a length of 0 asks for a pointer to the string in the resource\n"
+        )?;
+        write!(w, "        let len = unsafe {{\n")?;
+        write!(
+            w,
+            "            let module = &__ImageBase as *const u8 as *mut core::ffi::c_void;\n"
+        )?;
+        write!(w, "            LoadStringW(module, id as u32, &mut text as *mut *const u16 as *mut u16, 0)\n")?;
+        write!(w, "        }};\n")?;
+        write!(w, "        if len <= 0 {{\n")?;
+        write!(w, "            return None;\n")?;
+        write!(w, "        }}\n")?;
+        write!(
+            w,
+            "        let text = unsafe {{ core::slice::from_raw_parts(text, len as usize) }};\n"
+        )?;
+        write!(w, "        Some(String::from_utf16_lossy(text))\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "    // 0 for Zero, 1 One, 2 Two, 3 Few, 4 Many and 5 for Other.\n"
+        )?;
+        for line in PLURAL_RULES {
+            write!(w, "{}\n", line)?;
         }
+        write!(w, "}}\n")?;
+        Ok(())
     }
 
-    pub(crate) fn write_mandatory_narrow_str(
+    pub(crate) fn write_accelerators_runtime_module(
         w: &mut dyn Write,
-        string: Option<&CowStr>,
+        name: &str,
+        id_or_name: &IdOrName,
+        ids: &[Id],
     ) -> Result<(), IOError> {
-        if let Some(string) = string {
-            write_narrow_str(w, string)
+        write!(
+            w,
+            "// Accelerator glue automatically generated by RESW-RS.\n"
+        )?;
+        write!(w, "// Do not edit this file manually.\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "#[derive(Clone, Copy, Debug, PartialEq, Eq)]\n")?;
+        if !ids.is_empty() {
+            write!(w, "#[repr(u16)]\n")?;
+        }
+        write!(w, "pub enum {}Command {{\n", name)?;
+        for id in ids {
+            write!(w, "    Command{} = {},\n", id, id)?;
+        }
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "impl {}Command {{\n", name)?;
+        write!(w, "    pub fn from_id(id: u16) -> Option<Self> {{\n")?;
+        write!(w, "        match id {{\n")?;
+        for id in ids {
+            write!(
+                w,
+                "            {} => Some({}Command::Command{}),\n",
+                id, name, id
+            )?;
+        }
+        write!(w, "            _ => None,\n")?;
+        write!(w, "        }}\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "    // The command a WM_COMMAND sent by an accelerator or menu item refers to.\n"
+        )?;
+        write!(
+            w,
+            "    pub fn from_wm_command(wparam: usize) -> Option<Self> {{\n"
+        )?;
+        write!(w, "        match (wparam >> 16) as u16 {{\n")?;
+        write!(w, "            0 | 1 => Self::from_id(wparam as u16),\n")?;
+        write!(w, "            _ => None,\n")?;
+        write!(w, "        }}\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(w, "    pub fn id(self) -> u16 {{\n")?;
+        if ids.is_empty() {
+            write!(w, "        match self {{}}\n")?;
         } else {
-            write_narrow_str(w, &CowStr::from(""))
+            write!(w, "        self as u16\n")?;
+        }
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "pub struct {}Accelerators(*mut core::ffi::c_void);\n",
+            name
+        )?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "impl {}Accelerators {{\n", name)?;
+        write!(w, "    pub fn load() -> Option<Self> {{\n")?;
+        write!(w, "        #[allow(non_snake_case)]\n")?;
+        write!(w, "        #[link(name = \"user32\")]\n")?;
+        write!(w, "        extern \"system\" {{\n")?;
+        write!(w, "            fn LoadAcceleratorsW(module: *mut core::ffi::c_void, name: *const u16) -> *mut core::ffi::c_void;\n")?;
+        write!(w, "        }}\n")?;
+        write!(w, "        extern \"C\" {{\n")?;
+        write!(w, "            static __ImageBase: u8;\n")?;
+        write!(w, "        }}\n")?;
+        match id_or_name {
+            IdOrName::Id(id) => {
+                write!(w, "        let name = {} as usize as *const u16;\n", id)?;
+            }
+            IdOrName::Name(resource_name) => {
+                write!(w, "        let name: &[u16] = &[")?;
+                for unit in resource_name.encode_utf16() {
+                    write!(w, "{}, ", unit)?;
+                }
+                write!(w, "0];\n")?;
+                write!(w, "        let name = name.as_ptr();\n")?;
+            }
         }
+        write!(w, "        let module = unsafe {{ &__ImageBase as *const u8 as *mut core::ffi::c_void }};\n")?;
+        write!(
+            w,
+            "        let table = unsafe {{ LoadAcceleratorsW(module, name) }};\n"
+        )?;
+        write!(w, "        if table.is_null() {{\n")?;
+        write!(w, "            None\n")?;
+        write!(w, "        }} else {{\n")?;
+        write!(w, "            Some({}Accelerators(table))\n", name)?;
+        write!(w, "        }}\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "    // Call for every message from GetMessageW, before TranslateMessage; when\n"
+        )?;
+        write!(
+            w,
+            "    // it returns true the message became a WM_COMMAND to `hwnd` and must not\n"
+        )?;
+        write!(
+            w,
+            "    // be dispatched again. `msg` points to the MSG structure.\n"
+        )?;
+        write!(w, "    pub unsafe fn translate(&self, hwnd: *mut core::ffi::c_void, msg: *mut core::ffi::c_void) -> bool {{\n")?;
+        write!(w, "        #[allow(non_snake_case)]\n")?;
+        write!(w, "        #[link(name = \"user32\")]\n")?;
+        write!(w, "        extern \"system\" {{\n")?;
+        write!(w, "            fn TranslateAcceleratorW(hwnd: *mut core::ffi::c_void, table: *mut core::ffi::c_void, msg: *mut core::ffi::c_void) -> i32;\n")?;
+        write!(w, "        }}\n")?;
+        write!(w, "        TranslateAcceleratorW(hwnd, self.0, msg) != 0\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "\n")?;
+        write!(w, "    pub fn handle(&self) -> *mut core::ffi::c_void {{\n")?;
+        write!(w, "        self.0\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        Ok(())
     }
 
-    pub(crate) fn write_narrow_str(w: &mut dyn Write, string: &CowStr) -> Result<(), IOError> {
-        write!(w, "\"")?;
-        let mut rest_string = string.as_bytes();
-        while !rest_string.is_empty() {
-            let seq = rest_string
-                .split(need_escape_narrow_byte)
-                .next()
-                .expect("unreachable");
-            if !seq.is_empty() {
-                w.write_all(seq)?;
-                rest_string = &rest_string[seq.len()..];
-            } else {
-                write!(w, "\\{:03o}", rest_string[0])?;
-                rest_string = &rest_string[1..];
+    pub(crate) fn write_format_message_module(
+        w: &mut dyn Write,
+        messages: &[(
+            &crate::message_compiler::McMessage,
+            std::collections::BTreeMap<u8, crate::message_compiler::InsertType>,
+        )],
+    ) -> Result<(), IOError> {
+        use crate::message_compiler::InsertType;
+        write!(
+            w,
+            "// Message formatting automatically generated by RESW-RS.\n"
+        )?;
+        write!(w, "// Do not edit this file manually.\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(
+            w,
+            "fn format_message(message_id: u32, args: &[usize]) -> Option<String> {{\n"
+        )?;
+        write!(w, "    #[allow(non_snake_case)]\n")?;
+        write!(w, "    #[link(name = \"kernel32\")]\n")?;
+        write!(w, "    extern \"system\" {{\n")?;
+        write!(w, "        fn FormatMessageW(flags: u32, source: *const core::ffi::c_void, message_id: u32, language_id: u32, buffer: *mut u16, size: u32, arguments: *const usize) -> u32;\n")?;
+        write!(
+            w,
+            "        fn LocalFree(mem: *mut core::ffi::c_void) -> *mut core::ffi::c_void;\n"
+        )?;
+        write!(w, "    }}\n")?;
+        write!(w, "    extern \"C\" {{\n")?;
+        write!(w, "        static __ImageBase: u8;\n")?;
+        write!(w, "    }}\n")?;
+        write!(
+            w,
+            "    const FORMAT_MESSAGE_ALLOCATE_BUFFER: u32 = 0x0100;\n"
+        )?;
+        write!(w, "    const FORMAT_MESSAGE_FROM_HMODULE: u32 = 0x0800;\n")?;
+        write!(
+            w,
+            "    const FORMAT_MESSAGE_ARGUMENT_ARRAY: u32 = 0x2000;\n"
+        )?;
+        write!(w, "    let mut buffer: *mut u16 = core::ptr::null_mut();\n")?;
+        write!(w, "    let len = unsafe {{\n")?;
+        write!(w, "        FormatMessageW(\n")?;
+        write!(w, "            FORMAT_MESSAGE_ALLOCATE_BUFFER | FORMAT_MESSAGE_FROM_HMODULE | FORMAT_MESSAGE_ARGUMENT_ARRAY,\n")?;
+        write!(
+            w,
+            "            &__ImageBase as *const u8 as *const core::ffi::c_void,\n"
+        )?;
+        write!(w, "            message_id,\n")?;
+        write!(w, "            0,\n")?;
+        write!(w, "            &mut buffer as *mut *mut u16 as *mut u16,\n")?;
+        write!(w, "            0,\n")?;
+        write!(w, "            args.as_ptr(),\n")?;
+        write!(w, "        )\n")?;
+        write!(w, "    }};\n")?;
+        write!(w, "    if len == 0 || buffer.is_null() {{\n")?;
+        write!(w, "        return None;\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "    let text = String::from_utf16_lossy(unsafe {{ core::slice::from_raw_parts(buffer, len as usize) }});\n")?;
+        write!(
+            w,
+            "    unsafe {{ LocalFree(buffer as *mut core::ffi::c_void) }};\n"
+        )?;
+        write!(w, "    Some(text)\n")?;
+        write!(w, "}}\n")?;
+        // `format_message` is taken by the helper above
+        let mut claimed = HashMap::new();
+        claimed.insert("message".to_owned(), "the FormatMessageW helper".to_owned());
+        for (message, inserts) in messages {
+            let function_name = match &message.symbolic_name {
+                Some(name) => name.to_ascii_lowercase(),
+                None => format!("message_{:08x}", message.message_id()),
+            };
+            let description = match &message.symbolic_name {
+                Some(name) => format!("message {}", name),
+                None => format!("message 0x{:08X}", message.message_id()),
+            };
+            // the `format_` prefix keeps keywords out, so any non-empty run
+            // of ASCII letters, digits and underscores is a valid name
+            let valid = !function_name.is_empty()
+                && function_name
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+            if !valid {
+                return Err(IOError::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!("{} can't be turned into a Rust function name", description),
+                ));
             }
-        }
-        write!(w, "\"")?;
-        Ok(())
-    }
-
-    #[cfg(windows)]
-    fn write_wide_os_str(w: &mut dyn Write, name: &std::ffi::OsStr) -> Result<(), IOError> {
-        use std::os::windows::ffi::OsStrExt;
-        write!(w, "L\"")?;
-        for ch in name.encode_wide() {
-            if ch == b'\\' as _ {
-                // for compatiblity with WINDRES.EXE, which doesn't seem
-                // supporting 4 byte escaped character at all.
-                write!(w, "\\\\")?;
-            } else if !need_escape_wide_u16(&ch) {
-                debug_assert!(ch <= std::u8::MAX as _);
-                let ch: [u8; 1] = [ch as u8];
-                w.write_all(&ch)?;
-            } else {
-                write!(w, "\\x{:04x}", ch)?;
+            if let Some(other) = claimed.get(&function_name) {
+                return Err(IOError::new(
+                    std::io::ErrorKind::InvalidInput,
+                    format!(
+                        "{} and {} would both be named format_{}",
+                        other, description, function_name
+                    ),
+                ));
             }
-        }
-        write!(w, "\"")?;
-        Ok(())
-    }
-
-    #[cfg(windows)]
-    fn write_os_str_prefer_narrow(
-        w: &mut dyn Write,
-        name: &std::ffi::OsStr,
-    ) -> Result<(), IOError> {
-        use std::os::windows::ffi::OsStrExt;
-        let mut narrow_chars = String::new();
-        let mut narrow = true;
-        'check: for ch in name.encode_wide() {
-            if ch <= std::u8::MAX as _ && (ch as u8).is_ascii() {
-                narrow_chars.push(ch as u8 as char);
-            } else {
-                narrow = false;
-                break 'check;
+            claimed.insert(function_name.clone(), description);
+            let arg_count = inserts.keys().next_back().cloned().unwrap_or(0);
+            write!(w, "\n")?;
+            write!(w, "#[allow(dead_code)]\n")?;
+            write!(w, "pub fn format_{}(", function_name)?;
+            for (idx, (index, ty)) in inserts.iter().enumerate() {
+                if idx != 0 {
+                    write!(w, ", ")?;
+                }
+                let rust_type = match ty {
+                    InsertType::Str => "&str",
+                    InsertType::Int => "i32",
+                    InsertType::UInt => "u32",
+                    InsertType::Char => "char",
+                    InsertType::Pointer => "*const core::ffi::c_void",
+                };
+                write!(w, "arg{}: {}", index, rust_type)?;
             }
+            write!(w, ") -> Option<String> {{\n")?;
+            for (index, ty) in inserts.iter() {
+                if *ty == InsertType::Str {
+                    write!(
+                        w,
+                        "    let arg{}: Vec<u16> = arg{}.encode_utf16().chain(Some(0)).collect();\n",
+                        index, index
+                    )?;
+                }
+            }
+            write!(w, "    let args: [usize; {}] = [", arg_count)?;
+            for index in 1..=arg_count {
+                if index != 1 {
+                    write!(w, ", ")?;
+                }
+                match inserts.get(&index) {
+                    Some(InsertType::Str) => write!(w, "arg{}.as_ptr() as usize", index)?,
+                    Some(InsertType::Int) => write!(w, "arg{} as isize as usize", index)?,
+                    Some(InsertType::UInt) | Some(InsertType::Pointer) => {
+                        write!(w, "arg{} as usize", index)?
+                    }
+                    Some(InsertType::Char) => write!(w, "arg{} as u32 as usize", index)?,
+                    // unused slots still take a place in the argument array
+                    None => write!(w, "0")?,
+                }
+            }
+            write!(w, "];\n")?;
+            write!(
+                w,
+                "    format_message(0x{:08X}, &args)\n",
+                message.message_id()
+            )?;
+            write!(w, "}}\n")?;
         }
-        if narrow {
-            write_narrow_str(w, &CowStr::Owned(narrow_chars))
-        } else {
-            write_wide_os_str(w, name)
-        }
+        Ok(())
     }
 
-    pub(crate) fn write_id(w: &mut dyn Write, id: &Id) -> Result<(), IOError> {
-        write!(w, "{}", id.0)
+    pub(crate) const RT_VERSION: WORD = 16;
+
+    // The RT_* type a resource statement compiles into; string tables are split
+    // into blocks and have no single id, so they are left out.
+    pub(crate) fn predefined_type_id(keyword: &str) -> Option<WORD> {
+        let type_id = match keyword {
+            "CURSOR" => 12, // RT_GROUP_CURSOR
+            "BITMAP" => 2,
+            "ICON" => 14, // RT_GROUP_ICON
+            "MENUEX" => 4,
+            "DIALOGEX" => 5,
+            "FONT" => 8,
+            "ACCELERATORS" => 9,
+            "RCDATA" => 10,
+            "MESSAGETABLE" => 11,
+            "VERSIONINFO" => RT_VERSION,
+            "HTML" => 23,
+            _ => return None,
+        };
+        Some(type_id)
     }
 
-    pub(crate) fn write_id_or_name(
+    pub(crate) fn write_resource_checks(
         w: &mut dyn Write,
-        id_or_name: &IdOrName,
+        entries: &[(crate::Lang, WORD, IdOrName)],
     ) -> Result<(), IOError> {
-        match id_or_name {
-            IdOrName::Id(id) => write!(w, "{}", id),
-            IdOrName::Name(name) => write_narrow_str(w, name),
+        write!(
+            w,
+            "// Resource checks automatically generated by RESW-RS.\n"
+        )?;
+        write!(w, "// Do not edit this file manually.\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(
+            w,
+            "pub fn verify_embedded_resources() -> Result<(), String> {{\n"
+        )?;
+        write!(w, "    #[allow(non_snake_case)]\n")?;
+        write!(w, "    #[link(name = \"kernel32\")]\n")?;
+        write!(w, "    extern \"system\" {{\n")?;
+        write!(w, "        fn FindResourceExW(module: *mut core::ffi::c_void, ty: *const u16, name: *const u16, lang: u16) -> *mut core::ffi::c_void;\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "    extern \"C\" {{\n")?;
+        write!(w, "        static __ImageBase: u8;\n")?;
+        write!(w, "    }}\n")?;
+        write!(
+            w,
+            "    let module = unsafe {{ &__ImageBase as *const u8 as *mut core::ffi::c_void }};\n"
+        )?;
+        write!(w, "    let checks: &[(u16, &[u16], u16, &str)] = &[\n")?;
+        for (lang, type_id, id_or_name) in entries {
+            write!(w, "        ({}, &[", type_id)?;
+            match id_or_name {
+                IdOrName::Id(id) => write!(w, "{}", id)?,
+                IdOrName::Name(name) => {
+                    for unit in name.encode_utf16() {
+                        write!(w, "{}, ", unit)?;
+                    }
+                    write!(w, "0")?;
+                }
+            }
+            write!(w, "], 0x{:04x}, \"", lang.lang_id())?;
+            match id_or_name {
+                IdOrName::Id(id) => write!(w, "{}", id)?,
+                IdOrName::Name(name) => write!(w, "{}", name.escape_default())?,
+            }
+            write!(w, "\"),\n")?;
         }
+        write!(w, "    ];\n")?;
+        write!(w, "    let mut missing = Vec::new();\n")?;
+        write!(w, "    for &(ty, name, lang, description) in checks {{\n")?;
+        write!(
+            w,
+            "        // a single element is an integer id, anything else a null terminated name\n"
+        )?;
+        write!(
+            w,
+            "        let name_ptr = if name.len() == 1 && name[0] != 0 {{\n"
+        )?;
+        write!(w, "            name[0] as usize as *const u16\n")?;
+        write!(w, "        }} else {{\n")?;
+        write!(w, "            name.as_ptr()\n")?;
+        write!(w, "        }};\n")?;
+        write!(w, "        let found = unsafe {{ FindResourceExW(module, ty as usize as *const u16, name_ptr, lang) }};\n")?;
+        write!(w, "        if found.is_null() {{\n")?;
+        write!(w, "            missing.push(format!(\"type {{}} name {{}} language 0x{{:04x}}\", ty, description, lang));\n")?;
+        write!(w, "        }}\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "    if missing.is_empty() {{\n")?;
+        write!(w, "        Ok(())\n")?;
+        write!(w, "    }} else {{\n")?;
+        write!(
+            w,
+            "        Err(format!(\"resources not found: {{}}\", missing.join(\", \")))\n"
+        )?;
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[cfg(test)]\n")?;
+        write!(w, "#[test]\n")?;
+        write!(w, "fn embedded_resources_resolve() {{\n")?;
+        write!(w, "    verify_embedded_resources().unwrap();\n")?;
+        write!(w, "}}\n")?;
+        Ok(())
     }
 
-    fn write_path(w: &mut dyn Write, path: &std::path::Path) -> Result<(), IOError> {
-        let os_str = path.as_os_str();
-        write_os_str_prefer_narrow(w, os_str)
-    }
+    // (script keyword, id type, loader function, load call)
+    const LOADER_KINDS: &[(&str, &str, &str, &str)] = &[
+        (
+            "ICON",
+            "IconId",
+            "load_icon",
+            "LoadIconW(module(), id.0.as_ptr())",
+        ),
+        (
+            "CURSOR",
+            "CursorId",
+            "load_cursor",
+            "LoadCursorW(module(), id.0.as_ptr())",
+        ),
+        (
+            "BITMAP",
+            "BitmapId",
+            "load_bitmap",
+            "LoadImageW(module(), id.0.as_ptr(), 0 /* IMAGE_BITMAP */, 0, 0, 0)",
+        ),
+        (
+            "MENUEX",
+            "MenuId",
+            "load_menu",
+            "LoadMenuW(module(), id.0.as_ptr())",
+        ),
+        (
+            "ACCELERATORS",
+            "AccelId",
+            "load_accelerators",
+            "LoadAcceleratorsW(module(), id.0.as_ptr())",
+        ),
+    ];
 
-    fn ensure_id_or_name_ignorable(id_or_name: &IdOrName) {
+    fn rust_const_name(id_or_name: &IdOrName) -> String {
         match id_or_name {
-            &IdOrName::Id(Id(v)) => {
-                if v == 0 || v == (-1 as _) {
-                    return;
+            IdOrName::Id(id) => format!("ID_{}", id),
+            IdOrName::Name(name) => {
+                let mut const_name = String::new();
+                if name.is_empty() || name.starts_with(|ch: char| ch.is_ascii_digit()) {
+                    const_name.push('_');
                 }
-            }
-            IdOrName::Name(s) => {
-                if s == "" || s == " " || s == "_" {
-                    return;
+                for ch in name.chars() {
+                    if ch.is_ascii_alphanumeric() {
+                        const_name.push(ch.to_ascii_uppercase());
+                    } else {
+                        const_name.push('_');
+                    }
                 }
+                const_name
             }
         }
-        eprintln!(
-            "Warning: Expected ignorable id or name, found {:?}. Ignored.",
-            id_or_name
-        );
     }
 
-    pub(crate) fn write_extra_info(
-        w: &mut dyn Write,
-        extra_info: Option<&crate::ExtraInfo>,
-    ) -> Result<(), IOError> {
-        if let Some(extra_info) = extra_info {
-            if let Some(characteristics) = &extra_info.characteristics {
-                write!(w, " ")?;
-                write_dword(w, *characteristics)?;
-            }
-            if let Some(version) = &extra_info.version {
-                write!(w, " ")?;
-                write_dword(w, *version)?;
-            }
+    // Different names can come out as the same constant, e.g. "a-b" and "A_B".
+    fn claim_rust_const_name(
+        claimed: &mut HashMap<String, String>,
+        const_name: String,
+        description: String,
+    ) -> Result<String, IOError> {
+        if let Some(other) = claimed.get(&const_name) {
+            return Err(IOError::new(
+                std::io::ErrorKind::InvalidInput,
+                format!(
+                    "resources {} and {} both get the constant name {}",
+                    other, description, const_name
+                ),
+            ));
         }
-        Ok(())
+        claimed.insert(const_name.clone(), description);
+        Ok(const_name)
     }
 
-    pub(crate) fn write_style_and_exstyle_statements(
+    pub(crate) fn write_loader_module(
         w: &mut dyn Write,
-        style: crate::dialog::WindowStyle,
+        entries: &[(&'static str, IdOrName)],
+        data_entries: &[(IdOrName, IdOrName)],
+        serde: bool,
     ) -> Result<(), IOError> {
-        match (style.0.as_ref(), style.1.as_ref()) {
-            (None, None) => {}
-            (Some(basic), None) => {
-                write!(w, "\nSTYLE ")?;
-                write_dword(w, *basic)?;
-            }
-            (None, Some(extended)) => {
-                write!(w, "\nEXSTYLE ")?;
-                write_dword(w, *extended)?;
+        write!(
+            w,
+            "// Resource loaders automatically generated by RESW-RS.\n"
+        )?;
+        write!(w, "// Do not edit this file manually.\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(w, "#[derive(Clone, Copy, Debug, PartialEq, Eq)]\n")?;
+        write!(w, "enum ResourceName {{\n")?;
+        write!(w, "    Id(u16),\n")?;
+        write!(w, "    Name(&'static [u16]),\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "impl ResourceName {{\n")?;
+        write!(w, "    fn as_ptr(self) -> *const u16 {{\n")?;
+        write!(w, "        match self {{\n")?;
+        write!(
+            w,
+            "            ResourceName::Id(id) => id as usize as *const u16,\n"
+        )?;
+        write!(
+            w,
+            "            ResourceName::Name(name) => name.as_ptr(),\n"
+        )?;
+        write!(w, "        }}\n")?;
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[allow(non_snake_case)]\n")?;
+        write!(w, "#[link(name = \"user32\")]\n")?;
+        write!(w, "extern \"system\" {{\n")?;
+        write!(w, "    fn LoadIconW(module: *mut core::ffi::c_void, name: *const u16) -> *mut core::ffi::c_void;\n")?;
+        write!(w, "    fn LoadCursorW(module: *mut core::ffi::c_void, name: *const u16) -> *mut core::ffi::c_void;\n")?;
+        write!(w, "    fn LoadImageW(module: *mut core::ffi::c_void, name: *const u16, ty: u32, cx: i32, cy: i32, flags: u32) -> *mut core::ffi::c_void;\n")?;
+        write!(w, "    fn LoadMenuW(module: *mut core::ffi::c_void, name: *const u16) -> *mut core::ffi::c_void;\n")?;
+        write!(w, "    fn LoadAcceleratorsW(module: *mut core::ffi::c_void, name: *const u16) -> *mut core::ffi::c_void;\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "extern \"C\" {{\n")?;
+        write!(w, "    static __ImageBase: u8;\n")?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "// the module this code is linked into, which is where the resources live\n"
+        )?;
+        write!(w, "fn module() -> *mut core::ffi::c_void {{\n")?;
+        write!(
+            w,
+            "    unsafe {{ &__ImageBase as *const u8 as *mut core::ffi::c_void }}\n"
+        )?;
+        write!(w, "}}\n")?;
+        for &(keyword, type_name, function_name, call) in LOADER_KINDS {
+            write!(w, "\n")?;
+            write!(w, "#[derive(Clone, Copy, Debug, PartialEq, Eq)]\n")?;
+            write!(w, "pub struct {}(ResourceName);\n", type_name)?;
+            write!(w, "\n")?;
+            write!(w, "impl {} {{\n", type_name)?;
+            let mut claimed = HashMap::new();
+            for (_, id_or_name) in entries.iter().filter(|(k, _)| *k == keyword) {
+                let const_name = claim_rust_const_name(
+                    &mut claimed,
+                    rust_const_name(id_or_name),
+                    format!("{} {}", keyword, id_or_name),
+                )?;
+                write!(w, "    pub const {}", const_name)?;
+                write!(w, ": {} = {}(", type_name, type_name)?;
+                write_rust_resource_name(w, id_or_name)?;
+                write!(w, ");\n")?;
             }
-            (Some(basic), Some(extended)) => {
-                write!(w, "\nSTYLE ")?;
-                write_dword(w, *basic)?;
-                write!(w, " EXSTYLE ")?;
-                write_dword(w, *extended)?;
+            write!(w, "}}\n")?;
+            write!(w, "\n")?;
+            write!(w, "#[allow(dead_code)]\n")?;
+            write!(
+                w,
+                "pub fn {}(id: {}) -> *mut core::ffi::c_void {{\n",
+                function_name, type_name
+            )?;
+            write!(w, "    unsafe {{ {} }}\n", call)?;
+            write!(w, "}}\n")?;
+        }
+        write_data_loaders(w, data_entries, serde)
+    }
+
+    fn write_rust_resource_name(w: &mut dyn Write, id_or_name: &IdOrName) -> Result<(), IOError> {
+        match id_or_name {
+            IdOrName::Id(id) => write!(w, "ResourceName::Id({})", id),
+            IdOrName::Name(name) => {
+                write!(w, "ResourceName::Name(&[")?;
+                for unit in name.encode_utf16() {
+                    write!(w, "{}, ", unit)?;
+                }
+                write!(w, "0])")
             }
-        };
-        Ok(())
+        }
     }
 
-    pub(crate) fn write_path_only_resource(
+    pub(crate) const RT_RCDATA: WORD = 10;
+
+    // Accessors for RCDATA and user-defined resources. RCDATA constants are
+    // named after the resource only, other types get the type as prefix.
+    fn write_data_loaders(
         w: &mut dyn Write,
-        lang: crate::Lang,
-        id_or_name: crate::IdOrName,
-        res_type_keyword: &'static str,
-        path: &std::path::Path,
+        data_entries: &[(IdOrName, IdOrName)],
+        serde: bool,
     ) -> Result<(), IOError> {
-        write_resource_header(w, lang, id_or_name, res_type_keyword)?;
-        write!(w, " ")?;
-        // let mut absolute_path = std::env::current_dir()?;
-        // absolute_path.push(path);
-        // write_path(w, &absolute_path)?;
-        write_path(w, path)?;
         write!(w, "\n")?;
+        write!(w, "#[allow(non_snake_case)]\n")?;
+        write!(w, "#[link(name = \"kernel32\")]\n")?;
+        write!(w, "extern \"system\" {{\n")?;
+        write!(w, "    fn FindResourceW(module: *mut core::ffi::c_void, name: *const u16, ty: *const u16) -> *mut core::ffi::c_void;\n")?;
+        write!(w, "    fn SizeofResource(module: *mut core::ffi::c_void, res_info: *mut core::ffi::c_void) -> u32;\n")?;
+        write!(w, "    fn LoadResource(module: *mut core::ffi::c_void, res_info: *mut core::ffi::c_void) -> *mut core::ffi::c_void;\n")?;
+        write!(
+            w,
+            "    fn LockResource(res_data: *mut core::ffi::c_void) -> *mut core::ffi::c_void;\n"
+        )?;
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(w, "#[derive(Clone, Copy, Debug, PartialEq, Eq)]\n")?;
+        write!(w, "pub struct DataId(ResourceName, ResourceName);\n")?;
+        write!(w, "\n")?;
+        write!(w, "impl DataId {{\n")?;
+        let mut claimed = HashMap::new();
+        for (type_id, id_or_name) in data_entries {
+            let mut const_name = rust_const_name(id_or_name);
+            if *type_id != IdOrName::Id(Id(RT_RCDATA)) {
+                const_name = format!("{}_{}", rust_const_name(type_id), const_name);
+            }
+            let description = format!("{} of type {}", id_or_name, type_id);
+            let const_name = claim_rust_const_name(&mut claimed, const_name, description)?;
+            write!(w, "    pub const {}: DataId = DataId(", const_name)?;
+            write_rust_resource_name(w, type_id)?;
+            write!(w, ", ")?;
+            write_rust_resource_name(w, id_or_name)?;
+            write!(w, ");\n")?;
+        }
+        write!(w, "}}\n")?;
+        write!(w, "\n")?;
+        write!(
+            w,
+            "// resource data stays mapped for as long as the module is loaded\n"
+        )?;
+        write!(w, "#[allow(dead_code)]\n")?;
+        write!(
+            w,
+            "pub fn load_data(id: DataId) -> Option<&'static [u8]> {{\n"
+        )?;
+        write!(w, "    unsafe {{\n")?;
+        write!(
+            w,
+            "        let res_info = FindResourceW(module(), id.1.as_ptr(), id.0.as_ptr());\n"
+        )?;
+        write!(w, "        if res_info.is_null() {{\n")?;
+        write!(w, "            return None;\n")?;
+        write!(w, "        }}\n")?;
+        write!(
+            w,
+            "        let size = SizeofResource(module(), res_info) as usize;\n"
+        )?;
+        write!(
+            w,
+            "        let data = LockResource(LoadResource(module(), res_info));\n"
+        )?;
+        write!(w, "        if data.is_null() {{\n")?;
+        write!(w, "            return None;\n")?;
+        write!(w, "        }}\n")?;
+        write!(
+            w,
+            "        Some(core::slice::from_raw_parts(data as *const u8, size))\n"
+        )?;
+        write!(w, "    }}\n")?;
+        write!(w, "}}\n")?;
+        if serde {
+            write!(w, "\n")?;
+            write!(w, "#[allow(dead_code)]\n")?;
+            write!(w, "pub fn load_data_json<T: serde::de::DeserializeOwned>(id: DataId) -> Option<serde_json::Result<T>> {{\n")?;
+            write!(w, "    load_data(id).map(serde_json::from_slice)\n")?;
+            write!(w, "}}\n")?;
+        }
         Ok(())
     }
 
@@ -2739,11 +12135,11 @@ mod codegen {
         write!(w, "LANGUAGE 0x{:x}, 0x{:x}\n", lang.0, lang.1)?;
         match res_type_keyword {
             resource::StringTable::TYPE_KEYWORD => {
-                ensure_id_or_name_ignorable(&id_or_name);
+                ensure_id_or_name_ignorable(&id_or_name)?;
             }
             resource::VersionInfo::TYPE_KEYWORD => {
                 if id_or_name != IdOrName::Id(Id(1)) {
-                    ensure_id_or_name_ignorable(&id_or_name);
+                    ensure_id_or_name_ignorable(&id_or_name)?;
                 }
                 write!(w, "1 ")?;
             }
@@ -2756,3 +12152,273 @@ mod codegen {
         Ok(())
     }
 }
+
+// A minimal reader of resource scripts, only used to re-read what was
+// generated. It knows just enough of the grammar to catch broken emission:
+// unterminated strings and bad escapes, unbalanced blocks and parentheses,
+// and stray commas, each reported with its line and column.
+mod rc_syntax {
+    use std::io::Error as IOError;
+
+    // line and column, both starting at 1
+    type Location = (usize, usize);
+
+    #[derive(Clone, Copy, PartialEq, Debug)]
+    enum Token {
+        Open,
+        Close,
+        OpenParen,
+        CloseParen,
+        Comma,
+        Other,
+    }
+
+    struct Reader<'a> {
+        bytes: &'a [u8],
+        pos: usize,
+        line: usize,
+        column: usize,
+    }
+
+    impl<'a> Reader<'a> {
+        fn peek(&self) -> Option<u8> {
+            self.bytes.get(self.pos).cloned()
+        }
+
+        fn peek_at(&self, offset: usize) -> Option<u8> {
+            self.bytes.get(self.pos + offset).cloned()
+        }
+
+        fn bump(&mut self) -> Option<u8> {
+            let byte = self.peek()?;
+            self.pos += 1;
+            if byte == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+            Some(byte)
+        }
+
+        fn error(&self, (line, column): Location, message: &str) -> IOError {
+            IOError::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "generated resource script is malformed at line {}, column {}: {}",
+                    line, column, message
+                ),
+            )
+        }
+
+        fn location(&self) -> Location {
+            (self.line, self.column)
+        }
+
+        // Skips whitespace, comments and preprocessor lines.
+        fn skip_trivia(&mut self) -> Result<(), IOError> {
+            let mut line_start = self.column == 1;
+            while let Some(byte) = self.peek() {
+                match byte {
+                    b'\n' => {
+                        self.bump();
+                        line_start = true;
+                    }
+                    b' ' | b'\t' | b'\r' => {
+                        self.bump();
+                    }
+                    b'#' if line_start => self.skip_directive(),
+                    b'/' if self.peek_at(1) == Some(b'/') => self.skip_line(),
+                    b'/' if self.peek_at(1) == Some(b'*') => {
+                        let start = self.location();
+                        self.bump();
+                        self.bump();
+                        loop {
+                            match self.bump() {
+                                Some(b'*') if self.peek() == Some(b'/') => {
+                                    self.bump();
+                                    break;
+                                }
+                                Some(_) => {}
+                                None => return Err(self.error(start, "unterminated comment")),
+                            }
+                        }
+                    }
+                    _ => break,
+                }
+            }
+            Ok(())
+        }
+
+        fn skip_line(&mut self) {
+            while let Some(byte) = self.peek() {
+                if byte == b'\n' {
+                    break;
+                }
+                self.bump();
+            }
+        }
+
+        // A preprocessor line goes on past a newline right after a backslash,
+        // as in a `#define` spread over several lines.
+        fn skip_directive(&mut self) {
+            let mut continued = false;
+            while let Some(byte) = self.peek() {
+                match byte {
+                    b'\n' if !continued => break,
+                    b'\\' => continued = true,
+                    b'\r' => {}
+                    _ => continued = false,
+                }
+                self.bump();
+            }
+        }
+
+        fn string(&mut self) -> Result<(), IOError> {
+            let start = self.location();
+            self.bump();
+            loop {
+                match self.bump() {
+                    None | Some(b'\n') => return Err(self.error(start, "unterminated string")),
+                    Some(b'"') if self.peek() == Some(b'"') => {
+                        self.bump();
+                    }
+                    Some(b'"') => return Ok(()),
+                    Some(b'\\') => {
+                        let escape = self.location();
+                        match self.bump() {
+                            Some(b'\\' | b'"' | b'n' | b't' | b'r' | b'a' | b'b' | b'f' | b'v') => {
+                            }
+                            Some(b'0'..=b'7') => {
+                                for _ in 0..2 {
+                                    if let Some(b'0'..=b'7') = self.peek() {
+                                        self.bump();
+                                    }
+                                }
+                            }
+                            Some(b'x' | b'X') => {
+                                if !self.peek().is_some_and(|b| b.is_ascii_hexdigit()) {
+                                    return Err(self.error(escape, "`\\x` without hex digits"));
+                                }
+                                while self.peek().is_some_and(|b| b.is_ascii_hexdigit()) {
+                                    self.bump();
+                                }
+                            }
+                            _ => return Err(self.error(escape, "unknown escape sequence")),
+                        }
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+
+        fn word(&mut self) -> &'a [u8] {
+            let start = self.pos;
+            while let Some(byte) = self.peek() {
+                if byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'.' {
+                    self.bump();
+                } else {
+                    break;
+                }
+            }
+            &self.bytes[start..self.pos]
+        }
+
+        fn next_token(&mut self) -> Result<Option<(Location, Token)>, IOError> {
+            self.skip_trivia()?;
+            let location = self.location();
+            let byte = match self.peek() {
+                Some(byte) => byte,
+                None => return Ok(None),
+            };
+            let token = match byte {
+                b'"' => {
+                    self.string()?;
+                    Token::Other
+                }
+                b'L' if self.peek_at(1) == Some(b'"') => {
+                    self.bump();
+                    self.string()?;
+                    Token::Other
+                }
+                b'{' | b'}' | b'(' | b')' | b',' => {
+                    self.bump();
+                    match byte {
+                        b'{' => Token::Open,
+                        b'}' => Token::Close,
+                        b'(' => Token::OpenParen,
+                        b')' => Token::CloseParen,
+                        _ => Token::Comma,
+                    }
+                }
+                _ if byte.is_ascii_alphanumeric() || byte == b'_' => match self.word() {
+                    b"BEGIN" => Token::Open,
+                    b"END" => Token::Close,
+                    _ => Token::Other,
+                },
+                _ => {
+                    self.bump();
+                    Token::Other
+                }
+            };
+            Ok(Some((location, token)))
+        }
+    }
+
+    pub(crate) fn check(script: &[u8]) -> Result<(), IOError> {
+        let mut reader = Reader {
+            bytes: script,
+            pos: 0,
+            line: 1,
+            column: 1,
+        };
+        let mut open: Vec<(Location, Token)> = Vec::new();
+        let mut previous: Option<Token> = None;
+        while let Some((location, token)) = reader.next_token()? {
+            match token {
+                Token::Open | Token::OpenParen => open.push((location, token)),
+                Token::Close | Token::CloseParen => {
+                    let opener = if token == Token::Close {
+                        Token::Open
+                    } else {
+                        Token::OpenParen
+                    };
+                    match open.pop() {
+                        Some((_, t)) if t == opener => {}
+                        Some((at, _)) => {
+                            return Err(reader.error(
+                                at,
+                                "block opened here is closed by the wrong kind of bracket",
+                            ))
+                        }
+                        None => {
+                            return Err(
+                                reader.error(location, "closing bracket without an opening one")
+                            )
+                        }
+                    }
+                    if previous == Some(Token::Comma) {
+                        return Err(reader.error(location, "comma before a closing bracket"));
+                    }
+                }
+                // two commas in a row leave an argument out, as MENUEX items
+                // with a state but no type do
+                Token::Comma => match previous {
+                    Some(Token::Open) | Some(Token::OpenParen) => {
+                        return Err(reader.error(location, "comma right after an opening bracket"))
+                    }
+                    _ => {}
+                },
+                Token::Other => {}
+            }
+            previous = Some(token);
+        }
+        if let Some((at, _)) = open.pop() {
+            return Err(reader.error(at, "block opened here is never closed"));
+        }
+        if previous == Some(Token::Comma) {
+            return Err(reader.error(reader.location(), "script ends with a comma"));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,169 @@
+use resw::dialog::{ButtonControl, Control, ControlTemplate};
+use resw::resource::Dialog;
+use resw::{lang, Build, FontCharset, FontItalic, FontSize, FontWeight, Rect};
+
+fn write(dialog: Dialog) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    Build::new(&[lang::LANG_ENU])
+        .resource(1u16, dialog)
+        .write_rc(&mut out)?;
+    Ok(out)
+}
+
+fn button() -> ButtonControl {
+    Control::from_template(ControlTemplate::PUSHBUTTON).text("OK")
+}
+
+#[test]
+fn auto_controls_get_free_ids() {
+    let dialog = Dialog::from_builder()
+        .control(1000u16, button())
+        .auto_control("IDC_FIRST", button())
+        .auto_control("IDC_SECOND", button())
+        .build();
+    assert_eq!(dialog.control_id("IDC_FIRST"), Some(1001u16.into()));
+    assert_eq!(dialog.control_id("IDC_SECOND"), Some(1002u16.into()));
+    write(dialog).unwrap();
+}
+
+#[test]
+fn auto_control_mistakes_fail_writing() {
+    let reused = Dialog::from_builder()
+        .auto_control("IDC_OK", button())
+        .auto_control("IDC_OK", button())
+        .build();
+    let err = write(reused).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("IDC_OK"), "{}", err);
+
+    let exhausted = Dialog::from_builder()
+        .auto_id_range(100..=100)
+        .auto_control("IDC_OK", button())
+        .auto_control("IDC_CANCEL", button())
+        .build();
+    let err = write(exhausted).unwrap_err();
+    assert!(err.to_string().contains("IDC_CANCEL"), "{}", err);
+
+    let clashing = Dialog::from_builder()
+        .auto_control("IDC_OK", button())
+        .control(1000u16, button())
+        .build();
+    let err = write(clashing).unwrap_err();
+    assert!(
+        err.to_string().contains("control id 1000 is used twice"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn static_controls_share_their_id() {
+    use resw::predefined_id;
+    let label = || Control::from_template(ControlTemplate::LTEXT).text("Name");
+    let dialog = Dialog::from_builder()
+        .control(predefined_id::STATIC, label())
+        .control(predefined_id::STATIC, label())
+        .build();
+    write(dialog).unwrap();
+}
+
+fn with_font(size: FontSize, weight: FontWeight) -> Dialog {
+    Dialog::from_builder()
+        .font(
+            "MS Shell Dlg",
+            size,
+            weight,
+            FontItalic::default(),
+            FontCharset::ANSI,
+        )
+        .build()
+}
+
+#[test]
+fn font_weights_beyond_1000_are_rejected() {
+    assert_eq!(
+        FontWeight::custom(350),
+        FontWeight::try_custom(350).unwrap()
+    );
+    assert!(FontWeight::try_custom(1001).is_err());
+    assert!(write(with_font(FontSize::pt(8), FontWeight::custom(1000))).is_ok());
+
+    let err = write(with_font(FontSize::pt(8), FontWeight::custom(5000))).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("DIALOGEX 1"), "{}", err);
+    assert!(err.to_string().contains("5000"), "{}", err);
+}
+
+#[test]
+fn font_sizes_out_of_range_fail_writing() {
+    assert!(FontSize::try_pt(0).is_err());
+    for size in [0, -8, FontSize::MAX_PT + 1] {
+        let err = write(with_font(FontSize::pt(size), FontWeight::NORMAL)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("DIALOGEX 1"), "{}", err);
+    }
+    assert!(write(with_font(
+        FontSize::pt(FontSize::MAX_PT),
+        FontWeight::NORMAL
+    ))
+    .is_ok());
+}
+
+#[test]
+fn rects_out_of_range_fail_writing() {
+    let dialog = Dialog::from_builder().rect(Rect::new(0, 0, -1, 10)).build();
+    let err = write(dialog).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("DIALOGEX 1"), "{}", err);
+    assert!(err.to_string().contains("dialog has width -1"), "{}", err);
+
+    let dialog = Dialog::from_builder()
+        .rect(Rect::new(0, 0, 200, 100))
+        .control(1000u16, button().rect(Rect::new(40000, 0, 50, 14)))
+        .build();
+    let err = write(dialog).unwrap_err();
+    assert!(
+        err.to_string().contains("control 1000 has x 40000"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn non_ascii_typefaces_need_the_utf8_code_page() {
+    let dialog = || {
+        Dialog::from_builder()
+            .font(
+                "ＭＳ ゴシック",
+                FontSize::pt(9),
+                FontWeight::NORMAL,
+                FontItalic::default(),
+                FontCharset::SHIFT_JIS,
+            )
+            .build()
+    };
+    let mut out = Vec::new();
+    let err = Build::new(&[lang::LANG_JPN])
+        .lang_code_page(lang::LANG_JPN, 932)
+        .resource(1u16, dialog())
+        .write_rc(&mut out)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("code page 932"), "{}", err);
+
+    let mut out = Vec::new();
+    Build::new(&[lang::LANG_JPN])
+        .resource(1u16, dialog())
+        .write_rc(&mut out)
+        .unwrap();
+    let script = String::from_utf8(out).unwrap();
+    assert!(script.contains("\"ＭＳ ゴシック\""), "{}", script);
+
+    let mut out = Vec::new();
+    let err = Build::new(&[lang::LANG_JPN])
+        .lang_code_page(lang::LANG_JPN, 932)
+        .resource("ダイアログ", Dialog::from_builder().build())
+        .write_rc(&mut out)
+        .unwrap_err();
+    assert!(err.to_string().contains("resource name"), "{}", err);
+}
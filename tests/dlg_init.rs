@@ -0,0 +1,70 @@
+use resw::resource::DlgInit;
+use resw::{lang, Build};
+
+fn write(build: Build, dlg_init: DlgInit) -> std::io::Result<String> {
+    let mut out = Vec::new();
+    build.resource(100u16, dlg_init).write_rc(&mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+// The bytes of the data block, read back from the words and the odd byte the
+// script spells them as.
+fn data_block(script: &str) -> Vec<u8> {
+    let block = script.split("240\n{\n").nth(1).unwrap();
+    let block = &block[..block.find('}').unwrap()];
+    let mut data = Vec::new();
+    for item in block.split(',').map(str::trim) {
+        if let Some(word) = item.strip_prefix("0x") {
+            let word = u16::from_str_radix(word, 16).unwrap();
+            data.extend_from_slice(&word.to_le_bytes());
+        } else {
+            let octal = item.trim_matches('"').trim_start_matches('\\');
+            data.push(u8::from_str_radix(octal, 8).unwrap());
+        }
+    }
+    data
+}
+
+#[test]
+fn strings_are_encoded_in_order() {
+    let dlg_init = DlgInit::from_builder()
+        .combo_box(1000u16, vec!["Red", "Green"])
+        .list_box(1001u16, vec!["A"])
+        .build();
+    let script = write(Build::new(&[lang::LANG_ENU]), dlg_init).unwrap();
+    #[rustfmt::skip]
+    let expected: Vec<u8> = vec![
+        // control id, CB_ADDSTRING, length with the NUL, text
+        0xE8, 0x03, 0x03, 0x04, 4, 0, 0, 0, b'R', b'e', b'd', 0,
+        0xE8, 0x03, 0x03, 0x04, 6, 0, 0, 0, b'G', b'r', b'e', b'e', b'n', 0,
+        // LB_ADDSTRING
+        0xE9, 0x03, 0x01, 0x04, 2, 0, 0, 0, b'A', 0,
+        0, 0,
+    ];
+    assert_eq!(data_block(&script), expected, "{}", script);
+}
+
+#[test]
+fn odd_lengths_end_in_a_byte_string() {
+    let dlg_init = DlgInit::from_builder().combo_box(1u16, vec!["ab"]).build();
+    let script = write(Build::new(&[lang::LANG_ENU]), dlg_init).unwrap();
+    let data = data_block(&script);
+    assert_eq!(data.len(), 13, "{}", script);
+    assert_eq!(&data[8..], b"ab\0\0\0");
+}
+
+#[test]
+fn non_ascii_text_needs_utf8_scripts() {
+    let dlg_init = || {
+        DlgInit::from_builder()
+            .combo_box(1u16, vec!["Grün"])
+            .build()
+    };
+    let script = write(Build::new(&[lang::LANG_ENU]), dlg_init()).unwrap();
+    assert_eq!(&data_block(&script)[8..13], "Grün".as_bytes());
+
+    let build = Build::new(&[lang::LANG_ENU]).lang_code_page(lang::LANG_ENU, 1252);
+    let err = write(build, dlg_init()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("1252"), "{}", err);
+}
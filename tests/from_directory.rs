@@ -0,0 +1,38 @@
+use resw::Build;
+use std::path::Path;
+
+fn manifest_line(package: &Path) -> String {
+    std::env::set_var("CARGO_MANIFEST_DIR", package);
+    let mut out = Vec::new();
+    Build::from_directory("resources")
+        .unwrap()
+        .write_rc(&mut out)
+        .unwrap();
+    let script = String::from_utf8(out).unwrap();
+    script
+        .lines()
+        .find(|line| line.contains("app.manifest"))
+        .unwrap()
+        .to_owned()
+}
+
+#[test]
+fn named_manifests_get_the_id_of_what_the_package_builds() {
+    let package = std::env::temp_dir().join("resw-from-directory");
+    let _ = std::fs::remove_dir_all(&package);
+    std::fs::create_dir_all(package.join("resources")).unwrap();
+    std::fs::create_dir_all(package.join("src")).unwrap();
+    std::fs::write(package.join("Cargo.toml"), "[package]\nname = \"dll\"\n").unwrap();
+    std::fs::write(
+        package.join("resources").join("app.manifest"),
+        "<assembly/>",
+    )
+    .unwrap();
+
+    let line = manifest_line(&package);
+    assert!(line.starts_with("2 24 "), "{}", line);
+
+    std::fs::write(package.join("src").join("main.rs"), "fn main() {}").unwrap();
+    let line = manifest_line(&package);
+    assert!(line.starts_with("1 24 "), "{}", line);
+}
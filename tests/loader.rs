@@ -0,0 +1,35 @@
+use resw::resource::Icon;
+use resw::{lang, Build};
+
+#[test]
+fn colliding_constant_names_are_reported() {
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource("app-icon", Icon::from_file("app.ico"))
+        .resource("APP_ICON", Icon::from_file("app.ico"));
+    let path = std::env::temp_dir().join("resw-colliding-loader.rs");
+    let err = build.generate_loader_module(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("APP_ICON"), "{}", err);
+}
+
+#[test]
+fn conditional_resources_are_left_out_of_checks_and_loaders() {
+    use resw::resource::Conditional;
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource("APP", Icon::from_file("app.ico"))
+        .resource(
+            "DEBUG_ONLY",
+            Conditional::ifdef("RESW_NEVER_DEFINED", Icon::from_file("debug.ico")).unwrap(),
+        );
+    let dir = std::env::temp_dir();
+    let checks_path = dir.join("resw-conditional-checks.rs");
+    let loader_path = dir.join("resw-conditional-loader.rs");
+    build.generate_resource_checks(&checks_path).unwrap();
+    build.generate_loader_module(&loader_path).unwrap();
+    let checks = std::fs::read_to_string(&checks_path).unwrap();
+    let loader = std::fs::read_to_string(&loader_path).unwrap();
+    assert!(loader.contains("const APP"), "{}", loader);
+    assert!(!loader.contains("DEBUG_ONLY"), "{}", loader);
+    assert!(checks.contains("\"APP\""), "{}", checks);
+    assert!(!checks.contains("DEBUG_ONLY"), "{}", checks);
+}
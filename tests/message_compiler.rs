@@ -0,0 +1,138 @@
+use resw::lang;
+use resw::message_compiler::{parse_inserts, parse_mc, read_mc_file, InsertType};
+
+fn fixture() -> resw::message_compiler::McFile {
+    read_mc_file(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/app.mc"
+    ))
+    .unwrap()
+}
+
+#[test]
+fn messages_take_ids_and_names_from_the_header() {
+    let file = fixture();
+    let ids: Vec<u32> = file.messages.iter().map(|m| m.message_id()).collect();
+    // the second message follows the first with the same severity and
+    // facility, the third is 5 after the second
+    assert_eq!(ids, [0x8101_0010, 0x8101_0011, 0xC102_0016]);
+    let names: Vec<_> = file
+        .messages
+        .iter()
+        .map(|m| m.symbolic_name.as_deref().unwrap())
+        .collect();
+    assert_eq!(names, ["MSG_DISK_FULL", "MSG_RETRYING", "MSG_OFFLINE"]);
+}
+
+#[test]
+fn texts_end_at_a_lone_period() {
+    let file = fixture();
+    let disk_full = &file.messages[0];
+    assert_eq!(disk_full.texts.len(), 2);
+    assert_eq!(
+        disk_full.texts[&lang::LANG_ENU],
+        "Disk %1 is full.\r\nFree up %2!d! bytes.\r\n"
+    );
+    assert_eq!(
+        disk_full.texts[&lang::LANG_DEU],
+        "Datenträger %1 ist voll.\r\n"
+    );
+    assert_eq!(file.messages[2].texts[&lang::LANG_ENU], "Offline.\r\n");
+}
+
+#[test]
+fn mistakes_name_their_line() {
+    let err = parse_mc("MessageId=1\nSeverity=Fatal\n").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().starts_with("line 2:"), "{}", err);
+
+    let err = parse_mc("MessageId=1\nLanguage=English\nNo end\n").unwrap_err();
+    assert!(err.to_string().contains("unterminated"), "{}", err);
+}
+
+#[test]
+fn relative_ids_count_from_the_previous_message() {
+    let file =
+        parse_mc("MessageId=+3\nLanguage=English\nA.\n.\nMessageId=+2\nLanguage=English\nB.\n.\n")
+            .unwrap();
+    let codes: Vec<_> = file.messages.iter().map(|m| m.code).collect();
+    assert_eq!(codes, [3, 5]);
+}
+
+#[test]
+fn facilities_beyond_0xfff_are_rejected() {
+    let source = "FacilityNames=(Big=0x1000:FACILITY_BIG)\nMessageId=1\nFacility=Big\n";
+    let err = parse_mc(source).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(err.to_string().starts_with("line 3:"), "{}", err);
+    assert!(err.to_string().contains("0x1000"), "{}", err);
+}
+
+#[test]
+fn inserts_are_typed_by_their_format() {
+    let inserts = parse_inserts("Disk %1 is full.\r\nFree up %2!d! bytes, %3!lu! left.").unwrap();
+    let inserts: Vec<_> = inserts.into_iter().collect();
+    assert_eq!(
+        inserts,
+        [
+            (1, InsertType::Str),
+            (2, InsertType::Int),
+            (3, InsertType::UInt)
+        ]
+    );
+    assert!(parse_inserts("100%% done%n%r%0").unwrap().is_empty());
+}
+
+#[test]
+fn inserts_with_conflicting_or_unknown_formats_are_rejected() {
+    assert!(parse_inserts("%1!s! and %1!d!").is_err());
+    assert!(parse_inserts("%1!f!").is_err());
+    assert!(parse_inserts("%1 and %1!s!").is_ok());
+}
+
+#[test]
+fn ansi_string_and_char_inserts_are_rejected() {
+    for format in ["S", "C", "hs", "hc", "hS", "hC", "-10S"] {
+        let err = parse_inserts(&format!("%1!{}!", format)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{}", format);
+        assert!(err.to_string().contains("ANSI"), "{}", err);
+    }
+    for (format, ty) in [
+        ("ls", InsertType::Str),
+        ("wS", InsertType::Str),
+        ("lc", InsertType::Char),
+        ("wC", InsertType::Char),
+        ("c", InsertType::Char),
+    ] {
+        let inserts = parse_inserts(&format!("%1!{}!", format)).unwrap();
+        assert_eq!(inserts[&1], ty, "{}", format);
+    }
+}
+
+#[test]
+fn format_functions_need_distinct_rust_names() {
+    let path = std::env::temp_dir().join("resw-format-names.rs");
+    let message = |name: &str, id: u32| {
+        format!(
+            "MessageId={}\nSymbolicName={}\nLanguage=English\nText.\n.\n",
+            id, name
+        )
+    };
+
+    let file = parse_mc(&(message("Type", 1) + &message("Loop", 2))).unwrap();
+    file.generate_format_module(&path).unwrap();
+    let module = std::fs::read_to_string(&path).unwrap();
+    assert!(module.contains("pub fn format_type()"), "{}", module);
+
+    let file = parse_mc(&(message("DiskFull", 1) + &message("DISKFULL", 2))).unwrap();
+    let err = file.generate_format_module(&path).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("format_diskfull"), "{}", err);
+
+    let file = parse_mc(&message("Message", 1)).unwrap();
+    assert!(file.generate_format_module(&path).is_err());
+
+    let file = parse_mc(&message("Disk-Full", 1)).unwrap();
+    let err = file.generate_format_module(&path).unwrap_err();
+    assert!(err.to_string().contains("Disk-Full"), "{}", err);
+}
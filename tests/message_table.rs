@@ -0,0 +1,93 @@
+use resw::message_table::Severity;
+use resw::resource::MessageTable;
+use resw::{lang, Build};
+
+fn write(table: MessageTable) -> std::io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    Build::new(&[lang::LANG_ENU])
+        .resource(1u16, table)
+        .write_rc(&mut out)?;
+    Ok(out)
+}
+
+#[test]
+fn unusable_messages_fail_writing() {
+    let table = MessageTable::from_builder()
+        .message(1, Severity::Error, 0x1000, "Failed")
+        .build();
+    let err = write(table).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("facility"), "{}", err);
+
+    let table = MessageTable::from_builder()
+        .message(1, Severity::Error, 0x100, "Failed")
+        .message(1, Severity::Error, 0x100, "Failed again")
+        .build();
+    let err = write(table).unwrap_err();
+    assert!(err.to_string().contains("twice"), "{}", err);
+}
+
+// The binary a table is compiled from, read back from the file the script
+// refers to.
+fn encoded(table: MessageTable) -> Vec<u8> {
+    std::fs::read(encoded_path(table)).unwrap()
+}
+
+fn encoded_path(table: MessageTable) -> String {
+    let out_dir = std::env::temp_dir().join("resw-message-table");
+    std::fs::create_dir_all(&out_dir).unwrap();
+    std::env::set_var("OUT_DIR", &out_dir);
+    let script = String::from_utf8(write(table).unwrap()).unwrap();
+    let line = script
+        .lines()
+        .find(|line| line.contains("MESSAGETABLE"))
+        .unwrap();
+    line.split('"').nth(1).unwrap().replace("\\\\", "\\")
+}
+
+#[test]
+fn encoded_tables_are_only_written_with_the_script() {
+    let table = || {
+        MessageTable::from_builder()
+            .message(7, Severity::Warning, 0x12, "Written with the script")
+            .build()
+    };
+    let path = encoded_path(table());
+    std::fs::remove_file(&path).unwrap();
+
+    let build = || Build::new(&[lang::LANG_ENU]).resource(1u16, table());
+    let checks = std::env::temp_dir().join("resw-message-table-checks.rs");
+    build().generate_resource_checks(&checks).unwrap();
+    assert!(!std::path::Path::new(&path).exists());
+
+    let invalid = MessageTable::from_builder()
+        .message(1, Severity::Error, 0x1000, "Failed")
+        .build();
+    let mut out = Vec::new();
+    assert!(build().resource(2u16, invalid).write_rc(&mut out).is_err());
+    assert!(!std::path::Path::new(&path).exists());
+
+    assert_eq!(encoded_path(table()), path);
+    assert!(std::path::Path::new(&path).exists());
+}
+
+#[test]
+fn consecutive_ids_share_a_block() {
+    let table = MessageTable::from_builder()
+        .message(5, Severity::Success, 0, "Bye")
+        .message(1, Severity::Success, 0, "Hi")
+        .message(2, Severity::Success, 0, "Ok.")
+        .build();
+    #[rustfmt::skip]
+    let expected: Vec<u8> = vec![
+        // two blocks: ids 1 to 2 at offset 28, id 5 at offset 52
+        2, 0, 0, 0,
+        1, 0, 0, 0, 2, 0, 0, 0, 28, 0, 0, 0,
+        5, 0, 0, 0, 5, 0, 0, 0, 52, 0, 0, 0,
+        // length, unicode flag, text with its NUL, padded to four bytes
+        12, 0, 1, 0, b'H', 0, b'i', 0, 0, 0, 0, 0,
+        12, 0, 1, 0, b'O', 0, b'k', 0, b'.', 0, 0, 0,
+        12, 0, 1, 0, b'B', 0, b'y', 0, b'e', 0, 0, 0,
+    ];
+    assert_eq!(encoded(table), expected);
+}
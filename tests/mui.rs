@@ -0,0 +1,43 @@
+use resw::resource::{Bitmap, Icon};
+use resw::{lang, Build, Lint, Severity};
+
+fn scripts(build: Build, name: &str) -> (String, Vec<String>) {
+    let dir = std::env::temp_dir().join("resw-mui").join(name);
+    let scripts = build
+        .lint(Lint::MissingFile, Severity::Allow)
+        .generate_mui_rc_files(&dir)
+        .unwrap();
+    let neutral = std::fs::read_to_string(&scripts.neutral).unwrap();
+    let languages = scripts
+        .languages
+        .iter()
+        .map(|(_, path)| std::fs::read_to_string(path).unwrap())
+        .collect();
+    (neutral, languages)
+}
+
+#[test]
+fn language_specific_bitmaps_go_to_their_satellites() {
+    let build = Build::new(&[lang::LANG_ENU, lang::LANG_DEU])
+        .resource(1u16, Icon::from_file("app.ico"))
+        .lang_specific_resource(lang::LANG_ENU, 2u16, Bitmap::from_file("en.bmp"))
+        .lang_specific_resource(lang::LANG_DEU, 2u16, Bitmap::from_file("de.bmp"));
+    let (neutral, languages) = scripts(build, "bitmaps");
+    assert!(neutral.contains("app.ico"), "{}", neutral);
+    assert!(!neutral.contains("BITMAP"), "{}", neutral);
+    assert_eq!(languages.len(), 2);
+    let english = languages.iter().find(|s| s.contains("en.bmp")).unwrap();
+    let german = languages.iter().find(|s| s.contains("de.bmp")).unwrap();
+    assert!(!english.contains("de.bmp"), "{}", english);
+    assert!(!german.contains("en.bmp"), "{}", german);
+    assert!(!german.contains("app.ico"), "{}", german);
+}
+
+#[test]
+fn deny_level_lints_fail_mui_builds() {
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource(1u16, Icon::from_file("missing.ico"))
+        .lint(Lint::MissingFile, Severity::Deny);
+    let dir = std::env::temp_dir().join("resw-mui").join("deny");
+    assert!(build.generate_mui_rc_files(&dir).is_err());
+}
@@ -0,0 +1,172 @@
+// File names end up in string literals of the generated script, where quotes
+// and backslashes need escapes and anything else is taken as is.
+
+use resw::resource::*;
+use resw::{lang, Build, Dialect, Lint};
+use std::path::{Path, PathBuf};
+
+fn script(build: Build) -> String {
+    let mut out = Vec::new();
+    build
+        .lint(Lint::MissingFile, resw::Severity::Allow)
+        .write_rc(&mut out)
+        .unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn icon_script(path: &Path, dialect: Dialect) -> String {
+    script(
+        Build::new(&[lang::LANG_ENU])
+            .dialect(dialect)
+            .resource(1u16, Icon::from_file(path)),
+    )
+}
+
+// The path as the script spells it, before any non-ASCII escapes.
+fn escaped(path: &Path) -> String {
+    path.to_str()
+        .unwrap()
+        .replace('\\', "\\\\")
+        .replace('"', "\"\"")
+}
+
+fn adversarial_dir() -> PathBuf {
+    std::env::temp_dir()
+        .join("resw paths")
+        .join("100% \"quoted\"")
+}
+
+#[test]
+fn quotes_spaces_and_percent_signs_are_escaped() {
+    let path = adversarial_dir().join("app #1.ico");
+    let expected = format!("1 ICON  \"{}\"\n", escaped(&path));
+    let script = icon_script(&path, Dialect::MsvcRc);
+    assert!(script.contains(&expected), "{}", script);
+    assert!(script.contains("100% \"\"quoted\"\""), "{}", script);
+}
+
+#[test]
+fn non_ascii_names_are_written_as_wide_strings() {
+    let path = adversarial_dir().join("ícone.ico");
+    let escaped = escaped(&path);
+    let expected = format!("1 ICON  L\"{}\"\n", escaped.replace('í', "\\x00ed"));
+    let script = icon_script(&path, Dialect::MsvcRc);
+    assert!(script.contains(&expected), "{}", script);
+
+    // windres reads the script as UTF-8 and keeps the text as is
+    let expected = format!("1 ICON  L\"{}\"\n", escaped);
+    let script = icon_script(&path, Dialect::GnuWindres);
+    assert!(script.contains(&expected), "{}", script);
+}
+
+#[test]
+fn long_paths_are_written_in_full() {
+    let mut path = adversarial_dir();
+    for _ in 0..40 {
+        path.push("a directory with a long name");
+    }
+    path.push("app.ico");
+    assert!(path.as_os_str().len() > 1024);
+    let expected = format!("1 ICON  \"{}\"\n", escaped(&path));
+    let script = icon_script(&path, Dialect::MsvcRc);
+    assert!(script.contains(&expected), "{}", script);
+}
+
+#[test]
+fn control_characters_are_rejected() {
+    let path = adversarial_dir().join("line\nbreak.ico");
+    let mut out = Vec::new();
+    let err = Build::new(&[lang::LANG_ENU])
+        .lint(Lint::MissingFile, resw::Severity::Allow)
+        .resource(1u16, Icon::from_file(path))
+        .write_rc(&mut out)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn included_scripts_are_named_by_narrow_strings() {
+    let dir = std::env::temp_dir()
+        .join("resw paths")
+        .join("100% ïncluded");
+    let path = dir.join("hand written.rc");
+    let expected = format!(
+        "#include \"{}\"\n",
+        path.to_str().unwrap().replace('\\', "\\\\")
+    );
+    let script = script(Build::new(&[lang::LANG_ENU]).include_rc_file(&path));
+    assert!(script.contains(&expected), "{}", script);
+}
+
+#[test]
+fn included_scripts_with_quotes_are_rejected() {
+    let path = adversarial_dir().join("hand written.rc");
+    let mut out = Vec::new();
+    let err = Build::new(&[lang::LANG_ENU])
+        .lint(Lint::MissingFile, resw::Severity::Allow)
+        .include_rc_file(path)
+        .write_rc(&mut out)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+fn inlined(name: &str, contents: &[u8]) -> String {
+    let dir = std::env::temp_dir().join("resw-inline");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    script(Build::new(&[lang::LANG_ENU]).inline_rc_file(&path))
+}
+
+#[test]
+fn inlined_scripts_are_converted_from_utf16() {
+    let text = "STRINGTABLE { 1, \"Grüße\" }\n";
+    let mut le = vec![0xff, 0xfe];
+    let mut be = vec![0xfe, 0xff];
+    for unit in text.encode_utf16() {
+        le.extend(unit.to_le_bytes());
+        be.extend(unit.to_be_bytes());
+    }
+    let mut utf8 = b"\xef\xbb\xbf".to_vec();
+    utf8.extend(text.as_bytes());
+    for (name, contents) in [("le.rc", le), ("be.rc", be), ("utf8.rc", utf8)] {
+        let script = inlined(name, &contents);
+        assert!(script.ends_with(text), "{}", script);
+    }
+}
+
+#[test]
+fn inlined_defines_may_span_lines() {
+    for line_ending in ["\n", "\r\n"] {
+        let text = format!(
+            "#define ID_ABOUT (ID_BASE + \\{0}    1){0}STRINGTABLE {{ ID_ABOUT, \"About\" }}{0}",
+            line_ending
+        );
+        let script = inlined("define.rc", text.as_bytes());
+        assert!(script.contains("#define ID_ABOUT"), "{}", script);
+    }
+}
+
+#[test]
+fn inlined_scripts_with_odd_utf16_are_rejected() {
+    let dir = std::env::temp_dir().join("resw-inline");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("odd.rc");
+    std::fs::write(&path, b"\xff\xfe\x41").unwrap();
+    let mut out = Vec::new();
+    let err = Build::new(&[lang::LANG_ENU])
+        .inline_rc_file(&path)
+        .write_rc(&mut out)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn out_dir_paths_need_out_dir() {
+    std::env::remove_var("OUT_DIR");
+    let err = resw::out_dir_path("app.ico").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    let dir = std::env::temp_dir().join("resw-out-dir");
+    std::env::set_var("OUT_DIR", &dir);
+    assert_eq!(resw::out_dir_path("app.ico").unwrap(), dir.join("app.ico"));
+}
@@ -0,0 +1,82 @@
+use resw::resource::RcInline;
+use resw::{lang, Build};
+
+// The RCDATA block, without the header line.
+fn block(data: RcInline) -> String {
+    let mut out = Vec::new();
+    Build::new(&[lang::LANG_ENU])
+        .resource(1u16, data)
+        .write_rc(&mut out)
+        .unwrap();
+    let script = String::from_utf8(out).unwrap();
+    let start = script.find("RCDATA").unwrap();
+    let block = &script[start..];
+    block[block.find('{').unwrap()..].to_owned()
+}
+
+#[test]
+fn dwords_get_the_long_suffix() {
+    let data = RcInline::from_builder()
+        .word(1)
+        .dword(2)
+        .dword(0xFFFF_FFFF)
+        .build();
+    assert_eq!(block(data), "{\n\t1,\n\t2L,\n\t4294967295L\n}\n");
+}
+
+#[test]
+fn long_strings_are_split() {
+    let block = block(RcInline::from_builder().bytes(&[b'a'; 8001]).build());
+    let a = "a".repeat(4000);
+    let expected = format!("{{\n\t\"{}\",\n\t\"{}\",\n\t\"a\"\n}}\n", a, a);
+    assert_eq!(block, expected);
+}
+
+#[test]
+fn non_ascii_bytes_are_written_as_words() {
+    let data = RcInline::from_builder()
+        .bytes(&[0x80, 0x81, 0x82])
+        .bytes(&[0xFF; 17])
+        .build();
+    let words = ["0xffff"; 8].join(", ");
+    // llvm-rc only takes a single high byte in a narrow string in code page 1252
+    let expected = format!(
+        "{{\n\t0x8180,\n{}\t\"\\202\",\n{}\t{},\n{}\t\"\\377\"\n{}}}\n",
+        "#pragma code_page(1252)\n",
+        "#pragma code_page(65001)\n",
+        words,
+        "#pragma code_page(1252)\n",
+        "#pragma code_page(65001)\n",
+    );
+    assert_eq!(block(data), expected);
+    // an odd ASCII byte stays on the line
+    let data = RcInline::from_builder().bytes(&[0x80, 0x81, b'a']).build();
+    assert_eq!(block(data), "{\n\t0x8180, \"a\"\n}\n");
+}
+
+#[test]
+fn odd_high_bytes_need_no_switch_in_code_page_1252() {
+    let mut out = Vec::new();
+    Build::new(&[lang::LANG_ENU])
+        .lang_code_page(lang::LANG_ENU, 1252)
+        .resource(1u16, RcInline::from_builder().bytes(&[0xFF]).build())
+        .write_rc(&mut out)
+        .unwrap();
+    let script = String::from_utf8(out).unwrap();
+    assert_eq!(
+        script.matches("#pragma code_page(1252)").count(),
+        1,
+        "{}",
+        script
+    );
+    assert!(script.ends_with("{\n\t\"\\377\"\n}\n"), "{}", script);
+}
+
+#[test]
+fn wide_strings_are_written_as_wide_literals() {
+    let data = RcInline::from_builder().wide_str("Grüße\0").build();
+    assert_eq!(block(data), "{\n\tL\"Gr\\x00fc\\x00dfe\\x0000\"\n}\n");
+    // empty strings add no data, so no literal either
+    let data = RcInline::from_builder().wide_str("").word(1).build();
+    assert_eq!(block(data), "{\n\t1\n}\n");
+}
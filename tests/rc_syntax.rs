@@ -0,0 +1,145 @@
+// Every resource writer's output goes through the syntax check generated
+// scripts get before they are written out.
+
+use resw::accelerators::{ASCIIKey, ASCIIModifier, Event, Modifier, VirtKey};
+use resw::dialog::{Control, ControlStyle, ControlTemplate, WindowStyle};
+use resw::menu::{MenuState, MenuType};
+use resw::message_table::Severity;
+use resw::resource::*;
+use resw::{lang, Build, FontCharset, FontItalic, FontSize, FontWeight, Lint, Rect};
+
+fn every_writer() -> Build {
+    let string_table = StringTable::from_builder()
+        .string(1u16, "Hello, \"world\"")
+        .lang_specific_string(lang::LANG_ENU, 2u16, "Tab\tand\nnewline")
+        .build();
+    let accelerators = Accelerators::from_builder()
+        .event(
+            10u16,
+            Event::ascii_key_event(ASCIIKey::ascii_key(b'a'), ASCIIModifier::Ctrl),
+        )
+        .event(11u16, Event::virt_key_event(VirtKey::F5, Modifier::Shift))
+        .build();
+    let menu = Menu::from_builder()
+        .popup("&File", |popup| {
+            popup
+                .item(20u16, "&Open\tCtrl+O")
+                .separator()
+                // no type but a state, written as `id, , state`
+                .complex_item(
+                    Some(21u16),
+                    "&Checked",
+                    MenuType::default(),
+                    MenuState::CHECKED,
+                )
+        })
+        .complex_popup(
+            None::<u16>,
+            "&Help",
+            MenuType::default(),
+            MenuState::default(),
+            |popup| popup.help_id(5).item(22u16, "&About"),
+        )
+        .complex_item(
+            Some(23u16),
+            "&Right",
+            MenuType::MENUBAR_BREAK,
+            MenuState::default(),
+        )
+        .build();
+    let dialog = Dialog::from_builder()
+        .system_menu()
+        .caption("About".into())
+        .rect(Rect::new(0, 0, 200, 100))
+        .font(
+            "MS Shell Dlg",
+            FontSize::pt(8),
+            FontWeight::THIN,
+            FontItalic::ITALIC,
+            FontCharset::ANSI,
+        )
+        .control(
+            1u16,
+            Control::from_template(ControlTemplate::DEFPUSHBUTTON)
+                .text("OK")
+                .rect(Rect::new(10, 10, 50, 14))
+                .style(WindowStyle::TAB_STOP),
+        )
+        .control(
+            30u16,
+            Control::from_template(ControlTemplate::LTEXT)
+                .text("Text, with a comma")
+                .rect(Rect::new(10, 30, 100, 8)),
+        )
+        .control(
+            31u16,
+            Control::from_template(ControlTemplate::COMBOBOX).rect(Rect::new(10, 50, 100, 40)),
+        )
+        .control(
+            32u16,
+            Control::custom("msctls_progress32")
+                .rect(Rect::new(10, 70, 100, 8))
+                .style(ControlStyle::custom(0x01)),
+        )
+        .build();
+    let version_info = VersionInfo::from_builder()
+        .product_name("Demo")
+        .file_version("1.0")
+        .custom_string("CommitHash", "abc123")
+        .build();
+    let rc_inline = RcInline::from_builder()
+        .word(1)
+        .dword(2)
+        .narrow_str("narrow")
+        .wide_str("wide")
+        .bytes(&[0, 1, 2])
+        .build();
+    let user_defined = UserDefined::from_builder()
+        .type_name("CUSTOM")
+        .data_bytes(b"data")
+        .build();
+    let message_table = MessageTable::from_builder()
+        .message(1, Severity::Error, 0x100, "Failed: %1")
+        .build();
+    let registry = Registry::from_builder()
+        .line("HKCR { NoRemove CLSID }")
+        .build();
+    let dlg_init = DlgInit::from_builder()
+        .combo_box(31u16, vec!["One", "Two"])
+        .build();
+
+    Build::new(&[lang::LANG_ENU])
+        .lint(Lint::MissingFile, resw::Severity::Allow)
+        .resource(0u16, string_table)
+        .resource(1u16, accelerators)
+        .resource(1u16, menu)
+        .resource(1u16, dialog)
+        .resource(1u16, version_info)
+        .resource(1u16, rc_inline)
+        .resource(1u16, user_defined)
+        .resource(1u16, message_table)
+        .resource(1u16, registry)
+        .resource(1u16, dlg_init)
+        .resource(1u16, Icon::from_file("app.ico"))
+        .resource(1u16, Bitmap::from_file("logo.bmp"))
+        .resource(1u16, Cursor::from_file("hand.cur"))
+        .resource(1u16, Font::from_file("font.fnt"))
+        .resource(1u16, HTML::from_file("about.html"))
+        .resource(1u16, Wave::from_file("ding.wav"))
+        .resource(1u16, Manifest::from_file("app.manifest"))
+        .resource(
+            2u16,
+            Conditional::ifdef("_DEBUG", Icon::from_file("debug.ico")).unwrap(),
+        )
+        .resource(0u16, RawRc::new("// copied in as is\n"))
+}
+
+#[test]
+fn every_writer_passes_the_syntax_check() {
+    let dir = std::env::temp_dir().join("resw-rc-syntax");
+    std::fs::create_dir_all(&dir).unwrap();
+    std::env::set_var("OUT_DIR", &dir);
+    every_writer()
+        .generate_rc_file(&dir.join("resource.rc"))
+        .unwrap();
+}
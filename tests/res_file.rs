@@ -0,0 +1,47 @@
+use resw::res_file::{extract_strings, parse_res, ResEntry};
+use resw::{lang, IdOrName};
+
+#[test]
+fn zeroed_res_header_is_rejected() {
+    let err = parse_res(&[0u8; 32]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn string_block_beyond_id_range_is_rejected() {
+    let entry = ResEntry {
+        type_id: IdOrName::from(6u16),
+        name: IdOrName::from(0xFFFFu16),
+        lang: lang::LANG_ENU,
+        memory_flags: 0,
+        data_version: 0,
+        version: 0,
+        characteristics: 0,
+        data: vec![1, 0, b'a', 0],
+    };
+    let err = extract_strings(&[entry]).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn odd_length_data_ends_in_code_page_1252() {
+    let entry = ResEntry {
+        type_id: IdOrName::from(10u16),
+        name: IdOrName::from(1u16),
+        lang: lang::LANG_ENU,
+        memory_flags: 0,
+        data_version: 0,
+        version: 0,
+        characteristics: 0,
+        data: vec![b'a', b'b', 0xFF],
+    };
+    let mut out = Vec::new();
+    resw::Build::new(&[lang::LANG_ENU])
+        .precompiled_resources(vec![entry])
+        .write_rc(&mut out)
+        .unwrap();
+    let script = String::from_utf8(out).unwrap();
+    let expected =
+        "{\n\t0x6261,\n#pragma code_page(1252)\n\t\"\\377\"\n#pragma code_page(65001)\n}\n";
+    assert!(script.ends_with(expected), "{}", script);
+}
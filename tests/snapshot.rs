@@ -0,0 +1,32 @@
+#![cfg(feature = "testing")]
+
+use resw::resource::StringTable;
+use resw::testing::assert_rc_snapshot;
+use resw::{lang, Build};
+
+fn build() -> Build {
+    Build::new(&[lang::LANG_ENU]).resource(
+        0u16,
+        StringTable::from_builder().string(1u16, "Hello").build(),
+    )
+}
+
+// One test, as the update variable is process wide.
+#[test]
+fn missing_snapshots_fail_unless_updating() {
+    let dir = std::env::temp_dir().join("resw-snapshot");
+    let path = dir.join("strings.rc");
+    let _ = std::fs::remove_file(&path);
+
+    std::env::remove_var("RESW_UPDATE_SNAPSHOTS");
+    let missing = std::panic::catch_unwind(|| assert_rc_snapshot(build(), &path));
+    assert!(missing.is_err());
+    assert!(!path.exists());
+
+    std::env::set_var("RESW_UPDATE_SNAPSHOTS", "1");
+    assert_rc_snapshot(build(), &path);
+    assert!(path.exists());
+
+    std::env::remove_var("RESW_UPDATE_SNAPSHOTS");
+    assert_rc_snapshot(build(), &path);
+}
@@ -0,0 +1,112 @@
+#![cfg(feature = "spec")]
+
+use resw::spec::BuildSpec;
+use std::path::Path;
+
+#[test]
+fn version_info_section_is_written() {
+    let spec = BuildSpec::from_toml_str(
+        r#"
+languages = ["en-US", "de-DE"]
+
+[version_info]
+fixed_file_version = "1.2.3.4"
+fixed_file_type = "app"
+product_name = "Demo"
+company_name = { default = "Demo Inc.", de-DE = "Demo GmbH" }
+
+[version_info.custom_strings]
+CommitHash = "abc123"
+"#,
+    )
+    .unwrap();
+    let reparsed = BuildSpec::from_toml_str(&spec.to_toml_string().unwrap()).unwrap();
+    assert_eq!(spec, reparsed);
+
+    let mut script = Vec::new();
+    spec.to_build(Path::new(""))
+        .unwrap()
+        .write_rc(&mut script)
+        .unwrap();
+    let script = String::from_utf8(script).unwrap();
+    assert!(script.contains("FILEVERSION 1, 2, 3, 4"));
+    assert!(script.contains("\"Demo GmbH\""));
+    assert!(script.contains("\"CommitHash\""));
+}
+
+#[test]
+fn standard_keys_among_custom_strings_are_an_error() {
+    let spec = BuildSpec::from_toml_str(
+        r#"
+languages = ["en-US"]
+
+[version_info]
+product_name = "Demo"
+
+[version_info.custom_strings]
+ProductName = "Other"
+"#,
+    )
+    .unwrap();
+    let err = spec
+        .to_build(Path::new(""))
+        .unwrap()
+        .write_rc(&mut Vec::new())
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("ProductName"), "{}", err);
+}
+
+#[test]
+fn ids_given_twice_are_an_error() {
+    let spec = BuildSpec::from_toml_str(
+        r#"
+languages = ["en-US"]
+
+[[string_tables]]
+strings = [{ id = 1, text = "Open" }]
+
+[[string_tables]]
+strings = [{ id = 1, text = "Save" }]
+"#,
+    )
+    .unwrap();
+    let err = spec.to_build(Path::new("")).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("string id 1"), "{}", err);
+
+    let spec = BuildSpec::from_toml_str(
+        r#"
+languages = ["en-US"]
+
+[[files]]
+kind = "icon"
+name = 1
+path = "a.ico"
+
+[[files]]
+kind = "icon"
+name = 1
+path = "b.ico"
+"#,
+    )
+    .unwrap();
+    let err = spec.to_build(Path::new("")).unwrap_err();
+    assert!(err.to_string().contains("ICON 1"), "{}", err);
+}
+
+#[test]
+fn symbols_must_be_c_identifiers() {
+    let spec = BuildSpec::from_toml_str(
+        r#"
+languages = ["en-US"]
+
+[[string_tables]]
+strings = [{ id = 1, symbol = "IDS_OPEN", text = "Open" }, { id = 2, symbol = "IDS SAVE", text = "Save" }]
+"#,
+    )
+    .unwrap();
+    let err = spec.write_c_header(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("IDS SAVE"), "{}", err);
+}
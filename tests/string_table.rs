@@ -0,0 +1,125 @@
+use resw::resource::StringTable;
+use resw::string_table::PluralCategory::{One, Other};
+use resw::{lang, Build, Lang};
+
+fn write(table: StringTable) -> std::io::Result<String> {
+    write_for(&[lang::LANG_ENU], table)
+}
+
+fn write_for(languages: &[Lang], table: StringTable) -> std::io::Result<String> {
+    let mut out = Vec::new();
+    Build::new(languages)
+        .resource(0u16, table)
+        .write_rc(&mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn plural_strings_keep_each_form() {
+    let table = StringTable::from_builder()
+        .plural_string(10u16, &[(One, "{} file"), (Other, "{} files")])
+        .build();
+    let script = write(table).unwrap();
+    assert!(script.contains("\"{} file\""), "{}", script);
+    assert!(script.contains("\"{} files\""), "{}", script);
+}
+
+#[test]
+fn plural_strings_without_other_form_fail_writing() {
+    let table = StringTable::from_builder()
+        .lang_specific_plural_string(lang::LANG_ENU, 10u16, &[(One, "{} file")])
+        .build();
+    let err = write(table).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(err.to_string().contains("Other"), "{}", err);
+
+    let table = StringTable::from_builder()
+        .plural_string(0xFFFFu16, &[(Other, "{} files")])
+        .build();
+    assert!(write(table).is_err());
+}
+
+#[test]
+fn strings_are_written_by_id_with_language_overrides() {
+    let table = StringTable::from_builder()
+        .string(2u16, "Open")
+        .string(1u16, "Save")
+        .lang_specific_string(lang::LANG_DEU, 2u16, "Öffnen")
+        .build();
+    let script = write_for(&[lang::LANG_ENU, lang::LANG_DEU], table).unwrap();
+    let german = "LANGUAGE 0x7, 0x1\nSTRINGTABLE \n{\n\t1, \"Save\"\n\t2, L\"\\x00d6ffnen\"\n}\n";
+    let english = "LANGUAGE 0x9, 0x1\nSTRINGTABLE \n{\n\t1, \"Save\"\n\t2, \"Open\"\n}\n";
+    assert!(script.contains(german), "{}", script);
+    assert!(script.contains(english), "{}", script);
+}
+
+#[test]
+fn languages_without_strings_get_no_table() {
+    let table = StringTable::from_builder()
+        .lang_specific_string(lang::LANG_DEU, 1u16, "Speichern")
+        .build();
+    let script = write_for(&[lang::LANG_ENU, lang::LANG_DEU], table).unwrap();
+    assert_eq!(script.matches("STRINGTABLE").count(), 1, "{}", script);
+    assert!(script.contains("\t1, \"Speichern\"\n"), "{}", script);
+}
+
+// Compiles the generated `form_offset` on its own, the rest of the module
+// needs Windows to link, and checks the forms it picks.
+#[test]
+fn plural_rules_pick_the_cldr_forms() {
+    let dir = std::env::temp_dir().join("resw-plural-rules");
+    std::fs::create_dir_all(&dir).unwrap();
+    let module_path = dir.join("plural.rs");
+    StringTable::from_builder()
+        .plural_string(10u16, &[(One, "{} file"), (Other, "{} files")])
+        .build()
+        .generate_plural_module("Plural", &module_path)
+        .unwrap();
+    let module = std::fs::read_to_string(&module_path).unwrap();
+    let start = module.find("    pub fn form_offset").unwrap();
+    let end = module.rfind('}').unwrap();
+    // (LANGID, n, form offset), 1 for One, 3 for Few and 5 for Other
+    let cases = [
+        (0x0409, 1, 1),
+        (0x0409, 2, 5),
+        (0x0418, 0, 3),
+        (0x0418, 1, 1),
+        (0x0418, 2, 3),
+        (0x0418, 19, 3),
+        (0x0418, 20, 5),
+        (0x0418, 101, 3),
+        (0x0418, 119, 3),
+        (0x0418, 120, 5),
+    ];
+    let mut program = format!(
+        "struct Rules;\n\nimpl Rules {{\n{}}}\n\n",
+        &module[start..end]
+    );
+    program.push_str("fn main() {\n");
+    for (lang_id, n, offset) in cases.iter() {
+        program.push_str(&format!(
+            "    assert_eq!(Rules::form_offset(0x{:04x}, {}), {}, \"0x{:04x} {}\");\n",
+            lang_id, n, offset, lang_id, n
+        ));
+    }
+    program.push_str("}\n");
+    let program_path = dir.join("main.rs");
+    std::fs::write(&program_path, program).unwrap();
+    let rustc = std::env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let status = std::process::Command::new(rustc)
+        .arg("--edition=2018")
+        .arg("-o")
+        .arg(dir.join("plural-rules"))
+        .arg(&program_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let output = std::process::Command::new(dir.join("plural-rules"))
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -0,0 +1,226 @@
+use resw::resource::StringTable;
+use resw::{lang, Build};
+
+#[test]
+fn string_tables_are_checked_per_string() {
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource(
+            0u16,
+            StringTable::from_builder().string(1u16, "one").build(),
+        )
+        .resource(
+            0u16,
+            StringTable::from_builder().string(2u16, "two").build(),
+        );
+    build.validate().unwrap();
+
+    let build = build.resource(
+        0u16,
+        StringTable::from_builder().string(2u16, "deux").build(),
+    );
+    let err = build.validate().unwrap_err();
+    assert!(err.to_string().contains("STRINGTABLE string 2"), "{}", err);
+}
+
+#[test]
+fn conditional_resources_clash_only_under_the_same_condition() {
+    use resw::resource::{Conditional, Icon};
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource(
+            1u16,
+            Conditional::ifdef("_DEBUG", Icon::from_file("debug.ico")).unwrap(),
+        )
+        .resource(
+            1u16,
+            Conditional::ifndef("_DEBUG", Icon::from_file("release.ico")).unwrap(),
+        );
+    build.validate().unwrap();
+
+    let build = build.resource(
+        1u16,
+        Conditional::ifdef("_DEBUG", Icon::from_file("other.ico")).unwrap(),
+    );
+    let err = build.validate().unwrap_err();
+    assert!(
+        err.to_string().contains("ICON 1 under #ifdef _DEBUG"),
+        "{}",
+        err
+    );
+}
+
+#[test]
+fn conditions_that_would_break_the_script_are_rejected() {
+    use resw::resource::Condition;
+    assert!(Condition::defined("_DEBUG").is_ok());
+    for name in ["", "1ST", "A B", "DEBUG\n#error", "\"X\""] {
+        let err = Condition::defined(name).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput, "{:?}", name);
+        assert!(Condition::not_defined(name).is_err(), "{:?}", name);
+    }
+    assert!(Condition::expr("WINVER >= 0x0A00").is_ok());
+    for expr in ["", "  ", "1\n#error", "A ||\r\nB"] {
+        assert!(Condition::expr(expr).is_err(), "{:?}", expr);
+    }
+}
+
+#[test]
+fn unconditional_resources_clash_under_every_condition() {
+    use resw::resource::{Conditional, Icon};
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource(1u16, Icon::from_file("app.ico"))
+        .resource(
+            1u16,
+            Conditional::ifdef("_DEBUG", Icon::from_file("debug.ico")).unwrap(),
+        );
+    let err = build.validate().unwrap_err();
+    assert!(
+        err.to_string().contains("ICON 1 under #ifdef _DEBUG"),
+        "{}",
+        err
+    );
+
+    let strings = |text| StringTable::from_builder().string(1u16, text).build();
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource(
+            0u16,
+            Conditional::ifdef("_DEBUG", strings("debug")).unwrap(),
+        )
+        .resource(0u16, strings("release"));
+    let err = build.validate().unwrap_err();
+    assert!(err.to_string().contains("STRINGTABLE string 1"), "{}", err);
+}
+
+#[test]
+fn lints_only_warn_by_default() {
+    use resw::resource::Icon;
+    use resw::{Lint, Severity};
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource(1u16, Icon::from_file("a.ico"))
+        .resource(1u16, Icon::from_file("b.ico"))
+        .lint(Lint::MissingFile, Severity::Allow);
+    build.check().unwrap();
+    let build = build.lint(Lint::DuplicateId, Severity::Deny);
+    assert!(build.check().is_err());
+}
+
+#[test]
+fn style_conflicts_are_found_in_dialogs_and_controls() {
+    use resw::dialog::WindowStyle;
+    use resw::dialog::{Control, ControlStyle, ControlTemplate, DialogStyle, EditControlStyle};
+    use resw::resource::Dialog;
+    use resw::{Lint, Severity};
+    let check = |dialog: Dialog| {
+        Build::new(&[lang::LANG_ENU])
+            .resource(1u16, dialog)
+            .lint(Lint::StyleConflict, Severity::Deny)
+            .check()
+    };
+    let password_box = || {
+        Control::from_template(ControlTemplate::EDITTEXT)
+            .style(EditControlStyle::PASSWORD)
+            .style(EditControlStyle::MULTILINE)
+    };
+    // ES_PASSWORD | ES_MULTILINE
+    let password_control = || Control::custom("Edit").style(ControlStyle::custom(0x0024));
+
+    let conflicts = [
+        (
+            Dialog::from_builder()
+                .style(WindowStyle::CHILD | WindowStyle::POPUP)
+                .build(),
+            "DIALOGEX 1: dialog has both WS_CHILD and WS_POPUP",
+        ),
+        (
+            Dialog::from_builder()
+                .style(DialogStyle::CONTROL | WindowStyle::CAPTION.into())
+                .build(),
+            "dialog has both DS_CONTROL and WS_CAPTION",
+        ),
+        (
+            Dialog::from_builder()
+                .control(10u16, password_box())
+                .build(),
+            "control 10 has both ES_PASSWORD and ES_MULTILINE",
+        ),
+        (
+            Dialog::from_builder()
+                .control(11u16, password_control())
+                .build(),
+            "control 11 has both ES_PASSWORD and ES_MULTILINE",
+        ),
+    ];
+    for (dialog, expected) in conflicts {
+        let err = check(dialog).unwrap_err();
+        assert!(err.to_string().contains(expected), "{}", err);
+        assert!(err.to_string().contains("[style_conflict]"), "{}", err);
+    }
+
+    // the same bits mean something else for other classes
+    let button = Control::custom("Button").style(ControlStyle::custom(0x0024));
+    check(Dialog::from_builder().control(12u16, button).build()).unwrap();
+    check(
+        Dialog::from_builder()
+            .style(WindowStyle::POPUP | WindowStyle::CAPTION)
+            .build(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn include_files_are_checked_like_scripts() {
+    use resw::resource::Icon;
+    use resw::{Lint, Severity};
+    let dir = std::env::temp_dir().join("resw-validate");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("duplicates.rc2");
+    let _ = std::fs::remove_file(&path);
+    let err = Build::new(&[lang::LANG_ENU])
+        .resource(1u16, Icon::from_file("a.ico"))
+        .resource(1u16, Icon::from_file("b.ico"))
+        .lint(Lint::MissingFile, Severity::Allow)
+        .lint(Lint::DuplicateId, Severity::Deny)
+        .generate_rc_include_file(&path)
+        .unwrap_err();
+    assert!(err.to_string().contains("[duplicate_id]"), "{}", err);
+    assert!(!path.exists());
+}
+
+#[test]
+fn validate_denies_its_lints_whatever_their_severity() {
+    use resw::resource::Icon;
+    use resw::{Lint, Severity};
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource(1u16, Icon::from_file("a.ico"))
+        .resource(1u16, Icon::from_file("b.ico"))
+        .lint(Lint::DuplicateId, Severity::Allow);
+    let err = build.validate().unwrap_err();
+    assert!(err.to_string().contains("ICON 1"), "{}", err);
+    // the files are missing, but that isn't validate's business
+    let build = Build::new(&[lang::LANG_ENU])
+        .resource(1u16, Icon::from_file("a.ico"))
+        .lint(Lint::MissingFile, Severity::Deny);
+    build.validate().unwrap();
+}
+
+#[test]
+fn scripts_written_to_writers_are_checked_like_files() {
+    use resw::resource::{Icon, RawRc};
+    use resw::{Lint, Severity};
+    let mut out = Vec::new();
+    let err = Build::new(&[lang::LANG_ENU])
+        .resource(1u16, Icon::from_file("a.ico"))
+        .resource(1u16, Icon::from_file("b.ico"))
+        .lint(Lint::MissingFile, Severity::Allow)
+        .lint(Lint::DuplicateId, Severity::Deny)
+        .write_rc(&mut out)
+        .unwrap_err();
+    assert!(err.to_string().contains("[duplicate_id]"), "{}", err);
+    assert!(out.is_empty());
+
+    let err = Build::new(&[lang::LANG_ENU])
+        .resource(0u16, RawRc::new("STRINGTABLE\n{\n"))
+        .write_rc(&mut out)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(out.is_empty());
+}
@@ -0,0 +1,49 @@
+use resw::version_info::Version;
+
+#[test]
+fn versions_parse_up_to_four_numbers() {
+    assert_eq!(Version::parse("1.2.3.4").unwrap(), Version::new(1, 2, 3, 4));
+    assert_eq!(Version::parse("1.2").unwrap(), Version::new(1, 2, 0, 0));
+    assert_eq!(
+        Version::parse("65535.0.0.1").unwrap(),
+        Version::new(65535, 0, 0, 1)
+    );
+}
+
+#[test]
+fn malformed_versions_are_rejected() {
+    for version in [
+        "",
+        "1..2",
+        "65536",
+        "1.2.3.4.5",
+        "1.2.3-beta.1",
+        "1.-2",
+        " 1.2",
+    ] {
+        let err = Version::parse(version).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains(version), "{}", err);
+    }
+}
+
+// The only test touching CARGO_PKG_VERSION, so none sees it changed under them.
+#[test]
+fn package_versions_drop_pre_release_and_build_metadata() {
+    for (package_version, version) in [
+        ("1.2.3", Version::new(1, 2, 3, 0)),
+        ("1.2.3-beta.1", Version::new(1, 2, 3, 0)),
+        ("0.4.0+20240101", Version::new(0, 4, 0, 0)),
+    ] {
+        std::env::set_var("CARGO_PKG_VERSION", package_version);
+        assert_eq!(Version::from_cargo_pkg_version().unwrap(), version);
+    }
+
+    std::env::set_var("CARGO_PKG_VERSION", "1.x");
+    let err = Version::from_cargo_pkg_version().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+    std::env::remove_var("CARGO_PKG_VERSION");
+    let err = Version::from_cargo_pkg_version().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+}